@@ -0,0 +1,539 @@
+use super::FoamField;
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
+// Arithmetic on fields, following the conventions of OpenFOAM:
+// values have 1 (scalar), 3 (vector), 6 (symmTensor: XX XY XZ YY YZ ZZ) or 9 components
+// (tensor: XX XY XZ YX YY YZ ZX ZY ZZ). Uniform fields are broadcast to the size of nonuniform
+// fields. Like OpenFOAM, which aborts on such errors, the operations panic if the sizes or the
+// types of the values do not match.
+
+/// Index in a symmetric tensor of each component of a full tensor.
+const SYMM_INDEX: [usize; 9] = [0, 1, 2, 1, 3, 4, 2, 4, 5];
+
+/// Index in a full tensor of each component of a symmetric tensor.
+const FULL_INDEX: [usize; 6] = [0, 1, 2, 4, 5, 8];
+
+/// The values of an operation result, before they are stored in a field.
+enum Values<T> {
+    Uniform(T),
+    Nonuniform(Vec<T>),
+}
+
+impl From<Values<f64>> for FoamField {
+    fn from(values: Values<f64>) -> Self {
+        match values {
+            Values::Uniform(value) => FoamField::UniformScalar(value),
+            Values::Nonuniform(values) => FoamField::Scalar(values),
+        }
+    }
+}
+
+impl From<Values<Vec<f64>>> for FoamField {
+    /// Single component values are stored as scalars.
+    fn from(values: Values<Vec<f64>>) -> Self {
+        match values {
+            Values::Uniform(value) if value.len() == 1 => FoamField::UniformScalar(value[0]),
+            Values::Uniform(value) => FoamField::UniformVector(value),
+            Values::Nonuniform(values) if values.first().is_some_and(|v| v.len() == 1) => {
+                FoamField::Scalar(values.into_iter().map(|v| v[0]).collect())
+            }
+            Values::Nonuniform(values) => FoamField::Vector(values),
+        }
+    }
+}
+
+impl FoamField {
+    /// Creates a nonuniform field from the components of its values.
+    /// Single component values are stored as scalars.
+    pub(crate) fn from_values(values: Vec<Vec<f64>>) -> FoamField {
+        Values::Nonuniform(values).into()
+    }
+
+    /// Returns true for uniform fields.
+    pub fn is_uniform(&self) -> bool {
+        self.n_values().is_none()
+    }
+
+    /// Returns the number of components of the values: 1 for scalars, 3 for vectors,
+    /// 6 for symmetric tensors and 9 for tensors.
+    /// Empty nonuniform vector fields are assumed to hold vectors.
+    pub fn n_components(&self) -> usize {
+        match self {
+            FoamField::UniformScalar(_) | FoamField::Scalar(_) => 1,
+            FoamField::UniformVector(value) => value.len(),
+            FoamField::Vector(values) => values.first().map_or(3, Vec::len),
+        }
+    }
+
+    /// Returns the components of the value at the given index.
+    /// Uniform fields have the same value at every index.
+    pub(crate) fn value(&self, i: usize) -> &[f64] {
+        match self {
+            FoamField::UniformScalar(value) => std::slice::from_ref(value),
+            FoamField::UniformVector(value) => value,
+            FoamField::Scalar(values) => std::slice::from_ref(&values[i]),
+            FoamField::Vector(values) => &values[i],
+        }
+    }
+
+    /// Applies a function to every value of the field.
+    fn apply<T>(&self, f: impl Fn(&[f64]) -> T) -> Values<T> {
+        match self.n_values() {
+            None => Values::Uniform(f(self.value(0))),
+            Some(n) => Values::Nonuniform((0..n).map(|i| f(self.value(i))).collect()),
+        }
+    }
+
+    /// Applies a function to the values of two fields, broadcasting uniform fields.
+    fn combine<T>(&self, other: &FoamField, f: impl Fn(&[f64], &[f64]) -> T) -> Values<T> {
+        let n = match (self.n_values(), other.n_values()) {
+            (None, None) => return Values::Uniform(f(self.value(0), other.value(0))),
+            (Some(n), None) | (None, Some(n)) => n,
+            (Some(a), Some(b)) => {
+                assert_eq!(a, b, "Fields of different sizes cannot be combined.");
+                a
+            }
+        };
+        Values::Nonuniform((0..n).map(|i| f(self.value(i), other.value(i))).collect())
+    }
+
+    /// Combines the values of two fields of the same type component by component.
+    fn combine_components(&self, other: &FoamField, f: impl Fn(f64, f64) -> f64) -> FoamField {
+        assert_eq!(
+            self.n_components(),
+            other.n_components(),
+            "Fields of different types cannot be combined."
+        );
+        if self.n_components() == 1 {
+            self.combine(other, |a, b| f(a[0], b[0])).into()
+        } else {
+            self.combine(other, |a, b| {
+                a.iter()
+                    .zip(b)
+                    .map(|(&x, &y)| f(x, y))
+                    .collect::<Vec<f64>>()
+            })
+            .into()
+        }
+    }
+
+    /// Returns the magnitude of the values.
+    pub fn mag(&self) -> FoamField {
+        self.mag_sqr().apply(|value| value[0].sqrt()).into()
+    }
+
+    /// Returns the squared magnitude of the values. Like in OpenFOAM, it is the sum of the squares
+    /// of all components of the tensor, so that the off-diagonal components of symmetric tensors
+    /// count twice.
+    pub fn mag_sqr(&self) -> FoamField {
+        let symmetric = self.n_components() == 6;
+        self.apply(|value| {
+            if symmetric {
+                FULL_INDEX
+                    .iter()
+                    .enumerate()
+                    .map(|(i, full)| {
+                        let weight = if full % 4 == 0 { 1.0 } else { 2.0 };
+                        weight * value[i] * value[i]
+                    })
+                    .sum()
+            } else {
+                value.iter().map(|c| c * c).sum::<f64>()
+            }
+        })
+        .into()
+    }
+
+    /// Returns the scalar field of a single component, e.g. 0 for the x component of vectors.
+    /// # Panics
+    /// If the values do not have this component.
+    pub fn component(&self, component: usize) -> FoamField {
+        assert!(
+            component < self.n_components(),
+            "Component {} does not exist in a field with {} components.",
+            component,
+            self.n_components()
+        );
+        self.apply(|value| value[component]).into()
+    }
+
+    /// Returns the inner product of the values, like the "&" operator of OpenFOAM:
+    /// vector & vector gives a scalar, tensor & vector and vector & tensor give a vector,
+    /// and tensor & tensor gives a tensor.
+    pub fn dot(&self, other: &FoamField) -> FoamField {
+        let (rank_a, rank_b) = (rank(self.n_components()), rank(other.n_components()));
+        match (rank_a, rank_b) {
+            (1, 1) => self
+                .combine(other, |a, b| {
+                    a.iter().zip(b).map(|(x, y)| x * y).sum::<f64>()
+                })
+                .into(),
+            (1, 2) => self
+                .combine(other, |v, t| {
+                    (0..3)
+                        .map(|j| (0..3).map(|i| v[i] * tensor(t, i, j)).sum())
+                        .collect::<Vec<f64>>()
+                })
+                .into(),
+            (2, 1) => self
+                .combine(other, |t, v| {
+                    (0..3)
+                        .map(|i| (0..3).map(|j| tensor(t, i, j) * v[j]).sum())
+                        .collect::<Vec<f64>>()
+                })
+                .into(),
+            (2, 2) => self
+                .combine(other, |a, b| {
+                    (0..9)
+                        .map(|ik| {
+                            let (i, k) = (ik / 3, ik % 3);
+                            (0..3).map(|j| tensor(a, i, j) * tensor(b, j, k)).sum()
+                        })
+                        .collect::<Vec<f64>>()
+                })
+                .into(),
+            _ => panic!("The inner product is only defined for vectors and tensors."),
+        }
+    }
+
+    /// Returns the cross product of two vector fields.
+    pub fn cross(&self, other: &FoamField) -> FoamField {
+        assert!(
+            self.n_components() == 3 && other.n_components() == 3,
+            "The cross product is only defined for vectors."
+        );
+        self.combine(other, |a, b| {
+            vec![
+                a[1] * b[2] - a[2] * b[1],
+                a[2] * b[0] - a[0] * b[2],
+                a[0] * b[1] - a[1] * b[0],
+            ]
+        })
+        .into()
+    }
+
+    /// Returns the trace of a tensor field.
+    pub fn trace(&self) -> FoamField {
+        self.check_tensor("trace");
+        self.apply(|t| (0..3).map(|i| tensor(t, i, i)).sum::<f64>())
+            .into()
+    }
+
+    /// Returns the symmetric part of a tensor field, as a symmetric tensor field.
+    pub fn symm(&self) -> FoamField {
+        self.check_tensor("symmetric part");
+        self.apply(|t| {
+            FULL_INDEX
+                .iter()
+                .map(|full| {
+                    let (i, j) = (full / 3, full % 3);
+                    0.5 * (tensor(t, i, j) + tensor(t, j, i))
+                })
+                .collect::<Vec<f64>>()
+        })
+        .into()
+    }
+
+    /// Returns the deviatoric part of a tensor field, i.e. the tensor minus a third of its trace
+    /// on the diagonal. The type of tensor is kept.
+    pub fn dev(&self) -> FoamField {
+        self.check_tensor("deviatoric part");
+        let diagonal: &[usize] = if self.n_components() == 6 {
+            &[0, 3, 5]
+        } else {
+            &[0, 4, 8]
+        };
+        self.apply(|t| {
+            let third_trace = diagonal.iter().map(|&i| t[i]).sum::<f64>() / 3.0;
+            let mut dev = t.to_vec();
+            for &i in diagonal {
+                dev[i] -= third_trace;
+            }
+            dev
+        })
+        .into()
+    }
+
+    /// Returns the minimum of the values, component by component, as a uniform field.
+    /// Returns None for empty fields.
+    pub fn min(&self) -> Option<FoamField> {
+        self.reduce(f64::min)
+    }
+
+    /// Returns the maximum of the values, component by component, as a uniform field.
+    /// Returns None for empty fields.
+    pub fn max(&self) -> Option<FoamField> {
+        self.reduce(f64::max)
+    }
+
+    /// Returns the sum of the values as a uniform field. Uniform fields are returned unchanged.
+    pub fn sum(&self) -> FoamField {
+        self.reduce(|a, b| a + b)
+            .unwrap_or_else(|| match self.n_components() {
+                1 => FoamField::UniformScalar(0.0),
+                n => FoamField::UniformVector(vec![0.0; n]),
+            })
+    }
+
+    /// Returns the arithmetic mean of the values as a uniform field.
+    /// Returns None for empty fields.
+    pub fn average(&self) -> Option<FoamField> {
+        match self.n_values() {
+            None => Some(self.clone()),
+            Some(0) => None,
+            Some(n) => Some(self.sum() / n as f64),
+        }
+    }
+
+    /// Reduces the values component by component.
+    fn reduce(&self, f: impl Fn(f64, f64) -> f64) -> Option<FoamField> {
+        let n = match self.n_values() {
+            None => return Some(self.clone()),
+            Some(0) => return None,
+            Some(n) => n,
+        };
+        let mut result = self.value(0).to_vec();
+        for i in 1..n {
+            for (r, &c) in result.iter_mut().zip(self.value(i)) {
+                *r = f(*r, c);
+            }
+        }
+        Some(Values::Uniform(result).into())
+    }
+
+    fn check_tensor(&self, operation: &str) {
+        assert!(
+            rank(self.n_components()) == 2,
+            "The {} is only defined for tensors.",
+            operation
+        );
+    }
+}
+
+/// Returns the rank of values with the given number of components, e.g. 2 for tensors.
+fn rank(n_components: usize) -> usize {
+    match n_components {
+        1 => 0,
+        3 => 1,
+        6 | 9 => 2,
+        _ => usize::MAX,
+    }
+}
+
+/// Returns the component (i, j) of a tensor or a symmetric tensor.
+fn tensor(t: &[f64], i: usize, j: usize) -> f64 {
+    if t.len() == 6 {
+        t[SYMM_INDEX[3 * i + j]]
+    } else {
+        t[3 * i + j]
+    }
+}
+
+impl Add for &FoamField {
+    type Output = FoamField;
+
+    fn add(self, other: &FoamField) -> FoamField {
+        self.combine_components(other, |a, b| a + b)
+    }
+}
+
+impl Sub for &FoamField {
+    type Output = FoamField;
+
+    fn sub(self, other: &FoamField) -> FoamField {
+        self.combine_components(other, |a, b| a - b)
+    }
+}
+
+/// Scales the values if one of the fields is a scalar field. The product of two vector fields is
+/// their outer product, a tensor field, like in OpenFOAM. Use `dot` for the inner product.
+impl Mul for &FoamField {
+    type Output = FoamField;
+
+    fn mul(self, other: &FoamField) -> FoamField {
+        match (self.n_components(), other.n_components()) {
+            (1, 1) => self.combine(other, |a, b| a[0] * b[0]).into(),
+            (1, _) => self
+                .combine(other, |s, v| {
+                    v.iter().map(|c| s[0] * c).collect::<Vec<f64>>()
+                })
+                .into(),
+            (_, 1) => self
+                .combine(other, |v, s| {
+                    v.iter().map(|c| c * s[0]).collect::<Vec<f64>>()
+                })
+                .into(),
+            (3, 3) => self
+                .combine(other, |a, b| {
+                    (0..9).map(|ij| a[ij / 3] * b[ij % 3]).collect::<Vec<f64>>()
+                })
+                .into(),
+            _ => panic!("The product is only defined with scalars and between vectors."),
+        }
+    }
+}
+
+/// Divides the values by the values of a scalar field.
+impl Div for &FoamField {
+    type Output = FoamField;
+
+    fn div(self, other: &FoamField) -> FoamField {
+        assert_eq!(
+            other.n_components(),
+            1,
+            "Fields can only be divided by scalars."
+        );
+        if self.n_components() == 1 {
+            self.combine(other, |a, b| a[0] / b[0]).into()
+        } else {
+            self.combine(other, |v, s| {
+                v.iter().map(|c| c / s[0]).collect::<Vec<f64>>()
+            })
+            .into()
+        }
+    }
+}
+
+impl Neg for &FoamField {
+    type Output = FoamField;
+
+    fn neg(self) -> FoamField {
+        self * -1.0
+    }
+}
+
+impl Neg for FoamField {
+    type Output = FoamField;
+
+    fn neg(self) -> FoamField {
+        -&self
+    }
+}
+
+/// Implements an operator for owned fields and for scalar values, based on the implementation
+/// between references to fields.
+macro_rules! forward_operator {
+    ($trait:ident, $method:ident) => {
+        impl $trait for FoamField {
+            type Output = FoamField;
+
+            fn $method(self, other: FoamField) -> FoamField {
+                (&self).$method(&other)
+            }
+        }
+
+        impl $trait<&FoamField> for FoamField {
+            type Output = FoamField;
+
+            fn $method(self, other: &FoamField) -> FoamField {
+                (&self).$method(other)
+            }
+        }
+
+        impl $trait<FoamField> for &FoamField {
+            type Output = FoamField;
+
+            fn $method(self, other: FoamField) -> FoamField {
+                self.$method(&other)
+            }
+        }
+
+        impl $trait<f64> for &FoamField {
+            type Output = FoamField;
+
+            fn $method(self, other: f64) -> FoamField {
+                self.$method(&FoamField::UniformScalar(other))
+            }
+        }
+
+        impl $trait<f64> for FoamField {
+            type Output = FoamField;
+
+            fn $method(self, other: f64) -> FoamField {
+                (&self).$method(&FoamField::UniformScalar(other))
+            }
+        }
+
+        impl $trait<&FoamField> for f64 {
+            type Output = FoamField;
+
+            fn $method(self, other: &FoamField) -> FoamField {
+                (&FoamField::UniformScalar(self)).$method(other)
+            }
+        }
+
+        impl $trait<FoamField> for f64 {
+            type Output = FoamField;
+
+            fn $method(self, other: FoamField) -> FoamField {
+                (&FoamField::UniformScalar(self)).$method(&other)
+            }
+        }
+    };
+}
+
+forward_operator!(Add, add);
+forward_operator!(Sub, sub);
+forward_operator!(Mul, mul);
+forward_operator!(Div, div);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_field_arithmetic() {
+        let p = FoamField::Scalar(vec![1.0, 2.0, 3.0]);
+        assert_eq!(&p - 1.0, FoamField::Scalar(vec![0.0, 1.0, 2.0]));
+        assert_eq!(2.0 * &p, FoamField::Scalar(vec![2.0, 4.0, 6.0]));
+        assert_eq!(-&p / &p, FoamField::Scalar(vec![-1.0; 3]));
+
+        let u = FoamField::Vector(vec![vec![3.0, 4.0, 0.0], vec![0.0, 0.0, 1.0]]);
+        let offset = FoamField::UniformVector(vec![1.0, 0.0, 0.0]);
+        assert_eq!(
+            &u + &offset,
+            FoamField::Vector(vec![vec![4.0, 4.0, 0.0], vec![1.0, 0.0, 1.0]])
+        );
+        assert_eq!(u.mag(), FoamField::Scalar(vec![5.0, 1.0]));
+        assert_eq!(u.component(1), FoamField::Scalar(vec![4.0, 0.0]));
+        assert_eq!(u.dot(&offset), FoamField::Scalar(vec![3.0, 0.0]));
+        assert_eq!(
+            offset.cross(&u),
+            FoamField::Vector(vec![vec![0.0, 0.0, 4.0], vec![0.0, -1.0, 0.0]])
+        );
+        assert_eq!(u.max(), Some(FoamField::UniformVector(vec![3.0, 4.0, 1.0])));
+        assert_eq!(
+            u.average(),
+            Some(FoamField::UniformVector(vec![1.5, 2.0, 0.5]))
+        );
+        assert_eq!(FoamField::Scalar(vec![]).min(), None);
+    }
+
+    #[test]
+    fn test_tensor_algebra() {
+        let a = FoamField::UniformVector(vec![1.0, 2.0, 0.0]);
+        let b = FoamField::UniformVector(vec![0.0, 1.0, 3.0]);
+        let t = &a * &b;
+        assert_eq!(
+            t,
+            FoamField::UniformVector(vec![0.0, 1.0, 3.0, 0.0, 2.0, 6.0, 0.0, 0.0, 0.0])
+        );
+        assert_eq!(t.trace(), FoamField::UniformScalar(2.0));
+        assert_eq!(t.dot(&b), &a * 10.0);
+        let symm = t.symm();
+        assert_eq!(
+            symm,
+            FoamField::UniformVector(vec![0.0, 0.5, 1.5, 2.0, 3.0, 0.0])
+        );
+        assert_eq!(symm.dot(&symm).trace(), symm.mag_sqr());
+        let dev = symm.dev();
+        assert!(matches!(dev.trace(), FoamField::UniformScalar(tr) if tr.abs() < 1e-15));
+        assert_eq!(dev.n_components(), 6);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_size_mismatch() {
+        let _ = FoamField::Scalar(vec![1.0, 2.0]) + FoamField::Scalar(vec![1.0]);
+    }
+}
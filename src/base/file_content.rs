@@ -2,15 +2,23 @@ use super::parser_base::discard_garbage;
 use super::FileElement;
 use super::FileParser;
 use super::FoamFile;
+use super::FoamValue;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
 use nom::IResult;
 
 use std::io::prelude::*;
 
+/// The line that separates the header from the data, surrounded by empty lines.
+pub(crate) const HEADER_SEPARATOR: &str =
+    "\n// * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * //\n";
+
 /// The FileContent structure holds the full content of a file.
 /// The file content is divided into two parts:
 /// - The meta data, which is written in the header and is a FoamStructure.
 /// - The data, which is the actual content of the file. The data has a custom
 ///   structure depending on the file type.
+///
 /// The location is the path to the file relative to the case directory.
 #[derive(Debug, PartialEq, Clone)]
 pub struct FileContent<T: FileParser> {
@@ -40,22 +48,31 @@ impl<T: FileParser> FileElement for FileContent<T> {
 impl<T: FileParser> std::fmt::Display for FileContent<T> {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         write!(f, "{}", self.meta)?;
-        writeln!(
-            f,
-            "\n// * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * //\n"
-        )?;
+        writeln!(f, "{}", HEADER_SEPARATOR)?;
         writeln!(f, "{}", self.data)
     }
 }
 
 impl<T: FileParser> FileContent<T> {
+    /// Creates a new FileContent with the same header and location, but different data.
+    pub fn with_data(&self, data: T) -> Self {
+        FileContent {
+            location: self.location.clone(),
+            meta: self.meta.clone(),
+            data,
+        }
+    }
+
     /// Create a new FileContent structure by parsing a file.
+    /// If the file does not exist but a gzip-compressed version with the ".gz" extension does,
+    /// the compressed file is read instead. The location never contains the ".gz" extension.
     pub fn parse_file(path: &std::path::Path) -> std::io::Result<Self> {
+        let path = &find_file(path);
         // load file
-        let input = std::fs::read_to_string(path)?;
+        let input = read_to_string(path)?;
         // find the path starting from the case directory
         let location = match find_case_directory(path) {
-            Some(dir) => path.strip_prefix(dir).unwrap().to_path_buf().into(),
+            Some(dir) => strip_gz(path.strip_prefix(dir).unwrap()).into(),
             None => None,
         };
         match Self::parse(&input) {
@@ -76,8 +93,30 @@ impl<T: FileParser> FileContent<T> {
         }
     }
 
+    /// Sets the path of the file relative to the case directory.
+    /// The "location" entry of the header is updated accordingly, if present.
+    pub fn set_location(&mut self, relative_path: &std::path::Path) {
+        if let Some(dir) = relative_path.parent() {
+            if let Some(location) = self.meta.get_mut("location") {
+                *location = FoamValue::String(format!("\"{}\"", dir.display()));
+            }
+        }
+        self.location = Some(relative_path.to_path_buf());
+    }
+
     /// Write the file to the given case directory.
     pub fn write_file(&self, path: &std::path::Path) -> std::io::Result<()> {
+        self.write_file_compressed(path, Compression::Uncompressed)
+    }
+
+    /// Write the file to the given case directory with the given compression.
+    /// Compressed files get the ".gz" extension. As in OpenFOAM, an existing version of the file
+    /// with the other compression is removed, so that it cannot be read by mistake.
+    pub fn write_file_compressed(
+        &self,
+        path: &std::path::Path,
+        compression: Compression,
+    ) -> std::io::Result<()> {
         let relative_path = match self.location {
             Some(ref p) => p.to_owned(),
             None => match self.meta.relative_file_path() {
@@ -85,13 +124,119 @@ impl<T: FileParser> FileContent<T> {
                 None => T::default_file_path(),
             },
         };
-        let full_path = path.join(relative_path);
-        if let Some(p) = full_path.parent() {
+        let plain_path = path.join(relative_path);
+        let gz_path = with_gz(&plain_path);
+        if let Some(p) = plain_path.parent() {
             std::fs::create_dir_all(p)?;
         }
-        let mut file = std::fs::File::create(full_path)?;
-        write!(&mut file, "{}", self)
+        match compression {
+            Compression::Uncompressed => {
+                if gz_path.is_file() {
+                    std::fs::remove_file(&gz_path)?;
+                }
+                let mut file = std::io::BufWriter::new(std::fs::File::create(plain_path)?);
+                write!(&mut file, "{}", self)?;
+                file.flush()
+            }
+            Compression::Gzip => {
+                if plain_path.is_file() {
+                    std::fs::remove_file(&plain_path)?;
+                }
+                let file = std::io::BufWriter::new(std::fs::File::create(gz_path)?);
+                let mut encoder = GzEncoder::new(file, flate2::Compression::default());
+                write!(&mut encoder, "{}", self)?;
+                encoder.finish()?.flush()
+            }
+        }
+    }
+}
+
+/// The compression of written files, corresponding to the "writeCompression" setting of OpenFOAM.
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub enum Compression {
+    #[default]
+    Uncompressed,
+    /// Files are compressed with gzip and get the ".gz" extension.
+    Gzip,
+}
+
+/// Returns the path to read for the given file: the file itself if it exists, otherwise its
+/// gzip-compressed version if that exists. This is the order in which OpenFOAM looks for files.
+pub(crate) fn find_file(path: &std::path::Path) -> std::path::PathBuf {
+    let gz_path = with_gz(path);
+    if !path.is_file() && gz_path.is_file() {
+        gz_path
+    } else {
+        path.to_path_buf()
+    }
+}
+
+/// Opens a file for buffered reading, see `find_file`. Compressed files are decompressed on the fly.
+pub(crate) fn open_file(path: &std::path::Path) -> std::io::Result<Box<dyn BufRead>> {
+    let path = find_file(path);
+    let file = std::fs::File::open(&path)?;
+    if is_gz(&path) {
+        Ok(Box::new(std::io::BufReader::new(GzDecoder::new(file))))
+    } else {
+        Ok(Box::new(std::io::BufReader::new(file)))
+    }
+}
+
+/// Lists the data files of a directory as (name, path) pairs, with the ".gz" extension removed
+/// from the names. If both a file and its compressed version exist, only the file is listed.
+/// Subdirectories are skipped.
+pub(crate) fn data_files(
+    dir: &std::path::Path,
+) -> std::io::Result<Vec<(String, std::path::PathBuf)>> {
+    let mut files = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            continue;
+        }
+        let name = strip_gz(&path)
+            .file_name()
+            .expect("Unable to extract file name.")
+            .to_str()
+            .expect("File name is not valid unicode.")
+            .to_string();
+        if is_gz(&path) && dir.join(&name).is_file() {
+            continue;
+        }
+        files.push((name, path));
     }
+    Ok(files)
+}
+
+/// Reads a file to a string, decompressing it if it has the ".gz" extension.
+fn read_to_string(path: &std::path::Path) -> std::io::Result<String> {
+    if is_gz(path) {
+        let mut input = String::new();
+        GzDecoder::new(std::fs::File::open(path)?).read_to_string(&mut input)?;
+        Ok(input)
+    } else {
+        std::fs::read_to_string(path)
+    }
+}
+
+fn is_gz(path: &std::path::Path) -> bool {
+    path.extension().is_some_and(|extension| extension == "gz")
+}
+
+/// Removes the ".gz" extension from a path, if present.
+fn strip_gz(path: &std::path::Path) -> std::path::PathBuf {
+    if is_gz(path) {
+        path.with_extension("")
+    } else {
+        path.to_path_buf()
+    }
+}
+
+/// Appends the ".gz" extension to a path.
+fn with_gz(path: &std::path::Path) -> std::path::PathBuf {
+    let mut gz_path = path.as_os_str().to_owned();
+    gz_path.push(".gz");
+    gz_path.into()
 }
 
 /// Checks if the given path is a directory that contains "constant" as a subdirectory.
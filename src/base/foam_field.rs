@@ -22,6 +22,72 @@ pub enum FoamField {
     Vector(Vec<Vec<f64>>),
 }
 
+impl FoamField {
+    /// Returns the number of values of a nonuniform field, or `None` for uniform fields.
+    pub fn n_values(&self) -> Option<usize> {
+        match self {
+            FoamField::UniformScalar(_) | FoamField::UniformVector(_) => None,
+            FoamField::Scalar(values) => Some(values.len()),
+            FoamField::Vector(values) => Some(values.len()),
+        }
+    }
+
+    /// Returns the OpenFOAM name of the type of the values, deduced from the number of components:
+    /// "scalar", "vector", "symmTensor" or "tensor".
+    pub fn value_type(&self) -> &'static str {
+        match self.n_components() {
+            1 => "scalar",
+            6 => "symmTensor",
+            9 => "tensor",
+            _ => "vector",
+        }
+    }
+
+    /// Returns a new field with the values found at the given indices.
+    /// Uniform fields do not depend on the indices and are returned unchanged.
+    pub fn subset(&self, indices: &[usize]) -> FoamField {
+        match self {
+            FoamField::Scalar(values) => {
+                FoamField::Scalar(indices.iter().map(|&i| values[i]).collect())
+            }
+            FoamField::Vector(values) => {
+                FoamField::Vector(indices.iter().map(|&i| values[i].clone()).collect())
+            }
+            uniform => uniform.clone(),
+        }
+    }
+
+    /// Returns a new field in which the sign of the values is flipped where `flip` is true.
+    /// This is needed for face fields (e.g. fluxes) when the orientation of faces is reversed.
+    /// A uniform field is expanded to a nonuniform field of length `flip.len()` if required.
+    pub fn flip_sign(&self, flip: &[bool]) -> FoamField {
+        if !flip.iter().any(|&f| f) {
+            return self.clone();
+        }
+        let sign = |f: bool| if f { -1.0 } else { 1.0 };
+        match self {
+            FoamField::UniformScalar(value) => {
+                FoamField::Scalar(flip.iter().map(|&f| sign(f) * value).collect())
+            }
+            FoamField::UniformVector(value) => FoamField::Vector(
+                flip.iter()
+                    .map(|&f| value.iter().map(|v| sign(f) * v).collect())
+                    .collect(),
+            ),
+            FoamField::Scalar(values) => {
+                FoamField::Scalar(values.iter().zip(flip).map(|(v, &f)| sign(f) * v).collect())
+            }
+            FoamField::Vector(values) => FoamField::Vector(
+                values
+                    .iter()
+                    .zip(flip)
+                    .map(|(v, &f)| v.iter().map(|c| sign(f) * c).collect())
+                    .collect(),
+            ),
+        }
+    }
+}
+
 impl FileElement for FoamField {
     /// Parse a FoamField from the given input.
     fn parse(input: &str) -> IResult<&str, FoamField> {
@@ -53,7 +119,7 @@ impl std::fmt::Display for FoamField {
                 writeln!(f, ";")?
             }
             FoamField::Vector(ref values) => {
-                writeln!(f, "nonuniform List<vector>")?;
+                writeln!(f, "nonuniform List<{}>", self.value_type())?;
                 write_fixed_witdh_data(values, f)?;
                 writeln!(f, ";")?
             }
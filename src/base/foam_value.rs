@@ -63,8 +63,8 @@ impl FileElement for FoamValue {
         {
             return Ok((input, FoamValue::Float(value)));
         }
-        // Check if it is a field.
-        if let Ok((input, value)) = terminated(FoamField::parse, semicolon)(input) {
+        // Check if it is a field. The field parser consumes the closing semicolon itself.
+        if let Ok((input, value)) = FoamField::parse(input) {
             return Ok((input, FoamValue::Field(value)));
         }
         // Check if it is a list.
@@ -89,7 +89,8 @@ impl std::fmt::Display for FoamValue {
             FoamValue::Integer(value) => write!(f, "{};", value)?,
             FoamValue::Float(value) => write!(f, "{};", value)?,
             FoamValue::Field(value) => {
-                write!(f, "{};", value)?;
+                // The field already ends with a semicolon and a line break.
+                write!(f, "{}", value.to_string().trim_end())?;
             }
             FoamValue::List(values) => {
                 write!(f, "List<word> ")?;
@@ -113,3 +114,32 @@ fn parse_list_content(input: &str) -> IResult<&str, Vec<String>> {
     let (input, n) = lws(usize_val)(input)?;
     block_parentheses(count(lws(string_val), n))(input)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base::foam_structure::FoamStructure;
+
+    #[test]
+    fn test_field_round_trip() {
+        let input = "
+        right
+        {
+            type            calculated;
+            value           nonuniform List<scalar> 2(1 2);
+        }";
+        let (_, structure) = FoamStructure::parse(input).expect("Failed to parse structure.");
+        let right = match &structure.0["right"] {
+            FoamValue::Structure(right) => right,
+            other => panic!("Expected a structure, found {:?}", other),
+        };
+        assert_eq!(
+            right.0["value"],
+            FoamValue::Field(FoamField::Scalar(vec![1.0, 2.0]))
+        );
+        let written = structure.to_string();
+        let (_, reparsed) =
+            FoamStructure::parse(&written).expect("Failed to parse written structure.");
+        assert_eq!(reparsed, structure);
+    }
+}
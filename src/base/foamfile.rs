@@ -8,11 +8,35 @@ use super::{parser_base::next, FileElement, FoamStructure, FoamValue};
 pub struct FoamFile(pub FoamStructure);
 
 impl FoamFile {
+    /// Creates the header of a new ascii file with the given class, location and object name.
+    pub fn new(class: &str, location: &str, object: &str) -> Self {
+        let mut meta = FoamStructure(indexmap::IndexMap::new());
+        meta.insert("format".to_string(), FoamValue::String("ascii".to_string()));
+        meta.insert("class".to_string(), FoamValue::String(class.to_string()));
+        meta.insert(
+            "location".to_string(),
+            FoamValue::String(format!("\"{}\"", location)),
+        );
+        meta.insert("object".to_string(), FoamValue::String(object.to_string()));
+        FoamFile(meta)
+    }
+
+    /// Returns the class of the file, e.g. "volScalarField" or "faceSet", if it is present.
+    pub fn class(&self) -> Option<&str> {
+        match self.get("class") {
+            Some(FoamValue::String(class)) => Some(class.trim_matches('"')),
+            _ => None,
+        }
+    }
+
     /// Tries to assemble a relative file path from the location and object fields if they are present.
     pub fn relative_file_path(&self) -> Option<std::path::PathBuf> {
         if let Some(FoamValue::String(location)) = self.get("location") {
             if let Some(FoamValue::String(object)) = self.get("object") {
-                return Some(std::path::PathBuf::from(&location).join(object));
+                return Some(
+                    std::path::PathBuf::from(location.trim_matches('"'))
+                        .join(object.trim_matches('"')),
+                );
             }
         }
         None
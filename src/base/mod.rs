@@ -1,3 +1,4 @@
+mod field_algebra;
 /// The base module contains the basic building blocks for parsing and writing OpenFOAM files.
 mod file_content;
 mod file_parser;
@@ -5,17 +6,58 @@ mod foam_field;
 mod foam_structure;
 mod foam_value;
 mod foamfile;
+pub(crate) mod parallel;
 pub(crate) mod parser_base;
+mod stream;
+pub(crate) mod vector;
 pub(crate) mod writer_base;
 
 // Re-export the data structures.
+pub use file_content::Compression;
 pub use file_content::FileContent;
+pub(crate) use file_content::{data_files, find_file};
 pub use file_parser::FileElement;
 pub use file_parser::FileParser;
 pub use foam_field::FoamField;
 pub use foam_structure::FoamStructure;
 pub use foam_value::FoamValue;
 pub use foamfile::FoamFile;
+pub(crate) use stream::read_file_header;
+pub use stream::CompactListReader;
+pub use stream::ListItem;
+pub use stream::ListReader;
+pub use stream::ListWriter;
+
+/// A directory in the temporary directory of the system, which is removed when it is dropped.
+/// The process id is part of the name, so that concurrent test runs do not interfere.
+#[cfg(test)]
+pub(crate) struct TempDir(std::path::PathBuf);
+
+#[cfg(test)]
+impl TempDir {
+    pub(crate) fn new(name: &str) -> Self {
+        let path =
+            std::env::temp_dir().join(format!("polymesh_rw_{}_{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+        TempDir(path)
+    }
+}
+
+#[cfg(test)]
+impl std::ops::Deref for TempDir {
+    type Target = std::path::Path;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
 
 #[cfg(test)]
 mod tests {
@@ -0,0 +1,180 @@
+use super::parser_base::next;
+use nom::{multi::fold_many_m_n, IResult};
+#[cfg(feature = "rayon")]
+use {
+    super::parser_base::discard_garbage,
+    nom::{multi::fold_many0, sequence::terminated},
+    rayon::prelude::*,
+};
+
+/// Lists with at least this many entries are split into chunks that are parsed in parallel.
+#[cfg(feature = "rayon")]
+const PARALLEL_LIST_LENGTH: usize = 10_000;
+
+/// Runs two closures and returns both results.
+/// The closures run in parallel if the "rayon" feature is enabled.
+pub(crate) fn join<A, B, RA, RB>(a: A, b: B) -> (RA, RB)
+where
+    A: FnOnce() -> RA + Send,
+    B: FnOnce() -> RB + Send,
+    RA: Send,
+    RB: Send,
+{
+    #[cfg(feature = "rayon")]
+    return rayon::join(a, b);
+    #[cfg(not(feature = "rayon"))]
+    return (a(), b());
+}
+
+/// Applies a function to all items of a slice.
+/// The items are processed in parallel if the "rayon" feature is enabled.
+pub(crate) fn map<T, U, F>(items: &[T], f: F) -> Vec<U>
+where
+    T: Sync,
+    U: Send,
+    F: Fn(&T) -> U + Sync + Send,
+{
+    #[cfg(feature = "rayon")]
+    return items.par_iter().map(f).collect();
+    #[cfg(not(feature = "rayon"))]
+    return items.iter().map(f).collect();
+}
+
+/// A collection that the entries of a list are parsed into.
+#[cfg_attr(not(feature = "rayon"), allow(dead_code))]
+pub(crate) trait ListCollection<T>: Default + Send {
+    /// Appends a single entry.
+    fn push_entry(&mut self, entry: T);
+    /// Appends all entries of another collection.
+    fn append_entries(&mut self, other: Self);
+    /// Returns the number of entries.
+    fn n_entries(&self) -> usize;
+}
+
+impl<T: Send> ListCollection<T> for Vec<T> {
+    fn push_entry(&mut self, entry: T) {
+        self.push(entry);
+    }
+
+    fn append_entries(&mut self, mut other: Self) {
+        self.append(&mut other);
+    }
+
+    fn n_entries(&self) -> usize {
+        self.len()
+    }
+}
+
+/// Parses the `n` entries of a list, up to the closing parenthesis, which is not consumed.
+/// If the "rayon" feature is enabled, long lists with one entry per line are split into chunks of
+/// lines that are parsed in parallel. The result is checked against the expected number of entries,
+/// and the list is parsed sequentially if the entries do not fit this layout.
+pub(crate) fn parse_list<'a, T: 'a, C: ListCollection<T>>(
+    input: &'a str,
+    n: usize,
+    item: fn(&'a str) -> IResult<&'a str, T>,
+) -> IResult<&'a str, C> {
+    #[cfg(feature = "rayon")]
+    if n >= PARALLEL_LIST_LENGTH {
+        if let Some(result) = parse_list_chunks(input, n, item) {
+            return Ok(result);
+        }
+    }
+    fold_many_m_n(n, n, next(item), C::default, |mut entries, entry| {
+        entries.push_entry(entry);
+        entries
+    })(input)
+}
+
+/// Parses the entries of a list in parallel, assuming that the list ends with a line starting with
+/// a closing parenthesis and that no entry spans several lines.
+/// Returns None if the entries do not match these assumptions.
+#[cfg(feature = "rayon")]
+fn parse_list_chunks<'a, T: 'a, C: ListCollection<T>>(
+    input: &'a str,
+    n: usize,
+    item: fn(&'a str) -> IResult<&'a str, T>,
+) -> Option<(&'a str, C)> {
+    let end = input.find("\n)")? + 1;
+    let chunks = split_lines(&input[..end], 4 * rayon::current_num_threads());
+    let parse_chunk = |chunk: &&'a str| {
+        let entries = fold_many0(next(item), C::default, |mut entries, entry| {
+            entries.push_entry(entry);
+            entries
+        });
+        match terminated(entries, discard_garbage)(chunk) {
+            Ok(("", entries)) => Some(entries),
+            _ => None,
+        }
+    };
+    let chunk_entries: Option<Vec<C>> = chunks.par_iter().map(parse_chunk).collect();
+    let mut entries = C::default();
+    for chunk in chunk_entries? {
+        entries.append_entries(chunk);
+    }
+    if entries.n_entries() != n {
+        return None;
+    }
+    Some((&input[end..], entries))
+}
+
+/// Splits the input into about `n_chunks` chunks of whole lines.
+#[cfg_attr(not(feature = "rayon"), allow(dead_code))]
+fn split_lines(input: &str, n_chunks: usize) -> Vec<&str> {
+    let chunk_length = input.len() / n_chunks.max(1) + 1;
+    let mut chunks = Vec::with_capacity(n_chunks);
+    let bytes = input.as_bytes();
+    let mut start = 0;
+    while start < input.len() {
+        // searching the bytes avoids slicing inside a multi-byte character,
+        // the position after a newline is always a character boundary
+        let search = (start + chunk_length).min(input.len());
+        let end = match bytes[search..].iter().position(|&b| b == b'\n') {
+            Some(i) => search + i + 1,
+            None => input.len(),
+        };
+        chunks.push(&input[start..end]);
+        start = end;
+    }
+    chunks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base::parser_base::usize_val;
+
+    #[test]
+    fn test_split_lines() {
+        let input = "0\n1\n2\n3\n4\n5\n6\n";
+        let chunks = split_lines(input, 3);
+        assert!(chunks.len() > 1);
+        assert_eq!(chunks.concat(), input);
+        assert!(chunks.iter().all(|chunk| chunk.ends_with('\n')));
+    }
+
+    #[test]
+    fn test_split_lines_non_ascii() {
+        let input = "// é\n// ü\n0 // ß\n1\n// €€€\n";
+        for n_chunks in 1..input.len() {
+            let chunks = split_lines(input, n_chunks);
+            assert_eq!(chunks.concat(), input);
+            assert!(chunks.iter().all(|chunk| chunk.ends_with('\n')));
+        }
+    }
+
+    #[test]
+    fn test_parse_list() {
+        let n = 25_000;
+        let mut input: String = (0..n).map(|i| format!("{}\n", i)).collect();
+        input.push_str(")\n");
+        let (rest, values): (_, Vec<usize>) = parse_list(&input, n, usize_val).unwrap();
+        assert_eq!(rest.trim_start(), ")\n");
+        assert_eq!(values, (0..n).collect::<Vec<_>>());
+        // a comment that looks like the end of the list
+        let input = format!("/* first\n) */\n{}", input);
+        let (rest, values): (_, Vec<usize>) = parse_list(&input, n, usize_val).unwrap();
+        assert_eq!(rest.trim_start(), ")\n");
+        assert_eq!(values, (0..n).collect::<Vec<_>>());
+    }
+}
@@ -1,15 +1,16 @@
+use super::parallel::parse_list;
 use nom::{
     branch::alt,
     bytes::complete::{is_not, tag, take_until, take_while1},
     character::complete::{char, digit0, digit1, multispace0, multispace1},
-    combinator::{map, map_res, opt, recognize, value},
-    multi::{count, many0, many1},
+    combinator::{map, map_res, value},
+    multi::{many0, many1},
     number::complete::double,
     sequence::{delimited, pair, preceded, terminated, tuple},
     IResult,
 };
 
-/// Modificators
+// Modificators
 
 /// A combinator that takes a parser `inner` and produces a parser that also consumes both leading and
 /// trailing whitespace, returning the output of `inner`.
@@ -56,7 +57,7 @@ where
     preceded(discard_garbage, inner)
 }
 
-/// Parsers
+// Parsers
 
 /// A parser that consumes a word, either contained in quotes, or a contiguous word
 /// potentially containing /, \, . and _. Returns a `&str`.
@@ -76,14 +77,6 @@ pub fn usize_val(input: &str) -> IResult<&str, usize> {
     map_res(digit1, str::parse)(input)
 }
 
-/// A parser that consumes a positive or negative integer and returns it as a `f64`.
-pub fn i32_val(input: &str) -> IResult<&str, i32> {
-    map_res(
-        recognize(preceded(opt(char('-')), digit1)),
-        str::parse::<i32>,
-    )(input)
-}
-
 /// A parser that consumes a "boolean" 0 or 1 and returns it as a `bool`.
 pub fn bool(input: &str) -> IResult<&str, bool> {
     map(alt((char('0'), char('1'))), |x| x == '1')(input)
@@ -121,7 +114,11 @@ pub fn single_i_data(input: &str) -> IResult<&str, Vec<usize>> {
     // data always starts with the number of elements
     let (input, n) = next(usize_val)(input)?;
     // now comes the actual data
-    delimited(next(char('(')), count(next(usize_val), n), next(char(')')))(input)
+    delimited(
+        next(char('(')),
+        move |i| parse_list(i, n, usize_val),
+        next(char(')')),
+    )(input)
 }
 
 /// Parses a list of `double` values separated by whitespace.
@@ -142,7 +139,7 @@ pub fn double_vector_field(input: &str) -> IResult<&str, Vec<Vec<f64>>> {
     // then we have a list of vectors
     delimited(
         next(char('(')),
-        count(next(double_vector), n),
+        move |i| parse_list(i, n, double_vector),
         next(char(')')),
     )(input)
 }
@@ -151,10 +148,14 @@ pub fn double_scalar_field(input: &str) -> IResult<&str, Vec<f64>> {
     // always starts with the number of entries
     let (input, n) = next(usize_val)(input)?;
     // then we have a list of vectors
-    delimited(next(char('(')), count(next(double), n), next(char(')')))(input)
+    delimited(
+        next(char('(')),
+        move |i| parse_list(i, n, double),
+        next(char(')')),
+    )(input)
 }
 
-/// Discarders
+// Discarders
 
 pub fn known_key_value<'a, 'b>(
     name: &'b str,
@@ -256,8 +257,7 @@ FoamFile
                 assert_eq!(expected, actual);
             }
             Err(e) => {
-                println!("Error: {:?}", e);
-                assert!(false);
+                panic!("Error: {:?}", e);
             }
         }
     }
@@ -0,0 +1,602 @@
+use super::file_content::{open_file, HEADER_SEPARATOR};
+use super::FileElement;
+use super::FoamFile;
+use std::io::{BufRead, Write};
+use std::marker::PhantomData;
+
+/// An item of a list file that can be read and written one at a time, e.g. a point of the
+/// polyMesh/points file, a face of the polyMesh/faces file or a cell label of the polyMesh/owner file.
+/// The trait is sealed: it is only implemented for the items of the polyMesh list files, because
+/// reading relies on the internal `Scanner`, which is not part of the public API.
+pub trait ListItem: Sized + private::Sealed {
+    /// Reads the next item.
+    fn read<R: BufRead>(scanner: &mut Scanner<R>) -> std::io::Result<Self>;
+    /// Writes the item on its own line.
+    fn write<W: Write>(&self, writer: &mut W) -> std::io::Result<()>;
+}
+
+mod private {
+    /// Prevents `ListItem` from being implemented outside of this crate.
+    pub trait Sealed {}
+
+    impl Sealed for usize {}
+    impl Sealed for [f64; 3] {}
+    impl Sealed for Vec<usize> {}
+}
+
+/// A label, e.g. a cell of the polyMesh/owner or polyMesh/neighbour files.
+impl ListItem for usize {
+    fn read<R: BufRead>(scanner: &mut Scanner<R>) -> std::io::Result<Self> {
+        scanner.value()
+    }
+
+    fn write<W: Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        writeln!(writer, "{}", self)
+    }
+}
+
+/// A point of the polyMesh/points file: `(x y z)`.
+impl ListItem for [f64; 3] {
+    fn read<R: BufRead>(scanner: &mut Scanner<R>) -> std::io::Result<Self> {
+        scanner.expect(b'(')?;
+        let point = [scanner.value()?, scanner.value()?, scanner.value()?];
+        scanner.expect(b')')?;
+        Ok(point)
+    }
+
+    fn write<W: Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        writeln!(writer, "({} {} {})", self[0], self[1], self[2])
+    }
+}
+
+/// A face of the polyMesh/faces file: `n(p0 p1 ... pn)`.
+impl ListItem for Vec<usize> {
+    fn read<R: BufRead>(scanner: &mut Scanner<R>) -> std::io::Result<Self> {
+        let n: usize = scanner.value()?;
+        scanner.expect(b'(')?;
+        let mut face = Vec::with_capacity(n);
+        for _ in 0..n {
+            face.push(scanner.value()?);
+        }
+        scanner.expect(b')')?;
+        Ok(face)
+    }
+
+    fn write<W: Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        write!(writer, "{}(", self.len())?;
+        for (i, point) in self.iter().enumerate() {
+            if i > 0 {
+                write!(writer, " ")?;
+            }
+            write!(writer, "{}", point)?;
+        }
+        writeln!(writer, ")")
+    }
+}
+
+/// Reads the items of a list file one at a time, without loading the full file into memory.
+/// This is useful for very large meshes, where the points, faces, owner and neighbour files do not
+/// fit into memory at once:
+/// ```
+/// # use polymesh_rw::*;
+/// # fn main() -> std::io::Result<()> {
+/// let path = std::path::Path::new("tests/test_cases/original/cylinder/constant/polyMesh/faces");
+/// let faces = ListReader::<_, Vec<usize>>::open(path)?;
+/// let mut n_vertices = 0;
+/// for face in faces {
+///     n_vertices += face?.len();
+/// }
+/// # assert!(n_vertices > 0);
+/// # Ok(())
+/// # }
+/// ```
+pub struct ListReader<R: BufRead, T: ListItem> {
+    scanner: Scanner<R>,
+    header: FoamFile,
+    len: usize,
+    remaining: usize,
+    finished: bool,
+    item: PhantomData<T>,
+}
+
+impl<T: ListItem> ListReader<Box<dyn BufRead>, T> {
+    /// Opens a list file. A gzip-compressed version of the file is read if the file itself does
+    /// not exist, see `FileContent::parse_file`.
+    pub fn open(path: &std::path::Path) -> std::io::Result<Self> {
+        Self::new(open_file(path)?)
+    }
+}
+
+impl<R: BufRead, T: ListItem> ListReader<R, T> {
+    /// Reads the header and the length of the list. The items are read by iterating.
+    pub fn new(mut reader: R) -> std::io::Result<Self> {
+        let header = read_header(&mut reader)?;
+        let mut scanner = Scanner::new(reader);
+        let len = scanner.value()?;
+        scanner.expect(b'(')?;
+        Ok(ListReader {
+            scanner,
+            header,
+            len,
+            remaining: len,
+            finished: false,
+            item: PhantomData,
+        })
+    }
+
+    /// Returns the header of the file.
+    pub fn header(&self) -> &FoamFile {
+        &self.header
+    }
+
+    /// Returns the number of items of the list, as given at the top of the list.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true if the list has no items.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+impl<R: BufRead, T: ListItem> Iterator for ListReader<R, T> {
+    type Item = std::io::Result<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+        if self.remaining == 0 {
+            // the closing parenthesis confirms the length of the list
+            self.finished = true;
+            return match self.scanner.expect(b')') {
+                Ok(()) => None,
+                Err(e) => Some(Err(e)),
+            };
+        }
+        self.remaining -= 1;
+        let item = T::read(&mut self.scanner);
+        if item.is_err() {
+            self.finished = true;
+        }
+        Some(item)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining + 1))
+    }
+}
+
+/// Reads the faces of a polyMesh/faces file in the "faceCompactList" format one at a time.
+/// Such a file holds two lists: the offsets of the faces, then the vertices of all faces.
+/// The two lists are read in step by two readers of the same file, so that neither of them
+/// needs to be in memory:
+/// ```
+/// # use polymesh_rw::*;
+/// # fn main() -> std::io::Result<()> {
+/// # let path = std::path::Path::new("tests/test_cases/original/cylinder/constant/polyMesh/faces");
+/// # let mut faces = FileContent::<FaceData>::parse_file(path)?;
+/// # faces.set_compact(true);
+/// # let compact = std::env::temp_dir().join(format!("polymesh_rw_compact_{}", std::process::id()));
+/// # std::fs::write(&compact, faces.to_string())?;
+/// let reader = CompactListReader::open(&compact)?;
+/// # assert_eq!(reader.len(), faces.data.len());
+/// let mut n_vertices = 0;
+/// for face in reader {
+///     n_vertices += face?.len();
+/// }
+/// # std::fs::remove_file(&compact)?;
+/// # assert_eq!(n_vertices, faces.data.vertices().len());
+/// # Ok(())
+/// # }
+/// ```
+pub struct CompactListReader<R: BufRead> {
+    offsets: ListReader<R, usize>,
+    vertices: Scanner<R>,
+    len: usize,
+    remaining: usize,
+    offset: usize,
+    finished: bool,
+}
+
+impl CompactListReader<Box<dyn BufRead>> {
+    /// Opens a faceCompactList file twice, once for the offsets and once for the vertices.
+    /// A gzip-compressed version of the file is read if the file itself does not exist.
+    pub fn open(path: &std::path::Path) -> std::io::Result<Self> {
+        Self::new(open_file(path)?, open_file(path)?)
+    }
+}
+
+impl<R: BufRead> CompactListReader<R> {
+    /// Reads the headers from two readers of the same file, then skips the offsets with the
+    /// second reader to reach the vertices. The faces are read by iterating.
+    pub fn new(offsets_reader: R, mut vertices_reader: R) -> std::io::Result<Self> {
+        let mut offsets = ListReader::<R, usize>::new(offsets_reader)?;
+        // an empty list may be written without the single offset
+        let len = offsets.len().saturating_sub(1);
+        let offset = match offsets.len() {
+            0 => 0,
+            _ => offsets.next().unwrap_or(Ok(0))?,
+        };
+        if offset != 0 {
+            return Err(invalid_data(format!(
+                "The first offset is {} instead of 0.",
+                offset
+            )));
+        }
+        read_header(&mut vertices_reader)?;
+        let mut vertices = Scanner::new(vertices_reader);
+        let n_offsets: usize = vertices.value()?;
+        vertices.expect(b'(')?;
+        for _ in 0..n_offsets {
+            vertices.value::<usize>()?;
+        }
+        vertices.expect(b')')?;
+        vertices.value::<usize>()?;
+        vertices.expect(b'(')?;
+        Ok(CompactListReader {
+            offsets,
+            vertices,
+            len,
+            remaining: len,
+            offset,
+            finished: false,
+        })
+    }
+
+    /// Returns the header of the file.
+    pub fn header(&self) -> &FoamFile {
+        self.offsets.header()
+    }
+
+    /// Returns the number of faces, one less than the number of offsets.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true if the list has no faces.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Reads the vertices of the next face, given by the next offset.
+    fn read_face(&mut self) -> std::io::Result<Vec<usize>> {
+        let next = match self.offsets.next() {
+            Some(next) => next?,
+            None => return Err(invalid_data("Missing offsets.".to_string())),
+        };
+        if next < self.offset {
+            return Err(invalid_data(format!(
+                "The offset {} is smaller than the previous one.",
+                next
+            )));
+        }
+        let mut face = Vec::with_capacity(next - self.offset);
+        for _ in self.offset..next {
+            face.push(self.vertices.value()?);
+        }
+        self.offset = next;
+        Ok(face)
+    }
+}
+
+impl<R: BufRead> Iterator for CompactListReader<R> {
+    type Item = std::io::Result<Vec<usize>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+        if self.remaining == 0 {
+            // the closing parentheses of both lists confirm their lengths
+            self.finished = true;
+            if let Some(Err(e)) = self.offsets.next() {
+                return Some(Err(e));
+            }
+            return match self.vertices.expect(b')') {
+                Ok(()) => None,
+                Err(e) => Some(Err(e)),
+            };
+        }
+        self.remaining -= 1;
+        let face = self.read_face();
+        if face.is_err() {
+            self.finished = true;
+        }
+        Some(face)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining + 1))
+    }
+}
+
+/// Writes a list file one item at a time, so that the full list never needs to be in memory.
+/// The output is identical to writing the corresponding `FileContent` with `write_file`.
+pub struct ListWriter<W: Write, T: ListItem> {
+    writer: W,
+    len: usize,
+    written: usize,
+    item: PhantomData<T>,
+}
+
+impl<W: Write, T: ListItem> ListWriter<W, T> {
+    /// Writes the header and the length of the list, which needs to be known in advance.
+    pub fn new(mut writer: W, header: &FoamFile, len: usize) -> std::io::Result<Self> {
+        write!(writer, "{}", header)?;
+        writeln!(writer, "{}", HEADER_SEPARATOR)?;
+        writeln!(writer, "{}", len)?;
+        writeln!(writer, "(")?;
+        Ok(ListWriter {
+            writer,
+            len,
+            written: 0,
+            item: PhantomData,
+        })
+    }
+
+    /// Writes the next item of the list.
+    pub fn write_item(&mut self, item: &T) -> std::io::Result<()> {
+        if self.written == self.len {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("The list only has {} items.", self.len),
+            ));
+        }
+        item.write(&mut self.writer)?;
+        self.written += 1;
+        Ok(())
+    }
+
+    /// Writes all items of an iterator.
+    pub fn write_items<I: IntoIterator<Item = T>>(&mut self, items: I) -> std::io::Result<()> {
+        for item in items {
+            self.write_item(&item)?;
+        }
+        Ok(())
+    }
+
+    /// Closes the list and returns the underlying writer.
+    /// Fails if fewer items were written than announced.
+    pub fn finish(mut self) -> std::io::Result<W> {
+        if self.written != self.len {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!(
+                    "Only {} of {} items were written to the list.",
+                    self.written, self.len
+                ),
+            ));
+        }
+        writeln!(self.writer, ")")?;
+        writeln!(self.writer)?;
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+/// Reads only the header of a file, e.g. to find out the class of a field without parsing it.
+pub(crate) fn read_file_header(path: &std::path::Path) -> std::io::Result<FoamFile> {
+    read_header(&mut open_file(path)?)
+}
+
+/// Reads the lines up to the end of the FoamFile header and parses the header.
+fn read_header<R: BufRead>(reader: &mut R) -> std::io::Result<FoamFile> {
+    let mut input = String::new();
+    let mut depth = 0;
+    let mut started = false;
+    loop {
+        let start = input.len();
+        if reader.read_line(&mut input)? == 0 {
+            return Err(invalid_data("No FoamFile header found.".to_string()));
+        }
+        if !started {
+            // skip the banner
+            if !input[start..].trim_start().starts_with("FoamFile") {
+                continue;
+            }
+            started = true;
+        }
+        for c in input[start..].chars() {
+            match c {
+                '{' => depth += 1,
+                '}' => depth -= 1,
+                _ => {}
+            }
+        }
+        if depth == 0 && input[start..].contains('}') {
+            break;
+        }
+    }
+    match FoamFile::parse(&input) {
+        Ok((_, header)) => Ok(header),
+        Err(e) => Err(invalid_data(format!("Failed to parse header: {}", e))),
+    }
+}
+
+fn invalid_data(message: String) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, message)
+}
+
+/// Splits the input into words and parentheses, one line at a time, skipping comments.
+pub struct Scanner<R: BufRead> {
+    reader: R,
+    line: String,
+    pos: usize,
+    in_comment: bool,
+}
+
+impl<R: BufRead> Scanner<R> {
+    fn new(reader: R) -> Self {
+        Scanner {
+            reader,
+            line: String::new(),
+            pos: 0,
+            in_comment: false,
+        }
+    }
+
+    /// Reads the next line. Returns false at the end of the input.
+    fn next_line(&mut self) -> std::io::Result<bool> {
+        self.line.clear();
+        self.pos = 0;
+        Ok(self.reader.read_line(&mut self.line)? > 0)
+    }
+
+    /// Skips whitespace and comments and returns the next character without consuming it.
+    /// Returns None at the end of the input.
+    fn peek(&mut self) -> std::io::Result<Option<u8>> {
+        loop {
+            if self.in_comment {
+                match self.line[self.pos..].find("*/") {
+                    Some(i) => {
+                        self.pos += i + 2;
+                        self.in_comment = false;
+                    }
+                    None => {
+                        if !self.next_line()? {
+                            return Ok(None);
+                        }
+                    }
+                }
+                continue;
+            }
+            let rest = self.line[self.pos..].trim_start();
+            self.pos = self.line.len() - rest.len();
+            if rest.is_empty() || rest.starts_with("//") {
+                if !self.next_line()? {
+                    return Ok(None);
+                }
+            } else if rest.starts_with("/*") {
+                self.pos += 2;
+                self.in_comment = true;
+            } else {
+                return Ok(Some(rest.as_bytes()[0]));
+            }
+        }
+    }
+
+    /// Consumes the given character.
+    fn expect(&mut self, expected: u8) -> std::io::Result<()> {
+        match self.peek()? {
+            Some(c) if c == expected => {
+                self.pos += 1;
+                Ok(())
+            }
+            Some(c) => Err(invalid_data(format!(
+                "Expected '{}', found '{}'.",
+                expected as char, c as char
+            ))),
+            None => Err(invalid_data(format!(
+                "Expected '{}', found the end of the file.",
+                expected as char
+            ))),
+        }
+    }
+
+    /// Consumes the next word and parses it as a value.
+    fn value<V: std::str::FromStr>(&mut self) -> std::io::Result<V> {
+        if self.peek()?.is_none() {
+            return Err(invalid_data(
+                "Expected a value, found the end of the file.".to_string(),
+            ));
+        }
+        let rest = &self.line[self.pos..];
+        let len = rest
+            .find(|c: char| c.is_whitespace() || "(){};".contains(c))
+            .unwrap_or(rest.len());
+        let word = &rest[..len];
+        match word.parse() {
+            Ok(value) => {
+                self.pos += len;
+                Ok(value)
+            }
+            Err(_) => Err(invalid_data(format!("Invalid value \"{}\".", word))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base::FileContent;
+    use crate::polymesh::{FaceData, PointData};
+
+    #[test]
+    fn test_read_list() {
+        let input = "
+FoamFile
+{
+    format      ascii;
+    class       faceList;
+    object      faces;
+}
+// comment
+3 /* the number of faces */
+(
+3(0 1 2)
+4(3 4
+  5 6)
+3(7 8 9)
+)
+";
+        let faces: Vec<Vec<usize>> = ListReader::new(input.as_bytes())
+            .unwrap()
+            .collect::<std::io::Result<_>>()
+            .unwrap();
+        assert_eq!(faces, vec![vec![0, 1, 2], vec![3, 4, 5, 6], vec![7, 8, 9]]);
+
+        let truncated = input.replace("3(7 8 9)\n", "");
+        let result: std::io::Result<Vec<Vec<usize>>> =
+            ListReader::new(truncated.as_bytes()).unwrap().collect();
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_stream_round_trip() {
+        let path = std::path::Path::new("tests/test_cases/original/cylinder/constant/polyMesh");
+        let points = FileContent::<PointData>::parse_file(&path.join("points")).unwrap();
+        let reader = ListReader::<_, [f64; 3]>::open(&path.join("points")).unwrap();
+        assert_eq!(reader.header(), &points.meta);
+        let mut writer = ListWriter::new(Vec::new(), &points.meta, reader.len()).unwrap();
+        for point in reader {
+            writer.write_item(&point.unwrap()).unwrap();
+        }
+        let output = String::from_utf8(writer.finish().unwrap()).unwrap();
+        assert_eq!(output, points.to_string());
+
+        let faces = FileContent::<FaceData>::parse_file(&path.join("faces")).unwrap();
+        let streamed: Vec<Vec<usize>> = ListReader::open(&path.join("faces"))
+            .unwrap()
+            .collect::<std::io::Result<_>>()
+            .unwrap();
+        assert_eq!(streamed, faces.data.to_vec());
+    }
+
+    #[test]
+    fn test_read_compact_list() {
+        let path = std::path::Path::new("tests/test_cases/original/cylinder/constant/polyMesh");
+        let mut faces = FileContent::<FaceData>::parse_file(&path.join("faces")).unwrap();
+        faces.set_compact(true);
+        let input = faces.to_string();
+        let reader = CompactListReader::new(input.as_bytes(), input.as_bytes()).unwrap();
+        assert_eq!(reader.header(), &faces.meta);
+        assert_eq!(reader.len(), faces.data.len());
+        let streamed: Vec<Vec<usize>> = reader.collect::<std::io::Result<_>>().unwrap();
+        assert_eq!(streamed, faces.data.to_vec());
+
+        // an offset beyond the vertices is reported
+        let n_vertices = faces.data.vertices().len();
+        let broken = input.replacen(
+            &format!("\n{}\n)", n_vertices),
+            &format!("\n{}\n)", n_vertices + 1),
+            1,
+        );
+        let result: std::io::Result<Vec<Vec<usize>>> =
+            CompactListReader::new(broken.as_bytes(), broken.as_bytes())
+                .unwrap()
+                .collect();
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,46 @@
+/// Small helpers for the 3D vector arithmetic needed by the mesh geometry computations.
+/// Vectors are stored as plain `[f64; 3]` arrays, like the points of a polyMesh.
+pub type Vector = [f64; 3];
+
+pub fn add(a: &Vector, b: &Vector) -> Vector {
+    [a[0] + b[0], a[1] + b[1], a[2] + b[2]]
+}
+
+pub fn sub(a: &Vector, b: &Vector) -> Vector {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+pub fn scale(a: &Vector, s: f64) -> Vector {
+    [a[0] * s, a[1] * s, a[2] * s]
+}
+
+pub fn dot(a: &Vector, b: &Vector) -> f64 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+pub fn cross(a: &Vector, b: &Vector) -> Vector {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+pub fn mag(a: &Vector) -> f64 {
+    dot(a, a).sqrt()
+}
+
+/// Returns the arithmetic mean of the given points.
+pub fn average<'a, I: IntoIterator<Item = &'a Vector>>(points: I) -> Vector {
+    let mut sum = [0.0; 3];
+    let mut n = 0;
+    for p in points {
+        sum = add(&sum, p);
+        n += 1;
+    }
+    if n == 0 {
+        sum
+    } else {
+        scale(&sum, 1.0 / n as f64)
+    }
+}
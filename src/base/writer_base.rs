@@ -48,10 +48,11 @@ pub fn write_vector_content<T: std::fmt::Display>(
 /// 4(6 7 8 9)
 /// )
 /// ```
-pub fn write_multi_data<T: std::fmt::Display>(
-    data: &[Vec<T>],
-    file: &mut std::fmt::Formatter,
-) -> std::fmt::Result {
+pub fn write_multi_data<'a, T, I>(data: I, file: &mut std::fmt::Formatter) -> std::fmt::Result
+where
+    T: std::fmt::Display + 'a,
+    I: ExactSizeIterator<Item = &'a [T]>,
+{
     writeln!(file, "{}", data.len())?;
     writeln!(file, "(")?;
     for d in data {
@@ -0,0 +1,273 @@
+use crate::base::{data_files, find_file, read_file_header, FileParser};
+use crate::{numbered_directories, region_directories};
+use crate::{FileContent, PolyMesh, RegionPropertiesData, ResultData, TimeDir, TimeSelector};
+use indexmap::IndexMap;
+use std::collections::HashMap;
+use std::path;
+use std::sync::{Arc, Mutex};
+
+/// The CaseIndex structure lists the contents of a case directory without parsing them:
+/// the time directories, the fields they contain with the class found in their header, and the
+/// meshes. Meshes and fields are only parsed when they are requested. Parsed data can optionally
+/// be cached, so that it is only parsed once.
+/// ```
+/// # use polymesh_rw::*;
+/// # fn main() -> std::io::Result<()> {
+/// let index = CaseIndex::scan(std::path::Path::new("tests/test_cases/original/cylinder"))?;
+/// for (time_name, time) in index.times.iter() {
+///     for (name, field) in time.fields.iter() {
+///         println!("{}: {} ({:?})", time_name, name, field.class);
+///     }
+/// }
+/// let u = index.field("0", "U")?;
+/// # assert_eq!(u.meta.class(), Some("volVectorField"));
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct CaseIndex {
+    /// The path to the case directory.
+    pub path: path::PathBuf,
+    /// Whether the case has a mesh in "constant/polyMesh".
+    pub has_mesh: bool,
+    /// The regions of a multi-region case, with meshes in "constant/<region>/polyMesh".
+    pub regions: Vec<String>,
+    /// The time directories, sorted by time. Keys: names of the time directories.
+    pub times: IndexMap<String, TimeIndex>,
+    cache: Option<Cache>,
+}
+
+/// The contents of a time directory, as listed by `CaseIndex`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct TimeIndex {
+    pub time: f64,
+    /// The fields of the time directory, sorted by name.
+    pub fields: IndexMap<String, FieldIndex>,
+    /// The fields of each region, found in "<time>/<region>/".
+    pub region_fields: IndexMap<String, IndexMap<String, FieldIndex>>,
+}
+
+/// A field file, as listed by `CaseIndex`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct FieldIndex {
+    /// The path to the file. It has the ".gz" extension if the file is compressed.
+    pub path: path::PathBuf,
+    /// The class found in the header of the file, e.g. "volScalarField".
+    /// None if the header could not be read.
+    pub class: Option<String>,
+}
+
+/// The meshes and fields that were already parsed. Keys: paths to the mesh directories and files.
+#[derive(Debug, Default)]
+struct Cache {
+    meshes: Mutex<HashMap<path::PathBuf, Arc<PolyMesh>>>,
+    fields: Mutex<HashMap<path::PathBuf, Arc<FileContent<ResultData>>>>,
+}
+
+impl CaseIndex {
+    /// Scans the case directory. Only the headers of the field files are read.
+    /// Caching is disabled, see `CaseIndex::with_cache`.
+    pub fn scan(dir_path: &path::Path) -> std::io::Result<CaseIndex> {
+        let has_mesh = dir_path.join("constant/polyMesh").is_dir();
+        let properties_path = dir_path.join(RegionPropertiesData::default_file_path());
+        let regions = if find_file(&properties_path).is_file() {
+            FileContent::<RegionPropertiesData>::parse_file(&properties_path)?
+                .data
+                .region_names()
+        } else {
+            region_directories(&dir_path.join("constant"))?
+        };
+
+        let mut times = Vec::new();
+        for (time, time_path) in numbered_directories(dir_path)? {
+            let time_name = time_path.file_name().unwrap().to_str().unwrap().to_string();
+            let mut region_fields = IndexMap::new();
+            for region in regions.iter() {
+                if time_path.join(region).is_dir() {
+                    region_fields.insert(region.clone(), index_fields(&time_path.join(region))?);
+                }
+            }
+            let time = TimeIndex {
+                time,
+                fields: index_fields(&time_path)?,
+                region_fields,
+            };
+            times.push((time_name, time));
+        }
+
+        Ok(CaseIndex {
+            path: dir_path.to_path_buf(),
+            has_mesh,
+            regions,
+            times: times.into_iter().collect(),
+            cache: None,
+        })
+    }
+
+    /// Enables or disables the caching of parsed meshes and fields.
+    pub fn with_cache(mut self, cache: bool) -> Self {
+        self.cache = if cache { Some(Cache::default()) } else { None };
+        self
+    }
+
+    /// Drops all cached meshes and fields.
+    pub fn clear_cache(&self) {
+        if let Some(cache) = &self.cache {
+            cache.meshes.lock().unwrap().clear();
+            cache.fields.lock().unwrap().clear();
+        }
+    }
+
+    /// Returns the names of the time directories, sorted by time.
+    pub fn time_names(&self) -> Vec<&str> {
+        self.times.keys().map(String::as_str).collect()
+    }
+
+    /// Returns the names of the selected time directories, sorted by time.
+    pub fn select_times(&self, selector: &TimeSelector) -> Vec<&str> {
+        let times: Vec<f64> = self.times.values().map(|time| time.time).collect();
+        selector
+            .select(&times)
+            .into_iter()
+            .map(|i| self.times.get_index(i).unwrap().0.as_str())
+            .collect()
+    }
+
+    /// Parses the mesh found in "constant/polyMesh".
+    pub fn mesh(&self) -> std::io::Result<Arc<PolyMesh>> {
+        self.parse_mesh(self.path.join("constant/polyMesh"))
+    }
+
+    /// Parses the mesh of a region, found in "constant/<region>/polyMesh".
+    pub fn region_mesh(&self, region: &str) -> std::io::Result<Arc<PolyMesh>> {
+        self.parse_mesh(self.path.join("constant").join(region).join("polyMesh"))
+    }
+
+    /// Parses a field of a time directory.
+    pub fn field(&self, time: &str, name: &str) -> std::io::Result<Arc<FileContent<ResultData>>> {
+        let field = self
+            .times
+            .get(time)
+            .and_then(|time_index| time_index.fields.get(name))
+            .ok_or_else(|| not_found(format!("Field {} not found in time {}.", name, time)))?;
+        self.parse_field(&field.path)
+    }
+
+    /// Parses a field of a region, found in "<time>/<region>/".
+    pub fn region_field(
+        &self,
+        time: &str,
+        region: &str,
+        name: &str,
+    ) -> std::io::Result<Arc<FileContent<ResultData>>> {
+        let field = self
+            .times
+            .get(time)
+            .and_then(|time_index| time_index.region_fields.get(region))
+            .and_then(|fields| fields.get(name))
+            .ok_or_else(|| {
+                not_found(format!(
+                    "Field {} of region {} not found in time {}.",
+                    name, region, time
+                ))
+            })?;
+        self.parse_field(&field.path)
+    }
+
+    /// Parses a complete time directory. The result is not cached.
+    pub fn time_dir(&self, time: &str) -> std::io::Result<TimeDir> {
+        if !self.times.contains_key(time) {
+            return Err(not_found(format!("Time {} not found.", time)));
+        }
+        TimeDir::parse(&self.path.join(time))
+    }
+
+    fn parse_mesh(&self, mesh_path: path::PathBuf) -> std::io::Result<Arc<PolyMesh>> {
+        let Some(cache) = &self.cache else {
+            return Ok(Arc::new(PolyMesh::parse(&mesh_path)?));
+        };
+        if let Some(mesh) = cache.meshes.lock().unwrap().get(&mesh_path) {
+            return Ok(mesh.clone());
+        }
+        // the lock is not held while parsing, so that other meshes can be requested meanwhile
+        let mesh = Arc::new(PolyMesh::parse(&mesh_path)?);
+        cache.meshes.lock().unwrap().insert(mesh_path, mesh.clone());
+        Ok(mesh)
+    }
+
+    fn parse_field(
+        &self,
+        field_path: &path::Path,
+    ) -> std::io::Result<Arc<FileContent<ResultData>>> {
+        let Some(cache) = &self.cache else {
+            return Ok(Arc::new(FileContent::parse_file(field_path)?));
+        };
+        if let Some(field) = cache.fields.lock().unwrap().get(field_path) {
+            return Ok(field.clone());
+        }
+        let field = Arc::new(FileContent::parse_file(field_path)?);
+        cache
+            .fields
+            .lock()
+            .unwrap()
+            .insert(field_path.to_path_buf(), field.clone());
+        Ok(field)
+    }
+}
+
+/// Lists the field files of a directory and reads their headers.
+fn index_fields(dir: &path::Path) -> std::io::Result<IndexMap<String, FieldIndex>> {
+    let mut fields: Vec<(String, FieldIndex)> = data_files(dir)?
+        .into_iter()
+        .map(|(name, path)| {
+            let class = read_file_header(&path)
+                .ok()
+                .and_then(|header| header.class().map(str::to_string));
+            (name, FieldIndex { path, class })
+        })
+        .collect();
+    fields.sort_by(|(a, _), (b, _)| a.cmp(b));
+    Ok(fields.into_iter().collect())
+}
+
+fn not_found(message: String) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::NotFound, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_case_index() {
+        let path = path::Path::new("tests/test_cases/original/cylinder");
+        let index = CaseIndex::scan(path).unwrap();
+        assert!(index.has_mesh);
+        assert!(index.regions.is_empty());
+        assert_eq!(index.time_names(), vec!["0"]);
+        assert_eq!(
+            index.select_times(&TimeSelector::FirstNonZero),
+            Vec::<&str>::new()
+        );
+        let fields = &index.times["0"].fields;
+        assert_eq!(
+            fields.keys().collect::<Vec<_>>(),
+            vec!["U", "U.orig", "p.orig", "phi"]
+        );
+        assert_eq!(fields["phi"].class.as_deref(), Some("surfaceScalarField"));
+
+        let u = index.field("0", "U").unwrap();
+        assert_eq!(*u, FileContent::parse_file(&path.join("0/U")).unwrap());
+        assert!(index.field("0", "T").is_err());
+        // without cache, every request parses the file again
+        assert!(!Arc::ptr_eq(&u, &index.field("0", "U").unwrap()));
+
+        let index = index.with_cache(true);
+        let u = index.field("0", "U").unwrap();
+        assert!(Arc::ptr_eq(&u, &index.field("0", "U").unwrap()));
+        let mesh = index.mesh().unwrap();
+        assert!(Arc::ptr_eq(&mesh, &index.mesh().unwrap()));
+        index.clear_cache();
+        assert!(!Arc::ptr_eq(&u, &index.field("0", "U").unwrap()));
+    }
+}
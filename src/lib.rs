@@ -6,7 +6,7 @@
 //!# fn main() -> std::io::Result<()> {
 //! let case_file_path = std::path::Path::new("tests/test_cases/original/cylinder");
 //! let mut case = Case::parse_file(case_file_path)?;
-//!# let boundary = &mut case.polymesh.boundary;
+//!# let boundary = &mut case.polymesh.as_mut().expect("No mesh found.").boundary;
 //!#
 //!# let boundary_file_path = &case_file_path.join("constant/polyMesh/boundary");
 //!# let boundary_2 = FileContent::<BoundaryData>::parse_file(&boundary_file_path)?;
@@ -32,13 +32,14 @@
 //! ```
 //! The data in a case struct is separated in a ```polymesh``` structure which stores the mesh, and a ```time_directories```
 //! structure which stores the simulation data. For example, the boundary conditions which are located in the
-//! ```constant/polyMesh/boundary``` file will be found in ```case.polymesh.boundary```.
+//! ```constant/polyMesh/boundary``` file will be found in ```case.polymesh.boundary```. The ```polymesh``` is
+//! optional, because multi-region cases store one mesh per region in ```case.regions``` instead.
 //! ```
 //!# use polymesh_rw::*;
 //!# fn main() -> std::io::Result<()> {
 //!# let case_file_path = std::path::Path::new("tests/test_cases/original/cylinder");
 //!# let mut case = Case::parse_file(case_file_path)?;
-//! let boundary = &mut case.polymesh.boundary;
+//! let boundary = &mut case.polymesh.as_mut().expect("No mesh found.").boundary;
 //!#
 //!# let boundary_file_path = &case_file_path.join("constant/polyMesh/boundary");
 //!# let boundary_2 = FileContent::<BoundaryData>::parse_file(&boundary_file_path)?;
@@ -69,7 +70,7 @@
 //!# fn main() -> std::io::Result<()> {
 //!# let case_file_path = std::path::Path::new("tests/test_cases/original/cylinder");
 //!# let mut case = Case::parse_file(case_file_path)?;
-//!# let boundary = &mut case.polymesh.boundary;
+//!# let boundary = &mut case.polymesh.as_mut().expect("No mesh found.").boundary;
 //!#
 //! let boundary_file_path = &case_file_path.join("constant/polyMesh/boundary");
 //! let boundary_2 = FileContent::<BoundaryData>::parse_file(&boundary_file_path)?;
@@ -99,7 +100,7 @@
 //!# fn main() -> std::io::Result<()> {
 //!# let case_file_path = std::path::Path::new("tests/test_cases/original/cylinder");
 //!# let mut case = Case::parse_file(case_file_path)?;
-//!# let boundary = &mut case.polymesh.boundary;
+//!# let boundary = &mut case.polymesh.as_mut().expect("No mesh found.").boundary;
 //!#
 //!# let boundary_file_path = &case_file_path.join("constant/polyMesh/boundary");
 //!# let boundary_2 = FileContent::<BoundaryData>::parse_file(&boundary_file_path)?;
@@ -136,7 +137,7 @@
 //!# fn main() -> std::io::Result<()> {
 //!# let case_file_path = std::path::Path::new("tests/test_cases/original/cylinder");
 //!# let mut case = Case::parse_file(case_file_path)?;
-//!# let boundary = &mut case.polymesh.boundary;
+//!# let boundary = &mut case.polymesh.as_mut().expect("No mesh found.").boundary;
 //!#
 //!# let boundary_file_path = &case_file_path.join("constant/polyMesh/boundary");
 //!# let boundary_2 = FileContent::<BoundaryData>::parse_file(&boundary_file_path)?;
@@ -167,7 +168,7 @@
 //!# fn main() -> std::io::Result<()> {
 //!# let case_file_path = std::path::Path::new("tests/test_cases/original/cylinder");
 //!# let mut case = Case::parse_file(case_file_path)?;
-//!# let boundary = &mut case.polymesh.boundary;
+//!# let boundary = &mut case.polymesh.as_mut().expect("No mesh found.").boundary;
 //!#
 //!# let boundary_file_path = &case_file_path.join("constant/polyMesh/boundary");
 //!# let boundary_2 = FileContent::<BoundaryData>::parse_file(&boundary_file_path)?;
@@ -191,13 +192,15 @@
 //!# Ok(())
 //!# }
 //! ```
+//! Gzip-compressed files (with the ".gz" extension) are read like uncompressed ones. To write compressed files, use
+//! ```write_file_compressed``` with ```Compression::Gzip```.
 //! We can also choose to write only the ```boundary``` file, which is a part of the full case.
 //!```
 //!# use polymesh_rw::*;
 //!# fn main() -> std::io::Result<()> {
 //!# let case_file_path = std::path::Path::new("tests/test_cases/original/cylinder");
 //!# let mut case = Case::parse_file(case_file_path)?;
-//!# let boundary = &mut case.polymesh.boundary;
+//!# let boundary = &mut case.polymesh.as_mut().expect("No mesh found.").boundary;
 //!#
 //!# let boundary_file_path = &case_file_path.join("constant/polyMesh/boundary");
 //!# let boundary_2 = FileContent::<BoundaryData>::parse_file(&boundary_file_path)?;
@@ -224,29 +227,112 @@
 //! We still provide the path to the case directory, but the file will be written to the correct location inside the
 //! case directory. If the relative location needs to be changed, it can be done by assigning the correct *relative*
 //! path to the ```boundary.meta.location``` field (relative to the case directory).
+//!
+//! ## Parallelism
+//! With the optional ```rayon``` feature, the files of the mesh and the time directories are read and written
+//! concurrently, and long lists (points, faces, labels and field values) are parsed in parallel chunks.
+//!
+//! ## Field algebra
+//! Fields support arithmetic like in OpenFOAM: ```+```, ```-```, ```*``` and ```/``` between fields and with scalars, and
+//! functions like ```mag```, ```mag_sqr```, ```component```, ```dot```, ```cross```, ```trace```, ```dev```, ```symm```, ```min```, ```max``` and
+//! ```average```. Uniform fields are broadcast to the size of nonuniform fields. On ```ResultData```, the dimensions are
+//! checked: adding fields of different dimensions panics, and the dimensions of products and quotients are combined. Dimensions can have rational
+//! exponents, and both the list of exponents and named units like ```[m^2 s^-2]``` are parsed and written.
+//!
+//! ## Derived fields
+//! The mesh computes the gradients of volume fields (```PolyMesh::gradient```), and the post-processing quantities of the
+//! velocity that OpenFOAM's function objects write: ```vorticity```, ```Q```, ```Lambda2```, ```wallShearStress``` and ```yPlus```.
+//! They are returned as new fields with the names, classes and dimensions that OpenFOAM would write, and can be inserted
+//! in the ```field_values``` of a ```TimeDir```.
+//!
+//! ## Search and probes
+//! ```MeshSearch``` (built by ```PolyMesh::search```) stores the cells and the boundary faces in octrees, and finds the cell
+//! that contains a point, the nearest cell and the nearest boundary face. ```MeshSearch::probe``` samples volume fields at
+//! given locations, with the cell values or interpolated values, and ```Probes``` writes the time history like OpenFOAM's
+//! probes function object.
+//! Fields are sampled along lines (```MeshSearch::sample_line```, written as raw or CSV ```SampledSet```s) and on
+//! cutting planes (```PolyMesh::cut_plane```, written as raw or VTK ```CuttingPlane```s), like the ```sets``` and ```surfaces```
+//! function objects.
+//!
+//! ## Mapping fields
+//! ```Case::map_fields``` maps the volume fields of a time onto another mesh, like OpenFOAM's mapFields: each cell takes the
+//! value of the source cell that contains its centre (or of the nearest one), or the volume-weighted average of the
+//! source cells it overlaps, which conserves volume integrals between meshes of the same domain. The boundary
+//! conditions of the patches found in both meshes are kept.
+//!
+//! ## Surfaces
+//! ```PolyMesh::patch_surface``` extracts a patch as a standalone ```PatchSurface``` with its own points, which can be
+//! triangulated, carry face values of fields, and be written as ASCII or binary STL, OBJ or VTK files.
+//! ```PolyMesh::patch_topology``` returns the edges of a patch, with queries for the boundary, non-manifold and feature
+//! edges, the connected regions and the surface topology reported by checkMesh.
+//!
+//! ## Coupled patches
+//! ```BoundaryData::coupled_patch``` reads the entries of cyclic, cyclicAMI, cyclicSlip and mapped patches as a
+//! ```CoupledPatch```, with the neighbour patch and the rotational or translational transform, and
+//! ```BoundaryData::set_coupled_patch``` writes them back. ```PolyMesh::cyclic_pairing``` pairs the faces of a conformal
+//! cyclic with the faces of its neighbour after the transform, and checks that the patches match.
+//!
+//! ## Changing patches
+//! ```Case::repatch``` moves boundary faces into a new or existing patch, like OpenFOAM's createPatch. The faces can be
+//! given as a list, a face ```Set``` or ```FaceZone```, or selected by centre and normal with
+//! ```PolyMesh::boundary_faces_where```. The boundary faces are reordered so that each patch stays contiguous, and the
+//! face zones, face sets and boundary conditions of the fields are updated.
+//!
+//! ## Sets and zones
+//! ```PolyMesh::topo_set``` and ```PolyMesh::topo_zone``` apply the actions of OpenFOAM's topoSet to cell, face and point
+//! sets and zones: new, add, subtract, intersect, invert, clear and remove. The entities are selected by a
+//! ```TopoSetSource```: labels, a box, cylinder or sphere, a range of field values, a patch, a zone, a set, or the
+//! entities connected to a set of another type (e.g. cellToFace or faceToPoint). The results are written with the mesh.
 
-use std::collections::HashMap;
+use base::{find_file, FileParser};
+use indexmap::IndexMap;
+use polymesh::same_time;
+use std::borrow::Cow;
 use std::path;
 
 mod base;
+mod caseindex;
 mod polymesh;
 
+pub use base::Compression;
 pub use base::FileContent;
+pub use caseindex::CaseIndex;
+pub use caseindex::FieldIndex;
+pub use caseindex::TimeIndex;
 /// Data-containing structures
 // Containers of aggregated data (full files or folders).
+pub use polymesh::MeshRenumbering;
+pub use polymesh::MeshRepatching;
+pub use polymesh::MeshSearch;
+pub use polymesh::MeshSubset;
+pub use polymesh::MeshToMesh;
+pub use polymesh::MeshUpdate;
+pub use polymesh::PatchSurface;
+pub use polymesh::PatchTopology;
 pub use polymesh::PolyMesh;
+pub use polymesh::Probes;
+pub use polymesh::RegionSplit;
 pub use polymesh::TimeDir;
+pub use polymesh::TimeSelector;
 
 // Containers of individual files.
 pub use polymesh::BoundaryData;
+pub use polymesh::CellToRegionData;
 pub use polymesh::CellZone;
+pub use polymesh::CuttingPlane;
 pub use polymesh::FaceData;
+pub use polymesh::FaceIter;
 pub use polymesh::FaceZone;
+pub use polymesh::FieldLocation;
+pub use polymesh::GradientScheme;
 pub use polymesh::NeighbourData;
 pub use polymesh::OwnerData;
+pub use polymesh::Point;
 pub use polymesh::PointData;
 pub use polymesh::PointZone;
+pub use polymesh::RegionPropertiesData;
 pub use polymesh::ResultData;
+pub use polymesh::SampledSet;
 pub use polymesh::Set;
 pub use polymesh::Sets;
 pub use polymesh::UniformData;
@@ -254,48 +340,353 @@ pub use polymesh::Zone;
 pub use polymesh::ZoneData;
 
 // Containers of smaller pieces of data.
+pub use base::CompactListReader;
 pub use base::FoamField;
 pub use base::FoamFile;
 pub use base::FoamStructure;
 pub use base::FoamValue;
+pub use base::ListItem;
+pub use base::ListReader;
+pub use base::ListWriter;
+pub use polymesh::Band;
+pub use polymesh::CellOrdering;
+pub use polymesh::CoupledKind;
+pub use polymesh::CoupledPatch;
+pub use polymesh::CoupledTransform;
+pub use polymesh::Dimensions;
+pub use polymesh::Exponent;
+pub use polymesh::LineSampling;
+pub use polymesh::MapMethod;
+pub use polymesh::ProbeInterpolation;
+pub use polymesh::SetAction;
+pub use polymesh::SetAxis;
+pub use polymesh::SetConnection;
+pub use polymesh::SetType;
+pub use polymesh::SurfaceTopology;
+pub use polymesh::TopoSetSource;
+pub use polymesh::UNSET_PROBE_VALUE;
 
 /// The Case structure holds the mesh and results found in a case directory.
+/// Multi-region cases (e.g. conjugate heat transfer) store the mesh and results of each region
+/// separately in ```regions```. Such cases usually do not have a mesh in "constant/polyMesh".
 #[derive(Debug, PartialEq, Clone)]
 pub struct Case {
+    pub polymesh: Option<PolyMesh>,
+    pub time_directories: IndexMap<String, TimeDir>,
+    pub region_properties: Option<FileContent<RegionPropertiesData>>,
+    pub regions: IndexMap<String, RegionData>,
+}
+
+/// The RegionData structure holds the mesh and results of a single region of a multi-region case.
+/// The mesh is found in "constant/<region>/polyMesh" and the results in "<time>/<region>/".
+#[derive(Debug, PartialEq, Clone)]
+pub struct RegionData {
     pub polymesh: PolyMesh,
-    pub time_directories: HashMap<String, TimeDir>,
+    pub time_directories: IndexMap<String, TimeDir>,
 }
 
 impl Case {
     /// Parses the case directory and returns a Case struct.
+    /// The regions are read from "constant/regionProperties" if it exists, otherwise every
+    /// directory "constant/<region>" that contains a "polyMesh" directory is considered a region.
     pub fn parse_file(dir_path: &path::Path) -> std::io::Result<Case> {
-        let poly_mesh = PolyMesh::parse(&dir_path.join("constant/polyMesh"))?;
-        let time_directories = numbered_directories(dir_path)?
-            .iter()
-            .map(|entry| {
-                let name = entry.file_name().unwrap().to_str().unwrap().to_string();
-                let time_directory = TimeDir::parse(entry).unwrap();
-                (name, time_directory)
-            })
+        Self::parse_file_with_times(dir_path, &TimeSelector::All)
+    }
+
+    /// Parses the case directory, but only the selected time directories, e.g. the latest one:
+    /// ```
+    /// # use polymesh_rw::*;
+    /// # fn main() -> std::io::Result<()> {
+    /// let case_file_path = std::path::Path::new("tests/test_cases/original/cylinder");
+    /// let case = Case::parse_file_with_times(case_file_path, &TimeSelector::Latest)?;
+    /// # assert_eq!(case.times(), vec![0.0]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn parse_file_with_times(
+        dir_path: &path::Path,
+        selector: &TimeSelector,
+    ) -> std::io::Result<Case> {
+        let mesh_path = dir_path.join("constant/polyMesh");
+        let poly_mesh = if mesh_path.is_dir() {
+            Some(PolyMesh::parse(&mesh_path)?)
+        } else {
+            None
+        };
+        let time_paths = numbered_directories(dir_path)?;
+        let times: Vec<f64> = time_paths.iter().map(|(time, _)| *time).collect();
+        let time_paths: Vec<path::PathBuf> = selector
+            .select(&times)
+            .into_iter()
+            .map(|i| time_paths[i].1.clone())
             .collect();
+        // the time directories are parsed concurrently if the "rayon" feature is enabled
+        let time_directories = base::parallel::map(&time_paths, |entry| {
+            let name = entry.file_name().unwrap().to_str().unwrap().to_string();
+            Ok((name, TimeDir::parse(entry)?))
+        })
+        .into_iter()
+        .collect::<std::io::Result<_>>()?;
+
+        let properties_path = dir_path.join(RegionPropertiesData::default_file_path());
+        let region_properties = if find_file(&properties_path).is_file() {
+            Some(FileContent::<RegionPropertiesData>::parse_file(
+                &properties_path,
+            )?)
+        } else {
+            None
+        };
+        let region_names = match &region_properties {
+            Some(properties) => properties.data.region_names(),
+            None => region_directories(&dir_path.join("constant"))?,
+        };
+        let mut regions = IndexMap::new();
+        for name in region_names {
+            let polymesh =
+                PolyMesh::parse(&dir_path.join("constant").join(&name).join("polyMesh"))?;
+            let mut time_directories = IndexMap::new();
+            for time_path in time_paths.iter().filter(|p| p.join(&name).is_dir()) {
+                let time_name = time_path.file_name().unwrap().to_str().unwrap().to_string();
+                time_directories.insert(time_name, TimeDir::parse_region(time_path, &name)?);
+            }
+            regions.insert(
+                name,
+                RegionData {
+                    polymesh,
+                    time_directories,
+                },
+            );
+        }
+
+        if poly_mesh.is_none() && regions.is_empty() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("No mesh found in case directory {:?}.", dir_path),
+            ));
+        }
         Ok(Case {
             polymesh: poly_mesh,
             time_directories,
+            region_properties,
+            regions,
         })
     }
 
+    /// Creates a new case that only contains the given cells, with all fields mapped onto the new
+    /// mesh. See `PolyMesh::subset` for details. Regions are not part of the new case.
+    pub fn subset(&self, cells: &[usize], exposed_patch: &str) -> std::io::Result<Case> {
+        let subset = self.mesh()?.subset(cells, exposed_patch)?;
+        let mut time_directories = IndexMap::new();
+        for (name, time_directory) in self.time_directories.iter() {
+            time_directories.insert(name.clone(), subset.map_time_dir(time_directory)?);
+        }
+        Ok(Case {
+            polymesh: Some(subset.mesh),
+            time_directories,
+            region_properties: None,
+            regions: IndexMap::new(),
+        })
+    }
+
+    /// Renumbers the cells of the mesh and all fields of the time directories consistently.
+    /// See `PolyMesh::renumber` for details.
+    pub fn renumber(&mut self, ordering: &CellOrdering) -> std::io::Result<MeshRenumbering> {
+        let Some(polymesh) = self.polymesh.as_mut() else {
+            return Err(no_mesh_error());
+        };
+        let renumbering = polymesh.renumber(ordering)?;
+        for time_directory in self.time_directories.values_mut() {
+            renumbering.renumber_time_dir(time_directory)?;
+        }
+        Ok(renumbering)
+    }
+
+    /// Moves boundary faces into the patch `patch` and updates the boundary conditions of all
+    /// fields of the time directories. See `PolyMesh::repatch` for details.
+    /// The mesh and the fields are repatched on copies, so that the case is left unchanged if
+    /// an error occurs.
+    pub fn repatch(&mut self, faces: &[usize], patch: &str) -> std::io::Result<MeshRepatching> {
+        let Some(polymesh) = self.polymesh.as_ref() else {
+            return Err(no_mesh_error());
+        };
+        let mut polymesh = polymesh.clone();
+        let repatching = polymesh.repatch(faces, patch)?;
+        let mut time_directories = self.time_directories.clone();
+        for time_directory in time_directories.values_mut() {
+            repatching.repatch_time_dir(&polymesh, time_directory)?;
+        }
+        self.polymesh = Some(polymesh);
+        self.time_directories = time_directories;
+        Ok(repatching)
+    }
+
+    /// Maps the volume fields of the given time onto another mesh, e.g. a refined mesh, and returns
+    /// them as a time directory of the target mesh. The mesh valid at that time is used as source,
+    /// see `Case::mesh_at`, and the fields are mapped as described in `MeshToMesh::map_field`.
+    pub fn map_fields(
+        &self,
+        target: &PolyMesh,
+        time: f64,
+        method: MapMethod,
+    ) -> std::io::Result<TimeDir> {
+        let time_directory = self.time_directory(time).ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("No time directory found for time {}.", time),
+            )
+        })?;
+        let source = self.mesh_at(time)?;
+        MeshToMesh::new(&source, target, method)?.map_time_dir(time_directory)
+    }
+
+    /// Returns the times of the time directories, in increasing order.
+    pub fn times(&self) -> Vec<f64> {
+        self.time_directories
+            .values()
+            .map(|time_directory| time_directory.time)
+            .collect()
+    }
+
+    /// Returns the time directory of the given time, which is compared with OpenFOAM's tolerance.
+    /// This avoids ambiguities between names like "0.1" and "0.10".
+    pub fn time_directory(&self, time: f64) -> Option<&TimeDir> {
+        self.time_directories
+            .values()
+            .find(|time_directory| same_time(time_directory.time, time))
+    }
+
+    /// Returns the mesh found in "constant/polyMesh", or an error if the case only has regions.
+    pub fn mesh(&self) -> std::io::Result<&PolyMesh> {
+        self.polymesh.as_ref().ok_or_else(no_mesh_error)
+    }
+
+    /// Returns the mesh that is valid at the given time in a dynamic mesh case, following the lookup
+    /// rules of OpenFOAM: the topology is taken from the latest time directory up to the given time
+    /// that contains a complete mesh, or from "constant/polyMesh" if there is none. The points are
+    /// then taken from the latest time directory after that which contains moved points.
+    /// Only the time directories that were parsed are considered.
+    pub fn mesh_at(&self, time: f64) -> std::io::Result<Cow<'_, PolyMesh>> {
+        mesh_at(self.polymesh.as_ref(), &self.time_directories, time)
+    }
+
     /// Writes the case contents to the given directory.
     pub fn write_file(&self, path: &path::Path) -> std::io::Result<()> {
-        self.polymesh.write(path)?;
-        for time_directory in self.time_directories.values() {
-            time_directory.write(path)?;
+        self.write_file_compressed(path, Compression::Uncompressed)
+    }
+
+    /// Writes the case contents to the given directory with the given compression.
+    /// With `Compression::Gzip`, all files get the ".gz" extension, as with OpenFOAM's
+    /// "writeCompression on" setting.
+    pub fn write_file_compressed(
+        &self,
+        path: &path::Path,
+        compression: Compression,
+    ) -> std::io::Result<()> {
+        if let Some(polymesh) = &self.polymesh {
+            polymesh.write_compressed(path, compression)?;
+        }
+        let time_directories: Vec<&TimeDir> = self.time_directories.values().collect();
+        base::parallel::map(&time_directories, |time_directory| {
+            time_directory.write_compressed(path, compression)
+        })
+        .into_iter()
+        .collect::<std::io::Result<()>>()?;
+        if let Some(region_properties) = &self.region_properties {
+            region_properties.write_file_compressed(path, compression)?;
+        }
+        for region in self.regions.values() {
+            region.polymesh.write_compressed(path, compression)?;
+            for time_directory in region.time_directories.values() {
+                time_directory.write_compressed(path, compression)?;
+            }
         }
         Ok(())
     }
 }
 
-/// Returns a list of directories in the provided path whose names are numbers.
-fn numbered_directories(path: &std::path::Path) -> std::io::Result<Vec<std::path::PathBuf>> {
+impl RegionData {
+    /// Returns the mesh of the region that is valid at the given time, see `Case::mesh_at`.
+    pub fn mesh_at(&self, time: f64) -> std::io::Result<Cow<'_, PolyMesh>> {
+        mesh_at(Some(&self.polymesh), &self.time_directories, time)
+    }
+}
+
+/// Finds the mesh that is valid at the given time, see `Case::mesh_at`.
+fn mesh_at<'a>(
+    constant_mesh: Option<&'a PolyMesh>,
+    time_directories: &'a IndexMap<String, TimeDir>,
+    time: f64,
+) -> std::io::Result<Cow<'a, PolyMesh>> {
+    let mut earlier: Vec<&TimeDir> = time_directories
+        .values()
+        .filter(|time_directory| {
+            time_directory.time <= time || same_time(time_directory.time, time)
+        })
+        .collect();
+    earlier.sort_by(|a, b| b.time.total_cmp(&a.time));
+    let topology = earlier
+        .iter()
+        .find_map(|time_directory| match &time_directory.mesh {
+            Some(MeshUpdate::Mesh(mesh)) => Some((time_directory.time, mesh.as_ref())),
+            _ => None,
+        });
+    let (topology_time, mesh) = match topology {
+        Some(topology) => topology,
+        None => (f64::NEG_INFINITY, constant_mesh.ok_or_else(no_mesh_error)?),
+    };
+    let points = earlier
+        .iter()
+        .take_while(|time_directory| time_directory.time > topology_time)
+        .find_map(|time_directory| match &time_directory.mesh {
+            Some(MeshUpdate::Points(points)) => Some(points),
+            _ => None,
+        });
+    match points {
+        Some(points) if points.data.len() != mesh.n_points() => Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!(
+                "{:?} has {} points, but the mesh has {}.",
+                points.location,
+                points.data.len(),
+                mesh.n_points()
+            ),
+        )),
+        Some(points) => Ok(Cow::Owned(PolyMesh {
+            points: points.clone(),
+            ..mesh.clone()
+        })),
+        None => Ok(Cow::Borrowed(mesh)),
+    }
+}
+
+fn no_mesh_error() -> std::io::Error {
+    std::io::Error::new(
+        std::io::ErrorKind::NotFound,
+        "The case has no mesh in constant/polyMesh.",
+    )
+}
+
+/// Returns the names of the directories in "constant" that contain a "polyMesh" directory.
+fn region_directories(constant_path: &path::Path) -> std::io::Result<Vec<String>> {
+    let mut regions = Vec::new();
+    for entry in std::fs::read_dir(constant_path)? {
+        let path = entry?.path();
+        if path.join("polyMesh").is_dir() {
+            let name = path
+                .file_name()
+                .unwrap()
+                .to_str()
+                .expect("Directory name in constant directory is not valid unicode.");
+            regions.push(name.to_string());
+        }
+    }
+    regions.sort();
+    Ok(regions)
+}
+
+/// Returns the directories in the provided path whose names are numbers, with their times,
+/// sorted by time.
+fn numbered_directories(path: &std::path::Path) -> std::io::Result<Vec<(f64, std::path::PathBuf)>> {
     let mut dirs = Vec::new();
     for entry in std::fs::read_dir(path)? {
         let entry = entry?;
@@ -309,9 +700,10 @@ fn numbered_directories(path: &std::path::Path) -> std::io::Result<Vec<std::path
             .unwrap()
             .to_str()
             .expect("File name in case directory is not valid unicode.");
-        if let Ok(_time) = dir_name.parse::<f64>() {
-            dirs.push(path);
+        if let Ok(time) = dir_name.parse::<f64>() {
+            dirs.push((time, path));
         }
     }
+    dirs.sort_by(|(a, _), (b, _)| a.total_cmp(b));
     Ok(dirs)
 }
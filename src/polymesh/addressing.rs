@@ -0,0 +1,98 @@
+use super::PolyMesh;
+
+/// Connectivity queries derived from the owner/neighbour/faces lists.
+impl PolyMesh {
+    /// Returns the number of points in the mesh.
+    pub fn n_points(&self) -> usize {
+        self.points.data.len()
+    }
+
+    /// Returns the number of faces in the mesh, internal and boundary.
+    pub fn n_faces(&self) -> usize {
+        self.faces.data.len()
+    }
+
+    /// Returns the number of internal faces, which are the first faces of the faces list.
+    pub fn n_internal_faces(&self) -> usize {
+        self.neighbour.data.len()
+    }
+
+    /// Returns the number of cells, which is not stored explicitly but given by the highest cell label.
+    pub fn n_cells(&self) -> usize {
+        self.owner
+            .data
+            .iter()
+            .chain(self.neighbour.data.iter())
+            .max()
+            .map_or(0, |max| max + 1)
+    }
+
+    /// Returns the faces of every cell, in increasing face order.
+    pub fn cell_faces(&self) -> Vec<Vec<usize>> {
+        let mut cell_faces = vec![Vec::new(); self.n_cells()];
+        for (face, &owner) in self.owner.data.iter().enumerate() {
+            cell_faces[owner].push(face);
+        }
+        for (face, &neighbour) in self.neighbour.data.iter().enumerate() {
+            cell_faces[neighbour].push(face);
+        }
+        for faces in cell_faces.iter_mut() {
+            faces.sort_unstable();
+        }
+        cell_faces
+    }
+
+    /// Returns the neighbouring cells of every cell, i.e. the cells that share an internal face with it.
+    pub fn cell_cells(&self) -> Vec<Vec<usize>> {
+        let mut cell_cells = vec![Vec::new(); self.n_cells()];
+        for (face, &neighbour) in self.neighbour.data.iter().enumerate() {
+            let owner = self.owner.data[face];
+            cell_cells[owner].push(neighbour);
+            cell_cells[neighbour].push(owner);
+        }
+        cell_cells
+    }
+
+    /// Returns the points of every cell, without duplicates and in increasing order.
+    pub fn cell_points(&self) -> Vec<Vec<usize>> {
+        self.cell_faces()
+            .iter()
+            .map(|faces| {
+                let mut points: Vec<usize> = faces
+                    .iter()
+                    .flat_map(|&face| self.faces.data[face].iter().copied())
+                    .collect();
+                points.sort_unstable();
+                points.dedup();
+                points
+            })
+            .collect()
+    }
+
+    /// Returns the cells that use each point.
+    pub fn point_cells(&self) -> Vec<Vec<usize>> {
+        let mut point_cells = vec![Vec::new(); self.n_points()];
+        for (cell, points) in self.cell_points().into_iter().enumerate() {
+            for point in points {
+                point_cells[point].push(cell);
+            }
+        }
+        point_cells
+    }
+
+    /// Updates the "note" entry of the owner and neighbour headers, which records the mesh size.
+    pub(crate) fn update_note(&mut self) {
+        let note = format!(
+            "\"nPoints: {} nCells: {} nFaces: {} nInternalFaces: {}\"",
+            self.n_points(),
+            self.n_cells(),
+            self.n_faces(),
+            self.n_internal_faces()
+        );
+        for meta in [&mut self.owner.meta, &mut self.neighbour.meta] {
+            if let Some(value) = meta.get_mut("note") {
+                *value = crate::FoamValue::String(note.clone());
+            }
+        }
+    }
+}
@@ -1,6 +1,20 @@
 use crate::base::{parser_base::*, FileElement};
-use crate::base::{FileParser, FoamStructure};
+use crate::base::{FileParser, FoamStructure, FoamValue};
+use indexmap::IndexMap;
 use nom::{character::complete::char, IResult};
+use std::ops::Range;
+
+/// Patch types whose boundary conditions must have the same type, e.g. "empty" or "cyclic".
+pub(crate) const CONSTRAINT_PATCH_TYPES: [&str; 8] = [
+    "empty",
+    "symmetryPlane",
+    "symmetry",
+    "wedge",
+    "cyclic",
+    "cyclicAMI",
+    "cyclicSlip",
+    "processor",
+];
 
 /// The BoundaryData structure holds the data of a polyMesh/boundary file.
 #[derive(Debug, PartialEq, Clone)]
@@ -20,6 +34,71 @@ impl std::ops::DerefMut for BoundaryData {
     }
 }
 
+impl BoundaryData {
+    /// Returns the range of face labels that belong to the given patch.
+    pub fn face_range(&self, name: &str) -> Option<Range<usize>> {
+        let FoamValue::Structure(patch) = self.get(name)? else {
+            return None;
+        };
+        let FoamValue::Integer(start) = patch.get("startFace")? else {
+            return None;
+        };
+        let FoamValue::Integer(n) = patch.get("nFaces")? else {
+            return None;
+        };
+        Some(*start..*start + *n)
+    }
+
+    /// Returns the face ranges of all patches, in the order in which they appear in the file.
+    pub fn face_ranges(&self) -> Vec<(String, Range<usize>)> {
+        self.keys()
+            .filter_map(|name| Some((name.clone(), self.face_range(name)?)))
+            .collect()
+    }
+
+    /// Returns the name of the patch that contains the given face, if any.
+    pub fn patch_of_face(&self, face: usize) -> Option<&str> {
+        self.keys()
+            .find(|name| matches!(self.face_range(name), Some(range) if range.contains(&face)))
+            .map(String::as_str)
+    }
+
+    /// Returns the type of the given patch, e.g. "patch", "wall" or "cyclic".
+    pub fn patch_type(&self, name: &str) -> Option<&str> {
+        let FoamValue::Structure(patch) = self.get(name)? else {
+            return None;
+        };
+        match patch.get("type")? {
+            FoamValue::String(patch_type) => Some(patch_type.as_str()),
+            _ => None,
+        }
+    }
+
+    /// Sets the "startFace" and "nFaces" entries of the given patch.
+    /// If the patch does not exist yet, a new patch of type "patch" is appended.
+    pub fn set_face_range(&mut self, name: &str, range: Range<usize>) {
+        let entry = self
+            .entry(name.to_string())
+            .or_insert_with(|| new_patch("patch"));
+        if let FoamValue::Structure(patch) = entry {
+            patch.insert("nFaces".to_string(), FoamValue::Integer(range.len()));
+            patch.insert("startFace".to_string(), FoamValue::Integer(range.start));
+        }
+    }
+}
+
+/// Creates the entry of an empty patch of the given type.
+pub(crate) fn new_patch(patch_type: &str) -> FoamValue {
+    let mut patch = IndexMap::new();
+    patch.insert(
+        "type".to_string(),
+        FoamValue::String(patch_type.to_string()),
+    );
+    patch.insert("nFaces".to_string(), FoamValue::Integer(0));
+    patch.insert("startFace".to_string(), FoamValue::Integer(0));
+    FoamValue::Structure(FoamStructure(patch))
+}
+
 impl FileParser for BoundaryData {
     fn default_file_path() -> std::path::PathBuf {
         std::path::PathBuf::from("constant/polyMesh/boundary")
@@ -0,0 +1,42 @@
+use crate::base::parser_base::*;
+use crate::base::writer_base::*;
+use crate::base::FileElement;
+use crate::base::FileParser;
+use nom::IResult;
+
+/// The CellToRegionData structure holds the data of a cellToRegion file, which maps each cell to the index of its region.
+#[derive(Debug, PartialEq, Clone)]
+pub struct CellToRegionData(pub Vec<usize>);
+
+impl std::ops::Deref for CellToRegionData {
+    type Target = Vec<usize>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl std::ops::DerefMut for CellToRegionData {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl FileParser for CellToRegionData {
+    fn default_file_path() -> std::path::PathBuf {
+        std::path::PathBuf::from("constant/cellToRegion")
+    }
+}
+
+impl FileElement for CellToRegionData {
+    fn parse(input: &str) -> IResult<&str, CellToRegionData> {
+        let (input, cells) = single_i_data(input)?;
+        Ok((input, CellToRegionData(cells)))
+    }
+}
+
+impl std::fmt::Display for CellToRegionData {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write_single_data(&self.0, f)
+    }
+}
@@ -0,0 +1,572 @@
+use super::boundary::new_patch;
+use super::geometry::face_centre_and_area;
+use super::search::{BoundBox, Octree};
+use super::{BoundaryData, Point, PolyMesh};
+use crate::base::vector::*;
+use crate::base::{FoamStructure, FoamValue};
+
+/// The tolerance of the match of paired faces, relative to the square root of the face area.
+const MATCH_TOLERANCE: f64 = 1e-4;
+
+/// The types of coupled patches.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CoupledKind {
+    /// "cyclic": conformal faces, paired one to one.
+    Cyclic,
+    /// "cyclicAMI": non-conformal faces, coupled by arbitrary mesh interpolation.
+    CyclicAMI,
+    /// "cyclicSlip": a cyclic patch whose fields slip along the faces.
+    CyclicSlip,
+    /// "mappedPatch" or "mappedWall": values sampled from another patch, possibly in another region.
+    Mapped,
+}
+
+impl CoupledKind {
+    fn from_patch_type(patch_type: &str) -> Option<Self> {
+        match patch_type {
+            "cyclic" => Some(CoupledKind::Cyclic),
+            "cyclicAMI" => Some(CoupledKind::CyclicAMI),
+            "cyclicSlip" => Some(CoupledKind::CyclicSlip),
+            "mappedPatch" | "mappedWall" => Some(CoupledKind::Mapped),
+            _ => None,
+        }
+    }
+
+    fn patch_type(&self) -> &'static str {
+        match self {
+            CoupledKind::Cyclic => "cyclic",
+            CoupledKind::CyclicAMI => "cyclicAMI",
+            CoupledKind::CyclicSlip => "cyclicSlip",
+            CoupledKind::Mapped => "mappedPatch",
+        }
+    }
+
+    /// Returns true for the patches whose faces are paired one to one with the neighbour patch.
+    pub fn is_conformal(&self) -> bool {
+        matches!(self, CoupledKind::Cyclic | CoupledKind::CyclicSlip)
+    }
+}
+
+/// The transform that maps the faces of a coupled patch onto the faces of its neighbour patch.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CoupledTransform {
+    /// The patches coincide, e.g. "transform noOrdering" or "coincidentFullMatch".
+    None,
+    /// "transform translational", with the "separationVector" from this patch to the neighbour.
+    Translational { separation: Point },
+    /// "transform rotational" around "rotationAxis" through "rotationCentre". Without
+    /// "rotationAngle" (in degrees), the angle is computed from the normals of the patches.
+    Rotational {
+        axis: Point,
+        centre: Point,
+        angle: Option<f64>,
+    },
+}
+
+/// The typed entries of a coupled patch of the boundary file.
+/// For mapped patches, the neighbour patch is the "samplePatch", and a uniform "offset" is
+/// represented as a translational transform.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CoupledPatch {
+    pub name: String,
+    pub kind: CoupledKind,
+    pub neighbour_patch: Option<String>,
+    pub transform: CoupledTransform,
+    /// The "sampleRegion" of mapped patches.
+    pub sample_region: Option<String>,
+    /// The "sampleMode" of mapped patches, e.g. "nearestPatchFace".
+    pub sample_mode: Option<String>,
+}
+
+/// The entries of the transform, which are replaced by `BoundaryData::set_coupled_patch`.
+const TRANSFORM_KEYS: [&str; 6] = [
+    "rotationAxis",
+    "rotationCentre",
+    "rotationAngle",
+    "separationVector",
+    "offset",
+    "offsetMode",
+];
+
+impl CoupledPatch {
+    fn from_entries(name: &str, entries: &FoamStructure) -> std::io::Result<Option<Self>> {
+        let Some(kind) = string(entries, "type").and_then(CoupledKind::from_patch_type) else {
+            return Ok(None);
+        };
+        let vector_entry = |key: &str| -> std::io::Result<Option<Point>> {
+            match entries.get(key) {
+                None => Ok(None),
+                Some(value) => vector(value).map(Some).ok_or_else(|| {
+                    invalid_data(format!(
+                        "The entry {} of patch {} is not a vector.",
+                        key, name
+                    ))
+                }),
+            }
+        };
+        let required = |key: &str| -> std::io::Result<Point> {
+            vector_entry(key)?
+                .ok_or_else(|| invalid_data(format!("The patch {} has no entry {}.", name, key)))
+        };
+        let transform = if kind == CoupledKind::Mapped {
+            match vector_entry("offset")? {
+                Some(separation) if mag(&separation) > 0.0 => {
+                    CoupledTransform::Translational { separation }
+                }
+                _ => CoupledTransform::None,
+            }
+        } else {
+            match string(entries, "transform") {
+                Some("rotational") => CoupledTransform::Rotational {
+                    axis: required("rotationAxis")?,
+                    centre: vector_entry("rotationCentre")?.unwrap_or_default(),
+                    angle: match entries.get("rotationAngle") {
+                        Some(FoamValue::Float(angle)) => Some(*angle),
+                        Some(FoamValue::Integer(angle)) => Some(*angle as f64),
+                        _ => None,
+                    },
+                },
+                Some("translational") => CoupledTransform::Translational {
+                    separation: required("separationVector")?,
+                },
+                _ => CoupledTransform::None,
+            }
+        };
+        let neighbour_key = match kind {
+            CoupledKind::Mapped => "samplePatch",
+            _ => "neighbourPatch",
+        };
+        Ok(Some(CoupledPatch {
+            name: name.to_string(),
+            kind,
+            neighbour_patch: string(entries, neighbour_key).map(str::to_string),
+            transform,
+            sample_region: string(entries, "sampleRegion").map(str::to_string),
+            sample_mode: string(entries, "sampleMode").map(str::to_string),
+        }))
+    }
+
+    /// Writes the entries of the patch, and removes the entries of other transforms.
+    fn write_entries(&self, entries: &mut FoamStructure) {
+        let is_mapped_type = string(entries, "type")
+            .and_then(CoupledKind::from_patch_type)
+            .is_some_and(|kind| kind == CoupledKind::Mapped);
+        if !(self.kind == CoupledKind::Mapped && is_mapped_type) {
+            entries.insert(
+                "type".to_string(),
+                FoamValue::String(self.kind.patch_type().to_string()),
+            );
+        }
+        for key in TRANSFORM_KEYS {
+            entries.shift_remove(key);
+        }
+        if matches!(
+            string(entries, "transform"),
+            Some("rotational" | "translational")
+        ) {
+            entries.shift_remove("transform");
+        }
+        let mut insert = |key: &str, value: FoamValue| {
+            entries.insert(key.to_string(), value);
+        };
+        let word = |value: &str| FoamValue::String(value.to_string());
+        let vector = |v: &Point| FoamValue::String(format!("({} {} {})", v[0], v[1], v[2]));
+        if self.kind == CoupledKind::Mapped {
+            if let Some(region) = &self.sample_region {
+                insert("sampleRegion", word(region));
+            }
+            if let Some(mode) = &self.sample_mode {
+                insert("sampleMode", word(mode));
+            }
+            if let Some(neighbour) = &self.neighbour_patch {
+                insert("samplePatch", word(neighbour));
+            }
+            if let CoupledTransform::Translational { separation } = &self.transform {
+                insert("offsetMode", word("uniform"));
+                insert("offset", vector(separation));
+            }
+            return;
+        }
+        if let Some(neighbour) = &self.neighbour_patch {
+            insert("neighbourPatch", word(neighbour));
+        }
+        match &self.transform {
+            CoupledTransform::None => {}
+            CoupledTransform::Translational { separation } => {
+                insert("transform", word("translational"));
+                insert("separationVector", vector(separation));
+            }
+            CoupledTransform::Rotational {
+                axis,
+                centre,
+                angle,
+            } => {
+                insert("transform", word("rotational"));
+                insert("rotationAxis", vector(axis));
+                insert("rotationCentre", vector(centre));
+                if let Some(angle) = angle {
+                    insert("rotationAngle", FoamValue::Float(*angle));
+                }
+            }
+        }
+    }
+}
+
+impl BoundaryData {
+    /// Returns the typed entries of a "cyclic", "cyclicAMI", "cyclicSlip" or mapped patch, or None
+    /// if the patch does not exist or has another type.
+    pub fn coupled_patch(&self, name: &str) -> std::io::Result<Option<CoupledPatch>> {
+        match self.get(name) {
+            Some(FoamValue::Structure(entries)) => CoupledPatch::from_entries(name, entries),
+            _ => Ok(None),
+        }
+    }
+
+    /// Returns the typed entries of all coupled patches, see `coupled_patch`.
+    pub fn coupled_patches(&self) -> std::io::Result<Vec<CoupledPatch>> {
+        let mut patches = Vec::new();
+        for name in self.keys() {
+            patches.extend(self.coupled_patch(name)?);
+        }
+        Ok(patches)
+    }
+
+    /// Writes the entries of a coupled patch. The other entries of the patch, e.g. the face range,
+    /// are kept. If the patch does not exist yet, it is appended without faces.
+    pub fn set_coupled_patch(&mut self, patch: &CoupledPatch) {
+        let entry = self
+            .entry(patch.name.clone())
+            .or_insert_with(|| new_patch(patch.kind.patch_type()));
+        if let FoamValue::Structure(entries) = entry {
+            patch.write_entries(entries);
+        }
+    }
+}
+
+impl PolyMesh {
+    /// Pairs the faces of a conformal "cyclic" or "cyclicSlip" patch with the faces of its
+    /// neighbour patch: returns, for each face of the patch, the matching face of the neighbour
+    /// patch (counted from the start of the neighbour patch). OpenFOAM expects the faces to be in
+    /// the same order on both sides, i.e. the identity.
+    /// Returns an error if the patches do not reference each other, have different numbers of
+    /// faces, or if some face has no matching face after the transform, with the same area and
+    /// the opposite normal.
+    pub fn cyclic_pairing(&self, name: &str) -> std::io::Result<Vec<usize>> {
+        let boundary = &self.boundary.data;
+        let patch = boundary
+            .coupled_patch(name)?
+            .filter(|patch| patch.kind.is_conformal())
+            .ok_or_else(|| {
+                std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    format!("The patch {} is not a cyclic patch.", name),
+                )
+            })?;
+        let neighbour = patch
+            .neighbour_patch
+            .as_deref()
+            .ok_or_else(|| invalid_data(format!("The patch {} has no neighbourPatch.", name)))?;
+        let back_reference = boundary
+            .coupled_patch(neighbour)?
+            .and_then(|neighbour| neighbour.neighbour_patch);
+        if back_reference.as_deref() != Some(name) {
+            return Err(invalid_data(format!(
+                "The neighbour patch {} of {} does not reference it.",
+                neighbour, name
+            )));
+        }
+        let (Some(range), Some(neighbour_range)) =
+            (boundary.face_range(name), boundary.face_range(neighbour))
+        else {
+            return Err(invalid_data(format!(
+                "The patch {} or {} has no faces.",
+                name, neighbour
+            )));
+        };
+        if range.len() != neighbour_range.len() {
+            return Err(invalid_data(format!(
+                "The patch {} has {} faces, but its neighbour {} has {}.",
+                name,
+                range.len(),
+                neighbour,
+                neighbour_range.len()
+            )));
+        }
+
+        let geometry = |range: std::ops::Range<usize>| -> (Vec<Point>, Vec<Point>) {
+            range
+                .map(|face| face_centre_and_area(&self.points.data, &self.faces.data[face]))
+                .unzip()
+        };
+        let (centres, areas) = geometry(range);
+        let (neighbour_centres, neighbour_areas) = geometry(neighbour_range);
+        let transform = Transform::new(&patch.transform, &areas, &neighbour_areas);
+        let boxes: Vec<BoundBox> = neighbour_centres
+            .iter()
+            .map(|centre| BoundBox::from_points([centre]))
+            .collect();
+        let tree = Octree::new(&boxes);
+
+        let mut pairing = Vec::with_capacity(centres.len());
+        let mut paired = vec![false; neighbour_centres.len()];
+        for (face, (centre, area)) in centres.iter().zip(&areas).enumerate() {
+            let centre = transform.point(centre);
+            let area = transform.vector(area);
+            let tolerance = MATCH_TOLERANCE * mag(&area).sqrt();
+            let matching = tree
+                .nearest(&centre, |i| mag(&sub(&neighbour_centres[i], &centre)))
+                .filter(|&(i, distance)| {
+                    distance <= tolerance
+                        && mag(&add(&area, &neighbour_areas[i])) <= MATCH_TOLERANCE * mag(&area)
+                });
+            let Some((other, _)) = matching else {
+                return Err(invalid_data(format!(
+                    "The face {} of patch {} has no matching face on patch {}.",
+                    face, name, neighbour
+                )));
+            };
+            if std::mem::replace(&mut paired[other], true) {
+                return Err(invalid_data(format!(
+                    "The face {} of patch {} matches several faces of patch {}.",
+                    other, neighbour, name
+                )));
+            }
+            pairing.push(other);
+        }
+        Ok(pairing)
+    }
+}
+
+/// The transform of points and vectors from a coupled patch to its neighbour.
+struct Transform {
+    rotation: [Point; 3],
+    centre: Point,
+    separation: Point,
+}
+
+impl Transform {
+    fn new(transform: &CoupledTransform, areas: &[Point], neighbour_areas: &[Point]) -> Self {
+        let identity = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
+        match transform {
+            CoupledTransform::None => Transform {
+                rotation: identity,
+                centre: [0.0; 3],
+                separation: [0.0; 3],
+            },
+            CoupledTransform::Translational { separation } => Transform {
+                rotation: identity,
+                centre: [0.0; 3],
+                separation: *separation,
+            },
+            CoupledTransform::Rotational {
+                axis,
+                centre,
+                angle,
+            } => {
+                let axis = scale(axis, 1.0 / mag(axis));
+                let angle = match angle {
+                    Some(angle) => angle.to_radians(),
+                    // the angle that turns the normal of the patch into the opposite of the
+                    // normal of the neighbour, in the plane normal to the axis
+                    None => {
+                        let sum = |areas: &[Point]| {
+                            let total = areas.iter().fold([0.0; 3], |sum, a| add(&sum, a));
+                            sub(&total, &scale(&axis, dot(&total, &axis)))
+                        };
+                        let from = sum(areas);
+                        let to = scale(&sum(neighbour_areas), -1.0);
+                        dot(&cross(&from, &to), &axis).atan2(dot(&from, &to))
+                    }
+                };
+                Transform {
+                    rotation: rotation_matrix(&axis, angle),
+                    centre: *centre,
+                    separation: [0.0; 3],
+                }
+            }
+        }
+    }
+
+    fn vector(&self, v: &Point) -> Point {
+        std::array::from_fn(|i| dot(&self.rotation[i], v))
+    }
+
+    fn point(&self, p: &Point) -> Point {
+        let rotated = add(&self.vector(&sub(p, &self.centre)), &self.centre);
+        add(&rotated, &self.separation)
+    }
+}
+
+/// Returns the matrix of the rotation by `angle` (in radians) around the unit vector `axis`.
+fn rotation_matrix(axis: &Point, angle: f64) -> [Point; 3] {
+    let (sin, cos) = angle.sin_cos();
+    let [x, y, z] = *axis;
+    [
+        [
+            cos + x * x * (1.0 - cos),
+            x * y * (1.0 - cos) - z * sin,
+            x * z * (1.0 - cos) + y * sin,
+        ],
+        [
+            y * x * (1.0 - cos) + z * sin,
+            cos + y * y * (1.0 - cos),
+            y * z * (1.0 - cos) - x * sin,
+        ],
+        [
+            z * x * (1.0 - cos) - y * sin,
+            z * y * (1.0 - cos) + x * sin,
+            cos + z * z * (1.0 - cos),
+        ],
+    ]
+}
+
+fn string<'a>(entries: &'a FoamStructure, key: &str) -> Option<&'a str> {
+    match entries.get(key)? {
+        FoamValue::String(value) => Some(value.as_str()),
+        _ => None,
+    }
+}
+
+/// Reads a vector written as "(x y z)", which is parsed as a string.
+fn vector(value: &FoamValue) -> Option<Point> {
+    let FoamValue::String(value) = value else {
+        return None;
+    };
+    let components: Vec<f64> = value
+        .trim()
+        .strip_prefix('(')?
+        .strip_suffix(')')?
+        .split_whitespace()
+        .map(str::parse)
+        .collect::<Result<_, _>>()
+        .ok()?;
+    components.try_into().ok()
+}
+
+fn invalid_data(message: String) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The channel of the two zones case, with its "bottom" and "top" patches turned into a
+    /// translational cyclic.
+    fn cyclic_mesh() -> (PolyMesh, Point) {
+        let path = std::path::Path::new("tests/test_cases/original/two_zones_with_interfaces");
+        let mut mesh = PolyMesh::parse(&path.join("constant/polyMesh")).unwrap();
+        let centres = mesh.face_centres();
+        let (top, bottom) = (
+            mesh.boundary.data.face_range("top").unwrap(),
+            mesh.boundary.data.face_range("bottom").unwrap(),
+        );
+        let separation = [0.0, centres[top.start][1] - centres[bottom.start][1], 0.0];
+        for (name, neighbour, separation) in [
+            ("bottom", "top", separation),
+            ("top", "bottom", scale(&separation, -1.0)),
+        ] {
+            mesh.boundary.data.set_coupled_patch(&CoupledPatch {
+                name: name.to_string(),
+                kind: CoupledKind::Cyclic,
+                neighbour_patch: Some(neighbour.to_string()),
+                transform: CoupledTransform::Translational { separation },
+                sample_region: None,
+                sample_mode: None,
+            });
+        }
+        (mesh, separation)
+    }
+
+    #[test]
+    fn test_coupled_patch_entries() {
+        let (mesh, separation) = cyclic_mesh();
+        let boundary = &mesh.boundary.data;
+        assert_eq!(boundary.patch_type("bottom"), Some("cyclic"));
+        assert_eq!(boundary.face_range("bottom").unwrap().len(), 36);
+        let bottom = boundary.coupled_patch("bottom").unwrap().unwrap();
+        assert_eq!(bottom.neighbour_patch.as_deref(), Some("top"));
+        assert_eq!(
+            bottom.transform,
+            CoupledTransform::Translational { separation }
+        );
+        assert_eq!(boundary.coupled_patches().unwrap().len(), 2);
+        assert_eq!(boundary.coupled_patch("inlet").unwrap(), None);
+
+        // the entries are written and parsed like the other entries of the boundary file
+        let (_, reparsed) = crate::base::FileElement::parse(&boundary.to_string()).unwrap();
+        let reparsed: BoundaryData = reparsed;
+        assert_eq!(
+            reparsed.coupled_patch("top").unwrap(),
+            boundary.coupled_patch("top").unwrap()
+        );
+
+        let mut rotational = boundary.clone();
+        let patch = CoupledPatch {
+            name: "inlet".to_string(),
+            kind: CoupledKind::CyclicAMI,
+            neighbour_patch: Some("outlet".to_string()),
+            transform: CoupledTransform::Rotational {
+                axis: [0.0, 0.0, 1.0],
+                centre: [1.0, 0.0, 0.0],
+                angle: Some(90.0),
+            },
+            sample_region: None,
+            sample_mode: None,
+        };
+        rotational.set_coupled_patch(&patch);
+        assert_eq!(rotational.coupled_patch("inlet").unwrap(), Some(patch));
+        let mapped = CoupledPatch {
+            name: "metal_in".to_string(),
+            kind: CoupledKind::Mapped,
+            neighbour_patch: Some("interface".to_string()),
+            transform: CoupledTransform::None,
+            sample_region: Some("solid".to_string()),
+            sample_mode: Some("nearestPatchFace".to_string()),
+        };
+        rotational.set_coupled_patch(&mapped);
+        assert_eq!(rotational.coupled_patch("metal_in").unwrap(), Some(mapped));
+    }
+
+    #[test]
+    fn test_cyclic_pairing() {
+        let (mut mesh, separation) = cyclic_mesh();
+        let pairing = mesh.cyclic_pairing("bottom").unwrap();
+        assert_eq!(pairing.len(), 36);
+        let mut sorted = pairing.clone();
+        sorted.sort_unstable();
+        assert_eq!(sorted, (0..36).collect::<Vec<_>>());
+        // the pairing of the neighbour is the inverse
+        let inverse = mesh.cyclic_pairing("top").unwrap();
+        assert!((0..36).all(|face| inverse[pairing[face]] == face));
+        assert!(mesh.cyclic_pairing("inlet").is_err());
+
+        // a wrong transform does not match
+        let mut bottom = mesh.boundary.data.coupled_patch("bottom").unwrap().unwrap();
+        bottom.transform = CoupledTransform::Translational {
+            separation: scale(&separation, 0.5),
+        };
+        mesh.boundary.data.set_coupled_patch(&bottom);
+        assert!(mesh.cyclic_pairing("bottom").is_err());
+    }
+
+    #[test]
+    fn test_rotation() {
+        let rotation = Transform {
+            rotation: rotation_matrix(&[0.0, 0.0, 1.0], std::f64::consts::FRAC_PI_2),
+            centre: [1.0, 0.0, 0.0],
+            separation: [0.0; 3],
+        };
+        let rotated = rotation.point(&[2.0, 0.0, 5.0]);
+        assert!(mag(&sub(&rotated, &[1.0, 1.0, 5.0])) < 1e-12);
+        // the angle is computed from the normals of the patches
+        let transform = CoupledTransform::Rotational {
+            axis: [0.0, 0.0, 2.0],
+            centre: [0.0; 3],
+            angle: None,
+        };
+        let transform = Transform::new(&transform, &[[1.0, 0.0, 0.0]], &[[0.0, 1.0, 0.0]]);
+        let normal = transform.vector(&[1.0, 0.0, 0.0]);
+        assert!(mag(&sub(&normal, &[0.0, -1.0, 0.0])) < 1e-12);
+    }
+}
@@ -0,0 +1,315 @@
+use super::geometry::face_centre_and_area;
+use super::probes::{format_number, ProbeInterpolation};
+use super::surface::vtk_polydata;
+use super::{Point, PolyMesh, ResultData};
+use crate::base::vector::*;
+use crate::base::{FileContent, FoamField};
+use indexmap::IndexMap;
+use std::collections::HashMap;
+
+/// The polygonal surface of the intersection of the cells with a plane, like the "cuttingPlane"
+/// surfaces of OpenFOAM's "surfaces" function object. Each face is the cut of one cell.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CuttingPlane {
+    pub name: String,
+    pub points: Vec<Point>,
+    /// The points of each face, ordered anticlockwise around the normal of the plane.
+    pub faces: Vec<Vec<usize>>,
+    /// The cell cut by each face.
+    pub cells: Vec<usize>,
+    /// The values of the sampled fields on the faces, by field name.
+    pub face_fields: IndexMap<String, FoamField>,
+    /// The values of the sampled fields on the points, by field name.
+    pub point_fields: IndexMap<String, FoamField>,
+    /// The mesh edge of each point, given by its two mesh points and the position along the edge.
+    edges: Vec<(usize, usize, f64)>,
+}
+
+/// A point of the cut: a mesh point on the plane, or the intersection of an edge with the plane.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum CutPoint {
+    Vertex(usize),
+    Edge(usize, usize),
+}
+
+impl PolyMesh {
+    /// Cuts the cells with the plane through `point` with the normal `normal`.
+    /// Mesh points on the plane are counted on the side of the normal, so that each point of the
+    /// cut belongs to a single face of a cell and faces in the plane are cut only once.
+    pub fn cut_plane(&self, name: &str, point: &Point, normal: &Point) -> CuttingPlane {
+        let normal = scale(normal, 1.0 / mag(normal));
+        let mesh_points = &self.points.data;
+        let distances: Vec<f64> = mesh_points
+            .iter()
+            .map(|p| dot(&sub(p, point), &normal))
+            .collect();
+        let mut plane = CuttingPlane {
+            name: name.to_string(),
+            points: Vec::new(),
+            faces: Vec::new(),
+            cells: Vec::new(),
+            face_fields: IndexMap::new(),
+            point_fields: IndexMap::new(),
+            edges: Vec::new(),
+        };
+        let mut indices: HashMap<CutPoint, usize> = HashMap::new();
+        for (cell, faces) in self.cell_faces().iter().enumerate() {
+            // the segments of the cut on each face of the cell
+            let mut segments = Vec::new();
+            for &face in faces {
+                let vertices = &self.faces.data[face];
+                let crossings: Vec<CutPoint> = (0..vertices.len())
+                    .filter_map(|i| {
+                        let (a, b) = (vertices[i], vertices[(i + 1) % vertices.len()]);
+                        match (distances[a] >= 0.0, distances[b] >= 0.0) {
+                            (true, false) if distances[a] == 0.0 => Some(CutPoint::Vertex(a)),
+                            (false, true) if distances[b] == 0.0 => Some(CutPoint::Vertex(b)),
+                            (true, false) | (false, true) => {
+                                Some(CutPoint::Edge(a.min(b), a.max(b)))
+                            }
+                            _ => None,
+                        }
+                    })
+                    .collect();
+                segments.extend(
+                    crossings
+                        .chunks_exact(2)
+                        .filter(|pair| pair[0] != pair[1])
+                        .map(|pair| (pair[0], pair[1])),
+                );
+            }
+            for polygon in chain(segments) {
+                let mut face: Vec<usize> = polygon
+                    .iter()
+                    .map(|cut_point| {
+                        *indices.entry(*cut_point).or_insert_with(|| {
+                            let (a, b, t) = match *cut_point {
+                                CutPoint::Vertex(a) => (a, a, 0.0),
+                                CutPoint::Edge(a, b) => {
+                                    (a, b, distances[a] / (distances[a] - distances[b]))
+                                }
+                            };
+                            let position = add(
+                                &mesh_points[a],
+                                &scale(&sub(&mesh_points[b], &mesh_points[a]), t),
+                            );
+                            plane.points.push(position);
+                            plane.edges.push((a, b, t));
+                            plane.points.len() - 1
+                        })
+                    })
+                    .collect();
+                let (_, area) = face_centre_and_area(&plane.points, &face);
+                if dot(&area, &normal) < 0.0 {
+                    face.reverse();
+                }
+                plane.faces.push(face);
+                plane.cells.push(cell);
+            }
+        }
+        plane
+    }
+}
+
+/// Chains the segments of the cut of a cell into closed polygons. Segments that do not close,
+/// e.g. in degenerate cells, are dropped.
+fn chain(mut segments: Vec<(CutPoint, CutPoint)>) -> Vec<Vec<CutPoint>> {
+    let mut polygons = Vec::new();
+    while let Some((first, mut current)) = segments.pop() {
+        let mut polygon = vec![first];
+        while current != first {
+            polygon.push(current);
+            let Some(next) = segments
+                .iter()
+                .position(|&(a, b)| a == current || b == current)
+            else {
+                break;
+            };
+            let (a, b) = segments.swap_remove(next);
+            current = if a == current { b } else { a };
+        }
+        if current == first && polygon.len() > 2 {
+            polygons.push(polygon);
+        }
+    }
+    polygons
+}
+
+impl CuttingPlane {
+    /// Samples a volume field on the surface, and stores the values under the name of the field.
+    /// With `ProbeInterpolation::Cell`, the faces take the values of their cells. With
+    /// `ProbeInterpolation::CellPoint`, the point values of the mesh are interpolated like in
+    /// `PolyMesh::cell_to_point` and then along the cut edges, giving values on the points.
+    pub fn sample(
+        &mut self,
+        mesh: &PolyMesh,
+        field: &FileContent<ResultData>,
+        interpolation: ProbeInterpolation,
+    ) -> std::io::Result<()> {
+        let name = field.name().ok_or_else(|| {
+            std::io::Error::new(std::io::ErrorKind::InvalidInput, "The field has no name.")
+        })?;
+        let values = &field.data.result;
+        if let Some(n) = values.n_values().filter(|&n| n != mesh.n_cells()) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!(
+                    "The field has {} values, but there are {} cells.",
+                    n,
+                    mesh.n_cells()
+                ),
+            ));
+        }
+        match interpolation {
+            ProbeInterpolation::Cell => {
+                self.face_fields
+                    .insert(name.to_string(), values.subset(&self.cells));
+            }
+            ProbeInterpolation::CellPoint => {
+                let point_values = mesh.cell_to_point(field)?.data.result;
+                let interpolated = self
+                    .edges
+                    .iter()
+                    .map(|&(a, b, t)| {
+                        point_values
+                            .value(a)
+                            .iter()
+                            .zip(point_values.value(b))
+                            .map(|(a, b)| a + t * (b - a))
+                            .collect()
+                    })
+                    .collect();
+                self.point_fields
+                    .insert(name.to_string(), FoamField::from_values(interpolated));
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes the surface like OpenFOAM's "raw" surface writer: the face fields at the face
+    /// centres and the point fields at the points, one line per location.
+    pub fn write_raw(&self, path: &std::path::Path) -> std::io::Result<()> {
+        std::fs::write(path, self.raw())
+    }
+
+    /// Writes the surface as a legacy VTK file of polygons, with the face fields as cell data and
+    /// the point fields as point data.
+    pub fn write_vtk(&self, path: &std::path::Path) -> std::io::Result<()> {
+        std::fs::write(path, self.vtk())
+    }
+
+    fn raw(&self) -> String {
+        let mut raw = String::new();
+        let face_centres: Vec<Point> = self
+            .faces
+            .iter()
+            .map(|face| face_centre_and_area(&self.points, face).0)
+            .collect();
+        for (locations, fields) in [
+            (&face_centres, &self.face_fields),
+            (&self.points, &self.point_fields),
+        ] {
+            if fields.is_empty() {
+                continue;
+            }
+            let mut columns = vec!["x".to_string(), "y".to_string(), "z".to_string()];
+            for (name, values) in fields {
+                match values.n_components() {
+                    1 => columns.push(name.clone()),
+                    n => columns.extend((0..n).map(|i| format!("{}_{}", name, i))),
+                }
+            }
+            raw.push_str(&format!("# {}\n", columns.join(" ")));
+            for (i, location) in locations.iter().enumerate() {
+                let mut row = location.to_vec();
+                for values in fields.values() {
+                    row.extend(values.value(i));
+                }
+                let row: Vec<String> = row.into_iter().map(format_number).collect();
+                raw.push_str(&row.join(" "));
+                raw.push('\n');
+            }
+        }
+        raw
+    }
+
+    fn vtk(&self) -> String {
+        vtk_polydata(
+            &self.name,
+            &self.points,
+            &self.faces,
+            &self.face_fields,
+            &self.point_fields,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cut_plane() {
+        let path = std::path::Path::new("tests/test_cases/original/cylinder");
+        let mesh = PolyMesh::parse(&path.join("constant/polyMesh")).unwrap();
+        let centres = mesh.cell_centres();
+        let origin = centres[0];
+        let plane = mesh.cut_plane("zNormal", &origin, &[0.0, 0.0, 1.0]);
+        // the 2D mesh is cut through every cell
+        assert_eq!(plane.faces.len(), mesh.n_cells());
+        let area: f64 = plane
+            .faces
+            .iter()
+            .map(|face| {
+                dot(
+                    &face_centre_and_area(&plane.points, face).1,
+                    &[0.0, 0.0, 1.0],
+                )
+            })
+            .sum();
+        // the area of the cut is the volume of the mesh divided by its thickness
+        let thickness = 2.0 * (mesh.points.data[0][2] - origin[2]).abs();
+        let volume: f64 = mesh.cell_volumes().iter().sum();
+        assert!((area * thickness - volume).abs() < 1e-9 * volume);
+        assert!(plane
+            .points
+            .iter()
+            .all(|p| (p[2] - origin[2]).abs() < 1e-12));
+
+        let mut u = FileContent::<ResultData>::parse_file(&path.join("0/U")).unwrap();
+        let mut plane = mesh.cut_plane("xNormal", &origin, &[1.0, 0.0, 0.0]);
+        assert!(!plane.faces.is_empty() && plane.faces.len() < mesh.n_cells());
+        plane.sample(&mesh, &u, ProbeInterpolation::Cell).unwrap();
+        u.meta.insert(
+            "object".to_string(),
+            crate::FoamValue::String("x".to_string()),
+        );
+        u.data.result = FoamField::Scalar(centres.iter().map(|c| c[0]).collect());
+        u.data.boundary_field = None;
+        plane
+            .sample(&mesh, &u, ProbeInterpolation::CellPoint)
+            .unwrap();
+        let vtk = plane.vtk();
+        assert!(vtk.contains(&format!(
+            "CELL_DATA {}\nFIELD attributes 1\nU 3 ",
+            plane.faces.len()
+        )));
+        assert!(vtk.contains(&format!(
+            "POINT_DATA {}\nFIELD attributes 1\nx 1 ",
+            plane.points.len()
+        )));
+        let raw = plane.raw();
+        assert_eq!(
+            raw.lines().count(),
+            plane.faces.len() + plane.points.len() + 2
+        );
+    }
+
+    #[test]
+    fn test_chain() {
+        let [a, b, c] = [0, 1, 2].map(CutPoint::Vertex);
+        let polygons = chain(vec![(a, b), (c, a), (b, c)]);
+        assert_eq!(polygons.len(), 1);
+        assert_eq!(polygons[0].len(), 3);
+    }
+}
@@ -0,0 +1,404 @@
+use crate::base::{parser_base::*, FileElement};
+use nom::{
+    bytes::complete::{tag, take_until},
+    character::complete::char,
+    combinator::{map_res, recognize},
+    sequence::{delimited, preceded, terminated},
+    IResult,
+};
+use std::ops::{Add, Deref, Div, Mul, Neg, Sub};
+
+/// The names of the base units, in the order of the exponents of OpenFOAM's dimension sets:
+/// mass, length, time, temperature, amount of substance, current and luminous intensity.
+const BASE_UNITS: [&str; 7] = ["kg", "m", "s", "K", "mol", "A", "cd"];
+
+/// Derived units that are accepted when parsing named units, with their base unit exponents.
+const DERIVED_UNITS: [(&str, [i32; 7]); 4] = [
+    ("N", [1, 1, -2, 0, 0, 0, 0]),
+    ("Pa", [1, -1, -2, 0, 0, 0, 0]),
+    ("J", [1, 2, -2, 0, 0, 0, 0]),
+    ("W", [1, 2, -3, 0, 0, 0, 0]),
+];
+
+/// Exponents written as decimal numbers are converted to fractions with at most this denominator.
+const MAX_DENOMINATOR: i32 = 100;
+
+/// A rational exponent of a base unit, e.g. 1/2 for the square root of a length.
+/// The fraction is always reduced, with a positive denominator.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub struct Exponent {
+    numerator: i32,
+    denominator: i32,
+}
+
+impl Exponent {
+    /// Creates the exponent `numerator / denominator`.
+    /// # Panics
+    /// If the denominator is zero.
+    pub fn new(numerator: i32, denominator: i32) -> Self {
+        assert!(denominator != 0, "The denominator of an exponent is zero.");
+        let divisor = gcd(numerator, denominator) * denominator.signum();
+        Exponent {
+            numerator: numerator / divisor,
+            denominator: denominator / divisor,
+        }
+    }
+
+    /// Converts a decimal number to the nearest fraction with a small denominator,
+    /// e.g. 0.333333 to 1/3. Returns None if there is no such fraction.
+    pub fn from_f64(value: f64) -> Option<Self> {
+        (1..=MAX_DENOMINATOR).find_map(|denominator| {
+            let numerator = (value * denominator as f64).round();
+            let exact = (value * denominator as f64 - numerator).abs() < 1e-5;
+            (exact && numerator.abs() <= i32::MAX as f64)
+                .then(|| Exponent::new(numerator as i32, denominator))
+        })
+    }
+
+    pub fn numerator(&self) -> i32 {
+        self.numerator
+    }
+
+    pub fn denominator(&self) -> i32 {
+        self.denominator
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.numerator == 0
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        self.numerator as f64 / self.denominator as f64
+    }
+}
+
+fn gcd(a: i32, b: i32) -> i32 {
+    let (mut a, mut b) = (a.abs(), b.abs());
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a.max(1)
+}
+
+impl Default for Exponent {
+    fn default() -> Self {
+        Exponent::from(0)
+    }
+}
+
+impl From<i32> for Exponent {
+    fn from(value: i32) -> Self {
+        Exponent::new(value, 1)
+    }
+}
+
+impl Add for Exponent {
+    type Output = Exponent;
+
+    fn add(self, other: Exponent) -> Exponent {
+        Exponent::new(
+            self.numerator * other.denominator + other.numerator * self.denominator,
+            self.denominator * other.denominator,
+        )
+    }
+}
+
+impl Sub for Exponent {
+    type Output = Exponent;
+
+    fn sub(self, other: Exponent) -> Exponent {
+        self + -other
+    }
+}
+
+impl Neg for Exponent {
+    type Output = Exponent;
+
+    fn neg(self) -> Exponent {
+        Exponent::new(-self.numerator, self.denominator)
+    }
+}
+
+impl Mul for Exponent {
+    type Output = Exponent;
+
+    fn mul(self, other: Exponent) -> Exponent {
+        Exponent::new(
+            self.numerator * other.numerator,
+            self.denominator * other.denominator,
+        )
+    }
+}
+
+/// Integers are written as such, fractions as decimal numbers like in OpenFOAM, e.g. "0.5".
+impl std::fmt::Display for Exponent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.denominator == 1 {
+            write!(f, "{}", self.numerator)
+        } else {
+            write!(f, "{}", self.to_f64())
+        }
+    }
+}
+
+/// The physical dimensions of a quantity, as the exponents of the seven base units of OpenFOAM:
+/// [kg m s K mol A cd]. Exponents can be fractions.
+/// Dimensions are written either as the list of exponents, e.g. "[0 2 -2 0 0 0 0]", or with named
+/// units, e.g. "[m^2 s^-2]". Both formats are parsed, and the format is kept for writing.
+/// The alternate format "{:#}" always prints the named units.
+/// ```
+/// # use polymesh_rw::*;
+/// let nu: Dimensions = "[0 2 -1 0 0 0 0]".parse().unwrap();
+/// assert_eq!(nu, "[m^2 s^-1]".parse().unwrap());
+/// assert_eq!(format!("{:#}", nu), "[m^2 s^-1]");
+/// assert_eq!(format!("{}", nu.pow(2)), "[0 4 -2 0 0 0 0]");
+/// ```
+#[derive(Debug, Clone)]
+pub struct Dimensions {
+    exponents: [Exponent; 7],
+    // whether the dimensions are written with named units
+    named: bool,
+}
+
+impl Dimensions {
+    /// Creates dimensions from integer exponents.
+    pub fn new(dimensions: [i32; 7]) -> Self {
+        Self::from_exponents(dimensions.map(Exponent::from))
+    }
+
+    /// Creates dimensions from rational exponents.
+    pub fn from_exponents(exponents: [Exponent; 7]) -> Self {
+        Dimensions {
+            exponents,
+            named: false,
+        }
+    }
+
+    /// Returns the dimensions of dimensionless quantities.
+    pub fn dimensionless() -> Self {
+        Self::new([0; 7])
+    }
+
+    /// Returns true if all exponents are zero.
+    pub fn is_dimensionless(&self) -> bool {
+        self.exponents.iter().all(Exponent::is_zero)
+    }
+
+    /// Returns the dimensions of a quantity raised to the given power.
+    pub fn pow<E: Into<Exponent>>(&self, exponent: E) -> Self {
+        let exponent = exponent.into();
+        self.map(|d| d * exponent)
+    }
+
+    /// Returns the dimensions of the square root of a quantity.
+    pub fn sqrt(&self) -> Self {
+        self.pow(Exponent::new(1, 2))
+    }
+
+    /// Returns true if the dimensions are written with named units.
+    pub fn uses_named_units(&self) -> bool {
+        self.named
+    }
+
+    /// Chooses between named units, e.g. "[m^2 s^-2]", and the list of exponents for writing.
+    pub fn set_named_units(&mut self, named: bool) {
+        self.named = named;
+    }
+
+    fn map(&self, f: impl Fn(Exponent) -> Exponent) -> Self {
+        Dimensions {
+            exponents: self.exponents.map(f),
+            named: self.named,
+        }
+    }
+
+    fn zip(&self, other: &Dimensions, f: impl Fn(Exponent, Exponent) -> Exponent) -> Self {
+        Dimensions {
+            exponents: std::array::from_fn(|i| f(self.exponents[i], other.exponents[i])),
+            named: self.named,
+        }
+    }
+
+    /// Parses the dimensions in brackets, e.g. "[0 2 -2 0 0 0 0]".
+    fn parse_data(input: &str) -> IResult<&str, Self> {
+        map_res(
+            recognize(delimited(char('['), take_until("]"), char(']'))),
+            str::parse,
+        )(input)
+    }
+
+    /// Parses the list of exponents. OpenFOAM also accepts the first five exponents only.
+    fn parse_exponents(tokens: &[&str]) -> Option<Self> {
+        if tokens.len() != 5 && tokens.len() != 7 {
+            return None;
+        }
+        let mut exponents = [Exponent::default(); 7];
+        for (exponent, token) in exponents.iter_mut().zip(tokens) {
+            *exponent = Exponent::from_f64(token.parse().ok()?)?;
+        }
+        Some(Self::from_exponents(exponents))
+    }
+
+    /// Parses named units, e.g. "m^2 s^-2" or "kg/m^3". Units after a "/" are inverted.
+    fn parse_units(tokens: &[&str]) -> Option<Self> {
+        let mut exponents = [Exponent::default(); 7];
+        let mut sign = Exponent::from(1);
+        for &token in tokens {
+            if token == "/" {
+                sign = Exponent::from(-1);
+                continue;
+            }
+            let (name, power) = match token.split_once('^') {
+                Some((name, power)) => (name, Exponent::from_f64(power.parse().ok()?)?),
+                None => (token, Exponent::from(1)),
+            };
+            let unit = match BASE_UNITS.iter().position(|&base| base == name) {
+                Some(i) => std::array::from_fn(|j| i32::from(i == j)),
+                None => DERIVED_UNITS.iter().find(|(n, _)| *n == name)?.1,
+            };
+            for (exponent, u) in exponents.iter_mut().zip(unit) {
+                *exponent = *exponent + Exponent::from(u) * power * sign;
+            }
+        }
+        Some(Dimensions {
+            exponents,
+            named: true,
+        })
+    }
+}
+
+impl Deref for Dimensions {
+    type Target = [Exponent; 7];
+
+    fn deref(&self) -> &Self::Target {
+        &self.exponents
+    }
+}
+
+/// Dimensions are equal if their exponents are equal, whatever the format used for writing.
+impl PartialEq for Dimensions {
+    fn eq(&self, other: &Self) -> bool {
+        self.exponents == other.exponents
+    }
+}
+
+/// The dimensions of a product: the exponents are added.
+impl Mul for &Dimensions {
+    type Output = Dimensions;
+
+    fn mul(self, other: &Dimensions) -> Dimensions {
+        self.zip(other, |a, b| a + b)
+    }
+}
+
+/// The dimensions of a quotient: the exponents are subtracted.
+impl Div for &Dimensions {
+    type Output = Dimensions;
+
+    fn div(self, other: &Dimensions) -> Dimensions {
+        self.zip(other, |a, b| a - b)
+    }
+}
+
+impl std::str::FromStr for Dimensions {
+    type Err = std::io::Error;
+
+    /// Parses dimensions in brackets, either as exponents or as named units.
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("Invalid dimensions \"{}\".", input),
+            )
+        };
+        let content = input
+            .trim()
+            .strip_prefix('[')
+            .and_then(|content| content.strip_suffix(']'))
+            .ok_or_else(invalid)?
+            .replace('/', " / ");
+        let tokens: Vec<&str> = content.split_whitespace().collect();
+        let numeric = tokens
+            .first()
+            .is_some_and(|token| token.parse::<f64>().is_ok());
+        if numeric {
+            Self::parse_exponents(&tokens)
+        } else {
+            Self::parse_units(&tokens)
+        }
+        .ok_or_else(invalid)
+    }
+}
+
+impl FileElement for Dimensions {
+    fn parse(input: &str) -> IResult<&str, Self> {
+        preceded(
+            next(tag("dimensions")),
+            terminated(next(Self::parse_data), next(char(';'))),
+        )(input)
+    }
+}
+
+impl std::fmt::Display for Dimensions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "[")?;
+        if self.named || f.alternate() {
+            let units: Vec<String> = BASE_UNITS
+                .iter()
+                .zip(self.exponents)
+                .filter(|(_, exponent)| !exponent.is_zero())
+                .map(|(unit, exponent)| match exponent == Exponent::from(1) {
+                    true => unit.to_string(),
+                    false => format!("{}^{}", unit, exponent),
+                })
+                .collect();
+            write!(f, "{}", units.join(" "))?;
+        } else {
+            let exponents: Vec<String> = self.exponents.iter().map(Exponent::to_string).collect();
+            write!(f, "{}", exponents.join(" "))?;
+        }
+        write!(f, "]")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_dimensions() {
+        let (_, dimensions) = Dimensions::parse("dimensions      [0 2 -2 0 0 0 0];").unwrap();
+        assert_eq!(dimensions, Dimensions::new([0, 2, -2, 0, 0, 0, 0]));
+        assert_eq!(dimensions.to_string(), "[0 2 -2 0 0 0 0]");
+
+        let (_, named) = Dimensions::parse("dimensions [kg/m^3];").unwrap();
+        assert!(named.uses_named_units());
+        assert_eq!(named, Dimensions::new([1, -3, 0, 0, 0, 0, 0]));
+        assert_eq!(named.to_string(), "[kg m^-3]");
+
+        let pressure: Dimensions = "[Pa]".parse().unwrap();
+        assert_eq!(&pressure / &named, dimensions);
+        assert!("[0 0]".parse::<Dimensions>().is_err());
+        assert!("[furlong]".parse::<Dimensions>().is_err());
+        assert!("[]".parse::<Dimensions>().unwrap().is_dimensionless());
+        assert_eq!(
+            "[0 1 0 0 0]".parse::<Dimensions>().unwrap(),
+            Dimensions::new([0, 1, 0, 0, 0, 0, 0])
+        );
+    }
+
+    #[test]
+    fn test_rational_dimensions() {
+        let length = Dimensions::new([0, 1, 0, 0, 0, 0, 0]);
+        let root = length.sqrt();
+        assert_eq!(root[1], Exponent::new(1, 2));
+        assert_eq!(root.to_string(), "[0 0.5 0 0 0 0 0]");
+        assert_eq!(format!("{:#}", root), "[m^0.5]");
+        assert_eq!(&root * &root, length);
+        assert_eq!(root.to_string().parse::<Dimensions>().unwrap(), root);
+
+        let third: Dimensions = "[m^0.333333]".parse().unwrap();
+        assert_eq!(third.pow(3), length);
+        assert_eq!(Exponent::new(2, -4), Exponent::new(-1, 2));
+    }
+}
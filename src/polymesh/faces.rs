@@ -1,27 +1,215 @@
+use super::FileContent;
+use crate::base::parallel::{parse_list, ListCollection};
 use crate::base::parser_base::*;
 use crate::base::writer_base::*;
 use crate::base::FileElement;
 use crate::base::FileParser;
+use crate::base::FoamValue;
 use nom::{bytes::complete::tag, character::complete::digit1, multi::count, IResult};
 
 /// The FaceData structure holds the data of a polyMesh/faces file.
-#[derive(Debug, PartialEq, Clone)]
-pub struct FaceData(
-    // each faces ist just a list of point numbers
-    pub Vec<Vec<usize>>,
-);
+/// The faces are stored in compressed sparse row format, like OpenFOAM's faceCompactList:
+/// the vertices of all faces are stored in a single list, and the offsets give the start of each
+/// face in that list, followed by the total number of vertices.
+/// Both the "faceList" and the "faceCompactList" file formats can be read and written.
+#[derive(Debug, Clone)]
+pub struct FaceData {
+    offsets: Vec<usize>,
+    vertices: Vec<usize>,
+    // whether the file is written in the "faceCompactList" format
+    compact: bool,
+}
+
+impl FaceData {
+    /// Creates the face data from the offsets and the vertices of a faceCompactList.
+    /// Returns an error if the offsets are not increasing from zero to the number of vertices.
+    pub fn from_compact(mut offsets: Vec<usize>, vertices: Vec<usize>) -> std::io::Result<Self> {
+        // an empty list may be written without the single offset
+        if offsets.is_empty() && vertices.is_empty() {
+            offsets.push(0);
+        }
+        let valid = offsets.first() == Some(&0)
+            && offsets.last() == Some(&vertices.len())
+            && offsets.windows(2).all(|w| w[0] <= w[1]);
+        if !valid {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "The offsets of the faceCompactList do not match the vertices.",
+            ));
+        }
+        Ok(FaceData {
+            offsets,
+            vertices,
+            compact: true,
+        })
+    }
+
+    /// Returns the number of faces.
+    pub fn len(&self) -> usize {
+        self.offsets.len() - 1
+    }
+
+    /// Returns true if there are no faces.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the vertices of the given face, or None if the face does not exist.
+    pub fn get(&self, face: usize) -> Option<&[usize]> {
+        if face < self.len() {
+            Some(&self.vertices[self.offsets[face]..self.offsets[face + 1]])
+        } else {
+            None
+        }
+    }
+
+    /// Returns an iterator over the vertices of all faces.
+    pub fn iter(&self) -> FaceIter<'_> {
+        FaceIter {
+            faces: self,
+            range: 0..self.len(),
+        }
+    }
+
+    /// Appends a face.
+    pub fn push(&mut self, face: &[usize]) {
+        self.vertices.extend_from_slice(face);
+        self.offsets.push(self.vertices.len());
+    }
+
+    /// Returns the start of each face in the list of vertices, followed by the number of vertices.
+    pub fn offsets(&self) -> &[usize] {
+        &self.offsets
+    }
+
+    /// Returns the vertices of all faces, one face after the other.
+    pub fn vertices(&self) -> &[usize] {
+        &self.vertices
+    }
+
+    /// Returns true if the faces are written in the "faceCompactList" format.
+    pub fn is_compact(&self) -> bool {
+        self.compact
+    }
+
+    /// Chooses between the "faceCompactList" and "faceList" formats for writing.
+    /// The class in the header of the file needs to match, see `FileContent::<FaceData>::set_compact`.
+    pub fn set_compact(&mut self, compact: bool) {
+        self.compact = compact;
+    }
+
+    /// Returns the faces as one vector of vertices per face.
+    pub fn to_vec(&self) -> Vec<Vec<usize>> {
+        self.iter().map(<[usize]>::to_vec).collect()
+    }
+}
+
+impl Default for FaceData {
+    fn default() -> Self {
+        FaceData {
+            offsets: vec![0],
+            vertices: Vec::new(),
+            compact: false,
+        }
+    }
+}
+
+/// Two face lists are equal if they hold the same faces, whichever format they are written in.
+impl PartialEq for FaceData {
+    fn eq(&self, other: &Self) -> bool {
+        self.offsets == other.offsets && self.vertices == other.vertices
+    }
+}
+
+impl std::ops::Index<usize> for FaceData {
+    type Output = [usize];
+
+    fn index(&self, face: usize) -> &Self::Output {
+        &self.vertices[self.offsets[face]..self.offsets[face + 1]]
+    }
+}
+
+impl<F: AsRef<[usize]>> FromIterator<F> for FaceData {
+    fn from_iter<I: IntoIterator<Item = F>>(faces: I) -> Self {
+        let mut data = FaceData::default();
+        for face in faces {
+            data.push(face.as_ref());
+        }
+        data
+    }
+}
+
+impl From<Vec<Vec<usize>>> for FaceData {
+    fn from(faces: Vec<Vec<usize>>) -> Self {
+        faces.into_iter().collect()
+    }
+}
+
+impl ListCollection<Vec<usize>> for FaceData {
+    fn push_entry(&mut self, face: Vec<usize>) {
+        self.push(&face);
+    }
+
+    fn append_entries(&mut self, other: Self) {
+        let n_vertices = self.vertices.len();
+        self.offsets
+            .extend(other.offsets[1..].iter().map(|offset| offset + n_vertices));
+        self.vertices.extend(other.vertices);
+    }
+
+    fn n_entries(&self) -> usize {
+        self.len()
+    }
+}
+
+impl<'a> IntoIterator for &'a FaceData {
+    type Item = &'a [usize];
+    type IntoIter = FaceIter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// An iterator over the vertices of the faces of a `FaceData` structure.
+#[derive(Debug, Clone)]
+pub struct FaceIter<'a> {
+    faces: &'a FaceData,
+    range: std::ops::Range<usize>,
+}
+
+impl<'a> Iterator for FaceIter<'a> {
+    type Item = &'a [usize];
 
-impl std::ops::Deref for FaceData {
-    type Target = Vec<Vec<usize>>;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.range.next().map(|face| &self.faces[face])
+    }
 
-    fn deref(&self) -> &Self::Target {
-        &self.0
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.range.size_hint()
     }
 }
 
-impl std::ops::DerefMut for FaceData {
-    fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.0
+impl DoubleEndedIterator for FaceIter<'_> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.range.next_back().map(|face| &self.faces[face])
+    }
+}
+
+impl ExactSizeIterator for FaceIter<'_> {}
+
+impl FileContent<FaceData> {
+    /// Chooses between the "faceCompactList" and "faceList" formats for writing,
+    /// and updates the class in the header accordingly.
+    pub fn set_compact(&mut self, compact: bool) {
+        self.data.set_compact(compact);
+        let class = if compact {
+            "faceCompactList"
+        } else {
+            "faceList"
+        };
+        self.meta
+            .insert("class".to_string(), FoamValue::String(class.to_string()));
     }
 }
 
@@ -44,23 +232,53 @@ fn parse_face(input: &str) -> IResult<&str, Vec<usize>> {
     Ok((input, vertices))
 }
 
+/// Parses the "faceList" format, with the vertices of each face in parentheses.
+fn parse_face_list(input: &str) -> IResult<&str, FaceData> {
+    // Parse the number of faces.
+    let (input, n_faces) = next(usize_val)(input)?;
+    let (input, _) = next(tag("("))(input)?;
+    // Parse exactly this many faces.
+    let (input, faces): (_, FaceData) = parse_list(input, n_faces, parse_face)?;
+    // If the number of faces was accurate this schould work:
+    let (input, _) = next(tag(")"))(input)?;
+    // Return the new data structure
+    Ok((input, faces))
+}
+
+/// Parses the "faceCompactList" format, made of the list of offsets and the list of vertices.
+fn parse_face_compact_list(input: &str) -> IResult<&str, FaceData> {
+    let (input, offsets) = single_i_data(input)?;
+    let (input, vertices) = single_i_data(input)?;
+    match FaceData::from_compact(offsets, vertices) {
+        Ok(faces) => Ok((input, faces)),
+        Err(_) => Err(nom::Err::Failure(nom::error::Error::new(
+            input,
+            nom::error::ErrorKind::Verify,
+        ))),
+    }
+}
+
 impl FileElement for FaceData {
     fn parse(input: &str) -> IResult<&str, FaceData> {
-        // Parse the number of faces.
-        let (input, n_faces) = next(usize_val)(input)?;
-        let (input, _) = next(tag("("))(input)?;
-        // Parse exactly this many faces.
-        let (input, faces) = count(next(parse_face), n_faces)(input)?;
-        // If the number of faces was accurate this schould work:
-        let (input, _) = next(tag(")"))(input)?;
-        // Return the new data structure
-        Ok((input, FaceData(faces)))
+        // The entries of a faceCompactList are plain labels, which fails quickly on a faceList.
+        match parse_face_compact_list(input) {
+            Ok(result) => Ok(result),
+            Err(nom::Err::Error(_)) => parse_face_list(input),
+            Err(e) => Err(e),
+        }
     }
 }
 
 impl std::fmt::Display for FaceData {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write_multi_data(&self.0, f)
+        if self.compact {
+            write_single_data(&self.offsets, f)?;
+            writeln!(f)?;
+            writeln!(f)?;
+            write_single_data(&self.vertices, f)
+        } else {
+            write_multi_data(self.iter(), f)
+        }
     }
 }
 
@@ -77,7 +295,7 @@ mod tests {
 3(42 84 113)
 3(42 113 92)
 )";
-        let expected_value = FaceData(vec![
+        let expected_value = FaceData::from(vec![
             vec![42, 92, 84],
             vec![113, 84, 92],
             vec![42, 84, 113],
@@ -86,4 +304,48 @@ mod tests {
         let (_, actual_value) = FaceData::parse(input).unwrap();
         assert_eq!(expected_value, actual_value);
     }
+
+    #[test]
+    fn test_parse_compact_faces() {
+        let input = "
+3
+(
+0
+3
+7
+)
+
+
+7
+(
+0 1 2
+3 4 5 6
+)";
+        let (_, faces) = FaceData::parse(input).unwrap();
+        assert!(faces.is_compact());
+        assert_eq!(faces.len(), 2);
+        assert_eq!(&faces[1], &[3, 4, 5, 6]);
+        assert_eq!(faces.to_vec(), vec![vec![0, 1, 2], vec![3, 4, 5, 6]]);
+        let (_, reparsed) = FaceData::parse(&faces.to_string()).unwrap();
+        assert_eq!(faces, reparsed);
+
+        let invalid = input.replace("7\n)", "8\n)");
+        assert!(FaceData::parse(&invalid).is_err());
+    }
+
+    #[test]
+    fn test_convert_face_formats() {
+        // long enough to be parsed in chunks with the "rayon" feature
+        let faces: FaceData = (0..12_000).map(|i| vec![i, i + 1, i + 2, i + 3]).collect();
+        let (_, reparsed) = FaceData::parse(&faces.to_string()).unwrap();
+        assert_eq!(faces, reparsed);
+        assert_eq!(&reparsed[11_999], &[11_999, 12_000, 12_001, 12_002]);
+
+        let mut compact = faces.clone();
+        compact.set_compact(true);
+        let (_, reparsed) = FaceData::parse(&compact.to_string()).unwrap();
+        assert!(reparsed.is_compact());
+        // the format does not take part in the comparison
+        assert_eq!(reparsed, faces);
+    }
 }
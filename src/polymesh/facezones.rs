@@ -2,7 +2,15 @@ use super::Zone;
 use crate::base::parser_base::*;
 use crate::base::writer_base::*;
 use crate::base::FileElement;
-use nom::{bytes::complete::tag, character::complete::char, sequence::delimited, IResult};
+use nom::{
+    branch::alt,
+    bytes::complete::tag,
+    character::complete::char,
+    combinator::map,
+    multi::count,
+    sequence::{delimited, terminated},
+    IResult,
+};
 
 /// Container for the data of a single faceZone.
 #[derive(Debug, PartialEq, Clone)]
@@ -10,9 +18,9 @@ pub struct FaceZone {
     // starts with a "type" which I have only seen as "faceZone", so I'm not storing it for now
     pub name: String,
     pub faces: Vec<usize>,
-    // no idea what flipmap is does and what forms it can take, only seen one example so far
-    // maybe even useless : https://gitlab.kitware.com/vtk/vtk/-/issues/17103
-    pub flipmap: bool,
+    /// For each face of the zone, whether the zone is oriented opposite to the face.
+    /// It is stored as "N{<bool>}" if all faces share the same value.
+    pub flipmap: Vec<bool>,
 }
 
 impl std::ops::Deref for FaceZone {
@@ -57,7 +65,7 @@ impl FileElement for FaceZone {
         // "flipMap         List<bool>"
         let (input, _) = next(known_key_value("flipMap", "List<bool>"))(input)?;
         let (input, _) = discard_empty(input)?;
-        // on the same line: <number of faces>{<bool>};
+        // on the same line: <number of faces>{<bool>}; or <number of faces>(<bool> ...);
         let (input, flipmap) = Self::parse_flipmap(input, n)?;
         // closing curly brace
         let (input, _) = next(char('}'))(input)?;
@@ -87,20 +95,52 @@ impl std::fmt::Display for FaceZone {
 }
 
 impl FaceZone {
+    /// Returns the zone with the faces renumbered by `new_face`, which gives the new label of each
+    /// old face, or None if the face is removed. The faces are sorted by their new labels.
+    /// `flipped` tells whether a new face is oriented opposite to the old one, in which case its
+    /// flipmap entry is inverted.
+    pub(crate) fn renumbered<F, G>(&self, new_face: F, flipped: G) -> FaceZone
+    where
+        F: Fn(usize) -> Option<usize>,
+        G: Fn(usize) -> bool,
+    {
+        let mut faces: Vec<(usize, bool)> = self
+            .faces
+            .iter()
+            .zip(self.flipmap.iter())
+            .filter_map(|(&face, &flip)| {
+                let new = new_face(face)?;
+                Some((new, flip != flipped(new)))
+            })
+            .collect();
+        faces.sort_unstable();
+        FaceZone {
+            name: self.name.clone(),
+            faces: faces.iter().map(|&(face, _)| face).collect(),
+            flipmap: faces.iter().map(|&(_, flip)| flip).collect(),
+        }
+    }
+
     fn write_flipmap(&self, file: &mut std::fmt::Formatter) -> std::fmt::Result {
         write!(file, "flipMap         List<bool> ")?;
-        write!(file, "{}", self.faces.len())?;
-        write!(file, "{{")?;
-        write!(file, "{}", bool_as_num(self.flipmap))?;
-        writeln!(file, "}};")?;
+        let first = self.flipmap.first().copied().unwrap_or_default();
+        if self.flipmap.iter().all(|&flip| flip == first) {
+            write!(file, "{}", self.faces.len())?;
+            write!(file, "{{")?;
+            write!(file, "{}", bool_as_num(first))?;
+            writeln!(file, "}};")?;
+        } else {
+            let flipmap: Vec<usize> = self.flipmap.iter().map(|&flip| bool_as_num(flip)).collect();
+            write_single_data(&flipmap, file)?;
+            writeln!(file, ";")?;
+        }
         Ok(())
     }
 
-    fn parse_flipmap(input: &str, n: usize) -> IResult<&str, bool> {
-        delimited(
-            tag(n.to_string().as_str()),
-            delimited(char('{'), bool, char('}')),
-            semicolon,
-        )(input)
+    fn parse_flipmap(input: &str, n: usize) -> IResult<&str, Vec<bool>> {
+        let (input, _) = tag(n.to_string().as_str())(input)?;
+        let uniform = map(delimited(char('{'), bool, char('}')), |flip| vec![flip; n]);
+        let nonuniform = block_parentheses(count(lws(bool), n));
+        terminated(alt((uniform, nonuniform)), semicolon)(input)
     }
 }
@@ -0,0 +1,213 @@
+use super::geometry::face_centre_and_area;
+use super::{Dimensions, PolyMesh, ResultData};
+use crate::base::vector::mag;
+use crate::base::FoamField;
+use std::ops::Range;
+
+/// Integrals and averages of fields over the cells and the patches of the mesh, computed like the
+/// "volFieldValue" and "surfaceFieldValue" function objects of OpenFOAM.
+/// The results are uniform fields, with the dimensions of the integrand.
+impl PolyMesh {
+    /// Returns the volume integral of a volume field over all cells, or over the cells of a cellZone.
+    pub fn volume_integral(
+        &self,
+        field: &ResultData,
+        cell_zone: Option<&str>,
+    ) -> std::io::Result<ResultData> {
+        let (volumes, values) = self.cell_values(field, cell_zone)?;
+        Ok(ResultData::new(
+            &field.dimensions * &Dimensions::new([0, 3, 0, 0, 0, 0, 0]),
+            (&values * &volumes).sum(),
+        ))
+    }
+
+    /// Returns the volume-weighted average of a volume field over all cells, or over the cells of
+    /// a cellZone.
+    pub fn volume_average(
+        &self,
+        field: &ResultData,
+        cell_zone: Option<&str>,
+    ) -> std::io::Result<ResultData> {
+        let (volumes, values) = self.cell_values(field, cell_zone)?;
+        let total = volumes.sum();
+        Ok(ResultData::new(
+            field.dimensions.clone(),
+            &(&values * &volumes).sum() / &total,
+        ))
+    }
+
+    /// Returns the area integral of the boundary values of a field over a patch.
+    /// The "value" entry of the boundary condition is used, or the values of the adjacent cells if
+    /// there is none, as for a "zeroGradient" condition.
+    pub fn patch_area_integral(
+        &self,
+        field: &ResultData,
+        patch: &str,
+    ) -> std::io::Result<ResultData> {
+        let (areas, values) = self.patch_values(field, patch)?;
+        Ok(ResultData::new(
+            &field.dimensions * &Dimensions::new([0, 2, 0, 0, 0, 0, 0]),
+            (&values * &areas).sum(),
+        ))
+    }
+
+    /// Returns the area-weighted average of the boundary values of a field over a patch.
+    /// The values are chosen like in `patch_area_integral`.
+    pub fn patch_area_average(
+        &self,
+        field: &ResultData,
+        patch: &str,
+    ) -> std::io::Result<ResultData> {
+        let (areas, values) = self.patch_values(field, patch)?;
+        let total = areas.sum();
+        Ok(ResultData::new(
+            field.dimensions.clone(),
+            &(&values * &areas).sum() / &total,
+        ))
+    }
+
+    /// Returns the sum of a face flux field, e.g. "phi", over the faces of a patch.
+    /// The flux is positive out of the domain. Returns an error if the field has no values on
+    /// the patch, e.g. on "empty" patches.
+    pub fn patch_flux(&self, phi: &ResultData, patch: &str) -> std::io::Result<ResultData> {
+        let range = self.patch_range(patch)?;
+        let values = phi.patch_value(patch).ok_or_else(|| {
+            invalid_data(format!("The flux has no values on the patch {}.", patch))
+        })?;
+        check_size(values, range.len(), &format!("faces on patch {}", patch))?;
+        let sum = match values.n_values() {
+            Some(_) => values.sum(),
+            None => values * range.len() as f64,
+        };
+        Ok(ResultData::new(phi.dimensions.clone(), sum))
+    }
+
+    /// Returns the volumes and the field values of all cells, or of the cells of a cellZone.
+    fn cell_values(
+        &self,
+        field: &ResultData,
+        cell_zone: Option<&str>,
+    ) -> std::io::Result<(FoamField, FoamField)> {
+        check_size(&field.result, self.n_cells(), "cells")?;
+        let volumes = self.cell_volumes();
+        match cell_zone {
+            None => Ok((FoamField::Scalar(volumes), field.result.clone())),
+            Some(name) => {
+                let zone = self
+                    .cellzones
+                    .as_ref()
+                    .and_then(|zones| zones.data.zones.get(name))
+                    .ok_or_else(|| not_found(format!("Cell zone {} not found.", name)))?;
+                let zone_volumes = zone.cells.iter().map(|&cell| volumes[cell]).collect();
+                Ok((FoamField::Scalar(zone_volumes), field.result.subset(zone)))
+            }
+        }
+    }
+
+    /// Returns the face areas and the boundary values of a field on a patch.
+    fn patch_values(
+        &self,
+        field: &ResultData,
+        patch: &str,
+    ) -> std::io::Result<(FoamField, FoamField)> {
+        let range = self.patch_range(patch)?;
+        let values = match field.patch_value(patch) {
+            Some(values) => values.clone(),
+            None => {
+                check_size(&field.result, self.n_cells(), "cells")?;
+                let cells: Vec<usize> = range.clone().map(|face| self.owner.data[face]).collect();
+                field.result.subset(&cells)
+            }
+        };
+        check_size(&values, range.len(), &format!("faces on patch {}", patch))?;
+        let areas = range
+            .map(|face| mag(&face_centre_and_area(&self.points.data, &self.faces.data[face]).1))
+            .collect();
+        Ok((FoamField::Scalar(areas), values))
+    }
+
+    fn patch_range(&self, patch: &str) -> std::io::Result<Range<usize>> {
+        self.boundary
+            .data
+            .face_range(patch)
+            .ok_or_else(|| not_found(format!("Patch {} not found.", patch)))
+    }
+}
+
+/// Checks that a nonuniform field has one value per entity, e.g. "cells" or "faces on patch inlet".
+fn check_size(values: &FoamField, expected: usize, entities: &str) -> std::io::Result<()> {
+    match values.n_values() {
+        Some(n) if n != expected => Err(invalid_data(format!(
+            "The field has {} values, but there are {} {}.",
+            n, expected, entities
+        ))),
+        _ => Ok(()),
+    }
+}
+
+fn invalid_data(message: String) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, message)
+}
+
+fn not_found(message: String) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::NotFound, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FileContent;
+
+    fn scalar(field: &ResultData) -> f64 {
+        match field.result {
+            FoamField::UniformScalar(value) => value,
+            _ => panic!("Not a uniform scalar."),
+        }
+    }
+
+    #[test]
+    fn test_volume_integrals() {
+        let path = std::path::Path::new("tests/test_cases/original/two_zones_with_interfaces");
+        let mesh = PolyMesh::parse(&path.join("constant/polyMesh")).unwrap();
+        let one = ResultData::new(Dimensions::dimensionless(), FoamField::UniformScalar(1.0));
+        let volume = mesh.volume_integral(&one, None).unwrap();
+        assert_eq!(volume.dimensions, Dimensions::new([0, 3, 0, 0, 0, 0, 0]));
+        let total: f64 = mesh.cell_volumes().iter().sum();
+        assert!((scalar(&volume) - total).abs() < 1e-12 * total);
+
+        let zones: f64 = ["channel", "metal"]
+            .iter()
+            .map(|&zone| scalar(&mesh.volume_integral(&one, Some(zone)).unwrap()))
+            .sum();
+        assert!((zones - total).abs() < 1e-9 * total);
+        let average = mesh.volume_average(&one, Some("metal")).unwrap();
+        assert!((scalar(&average) - 1.0).abs() < 1e-12);
+        assert!(mesh.volume_integral(&one, Some("air")).is_err());
+    }
+
+    #[test]
+    fn test_patch_values() {
+        let path = std::path::Path::new("tests/test_cases/original/cylinder");
+        let mesh = PolyMesh::parse(&path.join("constant/polyMesh")).unwrap();
+        let phi = FileContent::<ResultData>::parse_file(&path.join("0/phi")).unwrap();
+        let outflow = scalar(&mesh.patch_flux(&phi.data, "right").unwrap());
+        assert!((outflow - 1.9996943).abs() < 1e-9);
+        assert_eq!(scalar(&mesh.patch_flux(&phi.data, "down").unwrap()), 0.0);
+        assert!(mesh.patch_flux(&phi.data, "defaultFaces").is_err());
+
+        let u = FileContent::<ResultData>::parse_file(&path.join("0/U")).unwrap();
+        let average = mesh.patch_area_average(&u.data, "left").unwrap();
+        assert_eq!(average.dimensions, u.data.dimensions);
+        assert_eq!(average.result.n_components(), 3);
+        // phi is the flux of U, so that the normal velocity on the inlet gives the same flux
+        let area = mesh.patch_area_integral(
+            &ResultData::new(Dimensions::dimensionless(), FoamField::UniformScalar(1.0)),
+            "left",
+        );
+        let inflow = scalar(&mesh.patch_flux(&phi.data, "left").unwrap());
+        let FoamField::UniformVector(velocity) = average.result else {
+            panic!("Not a uniform vector.");
+        };
+        assert!((velocity[0] * scalar(&area.unwrap()) + inflow).abs() < 1e-3);
+    }
+}
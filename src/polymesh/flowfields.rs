@@ -0,0 +1,348 @@
+use super::geometry::face_centre_and_area;
+use super::{Dimensions, GradientScheme, PolyMesh, ResultData};
+use crate::base::vector::*;
+use crate::base::{FileContent, FoamField};
+use indexmap::IndexMap;
+
+/// Derived quantities of the velocity field, computed like the function objects of OpenFOAM, and
+/// returned as fields with the names that OpenFOAM writes, e.g. "vorticity" or "yPlus".
+/// The velocity gradient is computed with the "Gauss linear" scheme, see `PolyMesh::gradient`.
+impl PolyMesh {
+    /// Returns the vorticity, i.e. the curl of the velocity.
+    pub fn vorticity(
+        &self,
+        u: &FileContent<ResultData>,
+    ) -> std::io::Result<FileContent<ResultData>> {
+        self.velocity_gradient_field(u, "vorticity", 1, |g| {
+            vec![g[5] - g[7], g[6] - g[2], g[1] - g[3]]
+        })
+    }
+
+    /// Returns the Q-criterion, the second invariant of the velocity gradient:
+    /// Q = 0.5 (tr(grad U)^2 - tr(grad U & grad U)).
+    pub fn q_criterion(
+        &self,
+        u: &FileContent<ResultData>,
+    ) -> std::io::Result<FileContent<ResultData>> {
+        self.velocity_gradient_field(u, "Q", 2, |g| {
+            let trace = g[0] + g[4] + g[8];
+            let trace_square: f64 = (0..3)
+                .flat_map(|i| (0..3).map(move |j| (i, j)))
+                .map(|(i, j)| g[3 * i + j] * g[3 * j + i])
+                .sum();
+            vec![0.5 * (trace * trace - trace_square)]
+        })
+    }
+
+    /// Returns the lambda2 criterion: the opposite of the intermediate eigenvalue of
+    /// S^2 + W^2, where S and W are the symmetric and antisymmetric parts of the velocity gradient.
+    /// Vortex cores have positive values.
+    pub fn lambda2(&self, u: &FileContent<ResultData>) -> std::io::Result<FileContent<ResultData>> {
+        self.velocity_gradient_field(u, "Lambda2", 2, |g| {
+            let s = |i: usize, j: usize| 0.5 * (g[3 * i + j] + g[3 * j + i]);
+            let w = |i: usize, j: usize| 0.5 * (g[3 * i + j] - g[3 * j + i]);
+            let m: [[f64; 3]; 3] = std::array::from_fn(|i| {
+                std::array::from_fn(|j| (0..3).map(|k| s(i, k) * s(k, j) + w(i, k) * w(k, j)).sum())
+            });
+            vec![-symmetric_eigenvalues(&m)[1]]
+        })
+    }
+
+    /// Returns the wall shear stress on the wall patches, divided by the density like in
+    /// incompressible cases: tau = -n & R, with the effective stress R = -nu_eff dev(twoSymm(grad U))
+    /// and the normal n out of the domain. The effective viscosity is the sum of the kinematic
+    /// viscosity `nu` and of the turbulent viscosity `nut`, if given.
+    /// The internal field and the other patches are zero.
+    pub fn wall_shear_stress(
+        &self,
+        u: &FileContent<ResultData>,
+        nu: f64,
+        nut: Option<&FileContent<ResultData>>,
+    ) -> std::io::Result<FileContent<ResultData>> {
+        let walls = self.wall_values(u, nu, nut)?;
+        let boundary_field = self.calculated_boundary_field(|name, _| match walls.get(name) {
+            Some(wall) => FoamField::Vector(wall.shear_stress.iter().map(|t| t.to_vec()).collect()),
+            None => FoamField::UniformVector(vec![0.0; 3]),
+        });
+        Ok(u.derived_field(
+            "wallShearStress",
+            ResultData {
+                dimensions: Dimensions::new([0, 2, -2, 0, 0, 0, 0]),
+                result: FoamField::UniformVector(vec![0.0; 3]),
+                boundary_field: Some(boundary_field),
+            },
+        ))
+    }
+
+    /// Returns the dimensionless wall distance y+ = y u_tau / nu on the wall patches, where y is
+    /// the distance from the centre of the wall-adjacent cell to the wall, and u_tau is the friction
+    /// velocity given by the wall shear stress, see `wall_shear_stress`.
+    /// The internal field and the other patches are zero.
+    pub fn y_plus(
+        &self,
+        u: &FileContent<ResultData>,
+        nu: f64,
+        nut: Option<&FileContent<ResultData>>,
+    ) -> std::io::Result<FileContent<ResultData>> {
+        let walls = self.wall_values(u, nu, nut)?;
+        let boundary_field = self.calculated_boundary_field(|name, _| match walls.get(name) {
+            Some(wall) => FoamField::Scalar(
+                wall.shear_stress
+                    .iter()
+                    .zip(&wall.distances)
+                    .map(|(tau, y)| y * mag(tau).sqrt() / nu)
+                    .collect(),
+            ),
+            None => FoamField::UniformScalar(0.0),
+        });
+        Ok(u.derived_field(
+            "yPlus",
+            ResultData {
+                dimensions: Dimensions::dimensionless(),
+                result: FoamField::UniformScalar(0.0),
+                boundary_field: Some(boundary_field),
+            },
+        ))
+    }
+
+    /// Computes a field from the velocity gradient of each cell. The boundary values are the values
+    /// of the adjacent cells. `order` is the power of the velocity gradient in the dimensions.
+    fn velocity_gradient_field(
+        &self,
+        u: &FileContent<ResultData>,
+        name: &str,
+        order: i32,
+        f: impl Fn(&[f64]) -> Vec<f64>,
+    ) -> std::io::Result<FileContent<ResultData>> {
+        let gradient = self.velocity_gradient(u)?;
+        let values = (0..self.n_cells())
+            .map(|cell| f(gradient.data.result.value(cell)))
+            .collect();
+        let result = FoamField::from_values(values);
+        let boundary_field = self.calculated_boundary_field(|_, range| {
+            result.subset(&range.map(|face| self.owner.data[face]).collect::<Vec<_>>())
+        });
+        Ok(u.derived_field(
+            name,
+            ResultData {
+                dimensions: gradient.data.dimensions.pow(order),
+                result,
+                boundary_field: Some(boundary_field),
+            },
+        ))
+    }
+
+    fn velocity_gradient(
+        &self,
+        u: &FileContent<ResultData>,
+    ) -> std::io::Result<FileContent<ResultData>> {
+        if u.data.result.n_components() != 3 {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "The velocity must be a vector field.",
+            ));
+        }
+        self.gradient(u, GradientScheme::GaussLinear)
+    }
+
+    /// Computes the wall shear stress and the wall distance of the faces of all wall patches.
+    /// The velocity gradient at the wall is the gradient of the adjacent cell, with its normal
+    /// component replaced by the difference between the wall and the cell values.
+    fn wall_values(
+        &self,
+        u: &FileContent<ResultData>,
+        nu: f64,
+        nut: Option<&FileContent<ResultData>>,
+    ) -> std::io::Result<IndexMap<String, WallValues>> {
+        let gradient = self.velocity_gradient(u)?.data.result;
+        let cell_centres = self.cell_centres();
+        let mut walls = IndexMap::new();
+        for (name, range) in self.boundary.data.face_ranges() {
+            if self.boundary.data.patch_type(&name) != Some("wall") {
+                continue;
+            }
+            let n_faces = range.len();
+            let has_values = |field: &FoamField| field.n_values().is_none_or(|n| n == n_faces);
+            // walls without values, e.g. "noSlip" walls, do not move
+            let wall_velocity = u
+                .data
+                .patch_value(&name)
+                .filter(|values| has_values(values));
+            let wall_nut = nut.map(|nut| match nut.data.patch_value(&name) {
+                Some(values) if has_values(values) => values.clone(),
+                _ => nut.data.result.subset(
+                    &range
+                        .clone()
+                        .map(|face| self.owner.data[face])
+                        .collect::<Vec<_>>(),
+                ),
+            });
+
+            let mut wall = WallValues::default();
+            for (i, face) in range.enumerate() {
+                let cell = self.owner.data[face];
+                let (centre, area) =
+                    face_centre_and_area(&self.points.data, &self.faces.data[face]);
+                let normal = scale(&area, 1.0 / mag(&area));
+                let distance = dot(&normal, &sub(&centre, &cell_centres[cell]));
+                let cell_velocity = u.data.result.value(cell);
+                let velocity = wall_velocity.map_or([0.0; 3], |values| {
+                    let value = values.value(i);
+                    [value[0], value[1], value[2]]
+                });
+                let g = gradient.value(cell);
+                // replace the normal derivative of the gradient by the wall-normal difference
+                let mut wall_gradient: [f64; 9] = std::array::from_fn(|k| g[k]);
+                for j in 0..3 {
+                    let normal_gradient = (velocity[j] - cell_velocity[j]) / distance;
+                    let cell_normal_gradient: f64 = (0..3).map(|i| normal[i] * g[3 * i + j]).sum();
+                    for i in 0..3 {
+                        wall_gradient[3 * i + j] +=
+                            normal[i] * (normal_gradient - cell_normal_gradient);
+                    }
+                }
+                // the deviatoric part of twice the symmetric part of the gradient
+                let trace = wall_gradient[0] + wall_gradient[4] + wall_gradient[8];
+                let deviatoric = |i: usize, j: usize| {
+                    let diagonal = if i == j { 2.0 * trace / 3.0 } else { 0.0 };
+                    wall_gradient[3 * i + j] + wall_gradient[3 * j + i] - diagonal
+                };
+                let nu_eff = nu + wall_nut.as_ref().map_or(0.0, |nut| nut.value(i)[0]);
+                let shear_stress: Vector = std::array::from_fn(|j| {
+                    nu_eff * (0..3).map(|i| normal[i] * deviatoric(i, j)).sum::<f64>()
+                });
+                wall.shear_stress.push(shear_stress);
+                wall.distances.push(distance);
+            }
+            walls.insert(name, wall);
+        }
+        Ok(walls)
+    }
+}
+
+/// The wall shear stress and the wall distance of the faces of a wall patch.
+#[derive(Default)]
+struct WallValues {
+    shear_stress: Vec<Vector>,
+    distances: Vec<f64>,
+}
+
+/// Returns the eigenvalues of a symmetric 3x3 matrix in increasing order.
+fn symmetric_eigenvalues(m: &[[f64; 3]; 3]) -> [f64; 3] {
+    let off_diagonal = m[0][1] * m[0][1] + m[0][2] * m[0][2] + m[1][2] * m[1][2];
+    if off_diagonal <= f64::MIN_POSITIVE {
+        let mut eigenvalues = [m[0][0], m[1][1], m[2][2]];
+        eigenvalues.sort_by(f64::total_cmp);
+        return eigenvalues;
+    }
+    // trigonometric solution of the characteristic polynomial
+    let q = (m[0][0] + m[1][1] + m[2][2]) / 3.0;
+    let p = (((m[0][0] - q).powi(2)
+        + (m[1][1] - q).powi(2)
+        + (m[2][2] - q).powi(2)
+        + 2.0 * off_diagonal)
+        / 6.0)
+        .sqrt();
+    let b = |i: usize, j: usize| (m[i][j] - if i == j { q } else { 0.0 }) / p;
+    let determinant = b(0, 0) * (b(1, 1) * b(2, 2) - b(1, 2) * b(2, 1))
+        - b(0, 1) * (b(1, 0) * b(2, 2) - b(1, 2) * b(2, 0))
+        + b(0, 2) * (b(1, 0) * b(2, 1) - b(1, 1) * b(2, 0));
+    let phi = (determinant / 2.0).clamp(-1.0, 1.0).acos() / 3.0;
+    let largest = q + 2.0 * p * phi.cos();
+    let smallest = q + 2.0 * p * (phi + 2.0 * std::f64::consts::PI / 3.0).cos();
+    [smallest, 3.0 * q - largest - smallest, largest]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base::{FoamStructure, FoamValue};
+
+    #[test]
+    fn test_symmetric_eigenvalues() {
+        let m = [[2.0, 1.0, 0.0], [1.0, 2.0, 0.0], [0.0, 0.0, -1.0]];
+        let eigenvalues = symmetric_eigenvalues(&m);
+        for (value, expected) in eigenvalues.iter().zip([-1.0, 1.0, 3.0]) {
+            assert!((value - expected).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_velocity_gradient_fields() {
+        let path = std::path::Path::new("tests/test_cases/original/cylinder");
+        let mesh = PolyMesh::parse(&path.join("constant/polyMesh")).unwrap();
+        let u = FileContent::<ResultData>::parse_file(&path.join("0/U")).unwrap();
+        let vorticity = mesh.vorticity(&u).unwrap();
+        assert_eq!(vorticity.name(), Some("vorticity"));
+        assert_eq!(vorticity.meta.class(), Some("volVectorField"));
+        assert_eq!(vorticity.location, Some("0/vorticity".into()));
+        assert_eq!(
+            vorticity.data.dimensions,
+            Dimensions::new([0, 0, -1, 0, 0, 0, 0])
+        );
+        let q = mesh.q_criterion(&u).unwrap();
+        assert_eq!(q.meta.class(), Some("volScalarField"));
+        assert_eq!(q.data.dimensions, Dimensions::new([0, 0, -2, 0, 0, 0, 0]));
+        let lambda2 = mesh.lambda2(&u).unwrap();
+        assert_eq!(lambda2.data.result.n_values(), Some(mesh.n_cells()));
+        // in 2D, the vorticity is normal to the plane
+        let FoamField::Vector(values) = &vorticity.data.result else {
+            panic!("Not a vector field.");
+        };
+        assert!(values
+            .iter()
+            .all(|v| v[0].abs() < 1e-6 && v[1].abs() < 1e-6));
+    }
+
+    #[test]
+    fn test_wall_shear_stress() {
+        let path = std::path::Path::new("tests/test_cases/original/two_zones_with_interfaces");
+        let mut mesh = PolyMesh::parse(&path.join("constant/polyMesh")).unwrap();
+        let FoamValue::Structure(top) = mesh.boundary.data.get_mut("top").unwrap() else {
+            panic!("Not a patch.");
+        };
+        top.insert("type".to_string(), FoamValue::String("wall".to_string()));
+        // a shear flow that vanishes on the top wall at y = 1
+        let (a, nu) = (2.0, 1e-3);
+        let values = mesh
+            .cell_centres()
+            .iter()
+            .map(|x| vec![a * (1.0 - x[1]), 0.0, 0.0])
+            .collect();
+        let mut patches = IndexMap::new();
+        let mut wall = IndexMap::new();
+        wall.insert("type".to_string(), FoamValue::String("noSlip".to_string()));
+        patches.insert("top".to_string(), FoamValue::Structure(FoamStructure(wall)));
+        let mut boundary_field = IndexMap::new();
+        boundary_field.insert(
+            "boundaryField".to_string(),
+            FoamValue::Structure(FoamStructure(patches)),
+        );
+        let u = FileContent {
+            location: Some("1/U".into()),
+            meta: crate::FoamFile::new("volVectorField", "1", "U"),
+            data: ResultData {
+                dimensions: Dimensions::new([0, 1, -1, 0, 0, 0, 0]),
+                result: FoamField::Vector(values),
+                boundary_field: Some(FoamStructure(boundary_field)),
+            },
+        };
+
+        let tau = mesh.wall_shear_stress(&u, nu, None).unwrap();
+        assert_eq!(tau.name(), Some("wallShearStress"));
+        let top = tau.data.patch_value("top").unwrap();
+        let n_faces = mesh.boundary.data.face_range("top").unwrap().len();
+        for i in 0..n_faces {
+            // the stress on the fluid is opposite to the flow
+            assert!((top.value(i)[0] + nu * a).abs() < 1e-9);
+        }
+        assert_eq!(
+            tau.data.patch_value("bottom"),
+            Some(&FoamField::UniformVector(vec![0.0; 3]))
+        );
+
+        let y_plus = mesh.y_plus(&u, nu, None).unwrap();
+        let top_y_plus = y_plus.data.patch_value("top").unwrap();
+        assert!((0..n_faces).all(|i| top_y_plus.value(i)[0] > 0.0));
+    }
+}
@@ -0,0 +1,162 @@
+use super::{Point, PolyMesh};
+use crate::base::vector::*;
+
+/// Geometric quantities of the mesh, computed the same way as OpenFOAM's primitiveMesh.
+impl PolyMesh {
+    /// Returns the centre of every face.
+    pub fn face_centres(&self) -> Vec<Point> {
+        self.faces
+            .data
+            .iter()
+            .map(|face| face_centre_and_area(&self.points.data, face).0)
+            .collect()
+    }
+
+    /// Returns the area vector of every face, i.e. the face normal scaled by the face area.
+    /// The normal points out of the owner cell.
+    pub fn face_area_vectors(&self) -> Vec<Point> {
+        self.faces
+            .data
+            .iter()
+            .map(|face| face_centre_and_area(&self.points.data, face).1)
+            .collect()
+    }
+
+    /// Returns the centre of every cell.
+    pub fn cell_centres(&self) -> Vec<Point> {
+        self.cell_centres_and_volumes().0
+    }
+
+    /// Returns the volume of every cell.
+    pub fn cell_volumes(&self) -> Vec<f64> {
+        self.cell_centres_and_volumes().1
+    }
+
+    /// Returns the centres and the volumes of all cells.
+    /// The cells are decomposed into pyramids with the faces as bases and an estimate of the cell
+    /// centre (the average of the face centres) as apex.
+    pub fn cell_centres_and_volumes(&self) -> (Vec<Point>, Vec<f64>) {
+        let (face_centres, face_areas): (Vec<Point>, Vec<Point>) = self
+            .faces
+            .data
+            .iter()
+            .map(|face| face_centre_and_area(&self.points.data, face))
+            .unzip();
+        let n_cells = self.n_cells();
+
+        // estimate the cell centres from the face centres
+        let mut estimates = vec![[0.0; 3]; n_cells];
+        let mut n_cell_faces = vec![0usize; n_cells];
+        let owner_and_neighbour = self
+            .owner
+            .data
+            .iter()
+            .enumerate()
+            .chain(self.neighbour.data.iter().enumerate());
+        for (face, &cell) in owner_and_neighbour.clone() {
+            estimates[cell] = add(&estimates[cell], &face_centres[face]);
+            n_cell_faces[cell] += 1;
+        }
+        for (estimate, &n) in estimates.iter_mut().zip(n_cell_faces.iter()) {
+            if n > 0 {
+                *estimate = scale(estimate, 1.0 / n as f64);
+            }
+        }
+
+        // sum up the contributions of the pyramids
+        let mut centres = vec![[0.0; 3]; n_cells];
+        let mut volumes = vec![0.0; n_cells];
+        let n_internal_faces = self.n_internal_faces();
+        for (face, &cell) in owner_and_neighbour {
+            let mut pyramid_volume = dot(
+                &face_areas[face],
+                &sub(&face_centres[face], &estimates[cell]),
+            );
+            // the area vector points into the neighbour cell
+            if face < n_internal_faces && self.neighbour.data[face] == cell {
+                pyramid_volume = -pyramid_volume;
+            }
+            let pyramid_centre = add(
+                &scale(&face_centres[face], 0.75),
+                &scale(&estimates[cell], 0.25),
+            );
+            centres[cell] = add(&centres[cell], &scale(&pyramid_centre, pyramid_volume));
+            volumes[cell] += pyramid_volume;
+        }
+        for cell in 0..n_cells {
+            if volumes[cell].abs() > f64::MIN_POSITIVE {
+                centres[cell] = scale(&centres[cell], 1.0 / volumes[cell]);
+            } else {
+                centres[cell] = estimates[cell];
+            }
+            volumes[cell] /= 3.0;
+        }
+        (centres, volumes)
+    }
+}
+
+/// Computes the centre and the area vector of a single face.
+/// Polygons are decomposed into triangles around the average of their points.
+pub(crate) fn face_centre_and_area(points: &[Point], face: &[usize]) -> (Point, Point) {
+    if face.len() == 3 {
+        let (p0, p1, p2) = (&points[face[0]], &points[face[1]], &points[face[2]]);
+        let centre = scale(&add(&add(p0, p1), p2), 1.0 / 3.0);
+        let area = scale(&cross(&sub(p1, p0), &sub(p2, p0)), 0.5);
+        return (centre, area);
+    }
+    let estimate = average(face.iter().map(|&p| &points[p]));
+    let mut sum_n = [0.0; 3];
+    let mut sum_a = 0.0;
+    let mut sum_ac = [0.0; 3];
+    for (i, &p) in face.iter().enumerate() {
+        let this = &points[p];
+        let next = &points[face[(i + 1) % face.len()]];
+        let c = add(&add(this, next), &estimate);
+        let n = cross(&sub(next, this), &sub(&estimate, this));
+        let a = mag(&n);
+        sum_n = add(&sum_n, &n);
+        sum_a += a;
+        sum_ac = add(&sum_ac, &scale(&c, a));
+    }
+    let centre = if sum_a > f64::MIN_POSITIVE {
+        scale(&sum_ac, 1.0 / (3.0 * sum_a))
+    } else {
+        estimate
+    };
+    (centre, scale(&sum_n, 0.5))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_face_centre_and_area() {
+        let points = vec![
+            [0.0, 0.0, 0.0],
+            [2.0, 0.0, 0.0],
+            [2.0, 1.0, 0.0],
+            [0.0, 1.0, 0.0],
+        ];
+        let (centre, area) = face_centre_and_area(&points, &[0, 1, 2, 3]);
+        assert!(mag(&sub(&centre, &[1.0, 0.5, 0.0])) < 1e-12);
+        assert!(mag(&sub(&area, &[0.0, 0.0, 2.0])) < 1e-12);
+    }
+
+    #[test]
+    fn test_cell_volumes() {
+        let path = std::path::Path::new("tests/test_cases/original/two_zones_with_interfaces");
+        let mesh = PolyMesh::parse(&path.join("constant/polyMesh")).unwrap();
+        let volumes = mesh.cell_volumes();
+        assert_eq!(volumes.len(), mesh.n_cells());
+        assert!(volumes.iter().all(|&v| v > 0.0));
+        // the volume of the mesh equals the divergence of the position over the boundary
+        let centres = mesh.face_centres();
+        let areas = mesh.face_area_vectors();
+        let boundary_integral: f64 = (mesh.n_internal_faces()..mesh.n_faces())
+            .map(|face| dot(&centres[face], &areas[face]) / 3.0)
+            .sum();
+        let total: f64 = volumes.iter().sum();
+        assert!((total - boundary_integral).abs() < 1e-9 * total.abs());
+    }
+}
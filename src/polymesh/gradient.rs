@@ -0,0 +1,482 @@
+use super::boundary::CONSTRAINT_PATCH_TYPES;
+use super::geometry::face_centre_and_area;
+use super::{Dimensions, FieldLocation, PolyMesh, ResultData};
+use crate::base::vector::*;
+use crate::base::{FileContent, FoamField, FoamStructure, FoamValue};
+use indexmap::IndexMap;
+use std::ops::Range;
+
+/// The discretisation schemes of the gradient, see `PolyMesh::gradient`.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum GradientScheme {
+    /// Gauss' theorem with linear interpolation of the values to the faces ("Gauss linear").
+    GaussLinear,
+    /// Least-squares fit of the differences to the neighbouring cells ("leastSquares").
+    LeastSquares,
+}
+
+/// Gradients of volume fields, like the "grad" function object of OpenFOAM.
+impl PolyMesh {
+    /// Computes the gradient of a volume scalar or vector field, and returns it as a volume vector
+    /// or tensor field named "grad(<field>)" in the same time directory, e.g. to be inserted in
+    /// `TimeDir::field_values`. The gradient of a vector field U is the tensor with the components
+    /// (i, j) = d U_j / d x_i, like in OpenFOAM.
+    ///
+    /// On the boundary, the "value" entries of the "boundaryField" are used where present.
+    /// Otherwise, the values of the adjacent cells are used, without their normal component on
+    /// symmetry patches. Empty patches are ignored, so that the gradients of two-dimensional cases
+    /// have no component in the empty direction.
+    pub fn gradient(
+        &self,
+        field: &FileContent<ResultData>,
+        scheme: GradientScheme,
+    ) -> std::io::Result<FileContent<ResultData>> {
+        let class = field.meta.class().unwrap_or_default();
+        if FieldLocation::from_class(class).is_some_and(|location| location != FieldLocation::Cells)
+        {
+            return Err(invalid_input(format!(
+                "Only the gradient of volume fields can be computed, not of {}.",
+                class
+            )));
+        }
+        let values = &field.data.result;
+        if values.n_components() != 1 && values.n_components() != 3 {
+            return Err(invalid_input(
+                "Only the gradient of scalar and vector fields can be computed.".to_string(),
+            ));
+        }
+        if let Some(n) = values.n_values().filter(|&n| n != self.n_cells()) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!(
+                    "The field has {} values, but there are {} cells.",
+                    n,
+                    self.n_cells()
+                ),
+            ));
+        }
+
+        let gradient = Gradient::new(self, &field.data);
+        let gradients = match scheme {
+            GradientScheme::GaussLinear => gradient.gauss_linear(),
+            GradientScheme::LeastSquares => gradient.least_squares(),
+        };
+        let gradients: Vec<Vec<f64>> = gradients
+            .chunks(3 * gradient.n_components)
+            .map(<[f64]>::to_vec)
+            .collect();
+        let result = FoamField::Vector(gradients);
+        let boundary_field = self.calculated_boundary_field(|_, range| {
+            result.subset(&range.map(|face| self.owner.data[face]).collect::<Vec<_>>())
+        });
+        let name = format!("grad({})", field.name().unwrap_or_default());
+        Ok(field.derived_field(
+            &name,
+            ResultData {
+                dimensions: &field.data.dimensions / &Dimensions::new([0, 1, 0, 0, 0, 0, 0]),
+                result,
+                boundary_field: Some(boundary_field),
+            },
+        ))
+    }
+
+    /// Returns a "boundaryField" entry with the constraint types of the patches, and "calculated"
+    /// conditions with the given values on the other patches.
+    pub(crate) fn calculated_boundary_field(
+        &self,
+        values: impl Fn(&str, Range<usize>) -> FoamField,
+    ) -> FoamStructure {
+        let mut patches = IndexMap::new();
+        for (name, range) in self.boundary.data.face_ranges() {
+            let mut condition = IndexMap::new();
+            match self.boundary.data.patch_type(&name) {
+                Some(patch_type) if CONSTRAINT_PATCH_TYPES.contains(&patch_type) => {
+                    condition.insert(
+                        "type".to_string(),
+                        FoamValue::String(patch_type.to_string()),
+                    );
+                }
+                _ => {
+                    condition.insert(
+                        "type".to_string(),
+                        FoamValue::String("calculated".to_string()),
+                    );
+                    condition.insert("value".to_string(), FoamValue::Field(values(&name, range)));
+                }
+            }
+            patches.insert(name, FoamValue::Structure(FoamStructure(condition)));
+        }
+        let mut boundary_field = IndexMap::new();
+        boundary_field.insert(
+            "boundaryField".to_string(),
+            FoamValue::Structure(FoamStructure(patches)),
+        );
+        FoamStructure(boundary_field)
+    }
+}
+
+/// The data needed by the gradient schemes.
+struct Gradient<'a> {
+    mesh: &'a PolyMesh,
+    values: &'a FoamField,
+    n_components: usize,
+    face_centres: Vec<Vector>,
+    face_areas: Vec<Vector>,
+    cell_centres: Vec<Vector>,
+    cell_volumes: Vec<f64>,
+    // the value on each boundary face, or None on the faces of empty patches
+    boundary_values: Vec<Option<Vec<f64>>>,
+}
+
+impl<'a> Gradient<'a> {
+    fn new(mesh: &'a PolyMesh, field: &'a ResultData) -> Self {
+        let (face_centres, face_areas) = mesh
+            .faces
+            .data
+            .iter()
+            .map(|face| face_centre_and_area(&mesh.points.data, face))
+            .unzip();
+        let (cell_centres, cell_volumes) = mesh.cell_centres_and_volumes();
+        let mut gradient = Gradient {
+            mesh,
+            values: &field.result,
+            n_components: field.result.n_components(),
+            face_centres,
+            face_areas,
+            cell_centres,
+            cell_volumes,
+            boundary_values: Vec::new(),
+        };
+        gradient.boundary_values = gradient.boundary_values(field);
+        gradient
+    }
+
+    fn boundary_values(&self, field: &ResultData) -> Vec<Option<Vec<f64>>> {
+        let n_internal_faces = self.mesh.n_internal_faces();
+        let mut boundary_values = vec![None; self.mesh.n_faces() - n_internal_faces];
+        for (name, range) in self.mesh.boundary.data.face_ranges() {
+            let patch_type = self
+                .mesh
+                .boundary
+                .data
+                .patch_type(&name)
+                .unwrap_or_default();
+            if patch_type == "empty" {
+                continue;
+            }
+            let patch_values = field
+                .patch_value(&name)
+                .filter(|values| values.n_values().is_none_or(|n| n == range.len()))
+                .filter(|values| values.n_components() == self.n_components);
+            let symmetric = matches!(patch_type, "symmetry" | "symmetryPlane" | "wedge");
+            for (i, face) in range.enumerate() {
+                let value = match patch_values {
+                    Some(values) => values.value(i).to_vec(),
+                    None => {
+                        let value = self.values.value(self.mesh.owner.data[face]);
+                        if symmetric && self.n_components == 3 {
+                            let value = [value[0], value[1], value[2]];
+                            let normal =
+                                scale(&self.face_areas[face], 1.0 / mag(&self.face_areas[face]));
+                            sub(&value, &scale(&normal, dot(&normal, &value))).to_vec()
+                        } else {
+                            value.to_vec()
+                        }
+                    }
+                };
+                if let Some(boundary_value) = boundary_values.get_mut(face - n_internal_faces) {
+                    *boundary_value = Some(value);
+                }
+            }
+        }
+        boundary_values
+    }
+
+    /// Returns the gradients of all cells, with 3 * n_components values per cell.
+    fn gauss_linear(&self) -> Vec<f64> {
+        let n = self.n_components;
+        let mut gradients = vec![0.0; self.mesh.n_cells() * 3 * n];
+        let mut add = |cell: usize, area: &Vector, value: &[f64], sign: f64| {
+            for i in 0..3 {
+                for j in 0..n {
+                    gradients[(cell * 3 + i) * n + j] += sign * area[i] * value[j];
+                }
+            }
+        };
+        for (face, &neighbour) in self.mesh.neighbour.data.iter().enumerate() {
+            let owner = self.mesh.owner.data[face];
+            let area = &self.face_areas[face];
+            // the linear interpolation weight of the owner value
+            let to_neighbour = dot(
+                area,
+                &sub(&self.cell_centres[neighbour], &self.face_centres[face]),
+            );
+            let to_owner = dot(
+                area,
+                &sub(&self.face_centres[face], &self.cell_centres[owner]),
+            );
+            let weight = to_neighbour / (to_owner + to_neighbour);
+            let (owner_value, neighbour_value) =
+                (self.values.value(owner), self.values.value(neighbour));
+            let value: Vec<f64> = (0..n)
+                .map(|j| weight * owner_value[j] + (1.0 - weight) * neighbour_value[j])
+                .collect();
+            add(owner, area, &value, 1.0);
+            add(neighbour, area, &value, -1.0);
+        }
+        let n_internal_faces = self.mesh.n_internal_faces();
+        for (i, value) in self.boundary_values.iter().enumerate() {
+            if let Some(value) = value {
+                let face = n_internal_faces + i;
+                add(
+                    self.mesh.owner.data[face],
+                    &self.face_areas[face],
+                    value,
+                    1.0,
+                );
+            }
+        }
+        for (cell, volume) in self.cell_volumes.iter().enumerate() {
+            for gradient in &mut gradients[cell * 3 * n..(cell + 1) * 3 * n] {
+                *gradient /= volume;
+            }
+        }
+        gradients
+    }
+
+    /// Returns the gradients of all cells, with 3 * n_components values per cell.
+    /// The differences to the neighbours are weighted by the inverse of their squared distance.
+    fn least_squares(&self) -> Vec<f64> {
+        let n = self.n_components;
+        let n_cells = self.mesh.n_cells();
+        let mut dd = vec![[[0.0; 3]; 3]; n_cells];
+        let mut sums = vec![0.0; n_cells * 3 * n];
+        let mut add = |cell: usize, d: &Vector, difference: &[f64]| {
+            let weight = 1.0 / dot(d, d);
+            for i in 0..3 {
+                for k in 0..3 {
+                    dd[cell][i][k] += weight * d[i] * d[k];
+                }
+                for j in 0..n {
+                    sums[(cell * 3 + i) * n + j] += weight * d[i] * difference[j];
+                }
+            }
+        };
+        let difference =
+            |a: &[f64], b: &[f64]| -> Vec<f64> { (0..n).map(|j| a[j] - b[j]).collect() };
+        for (face, &neighbour) in self.mesh.neighbour.data.iter().enumerate() {
+            let owner = self.mesh.owner.data[face];
+            let d = sub(&self.cell_centres[neighbour], &self.cell_centres[owner]);
+            let (owner_value, neighbour_value) =
+                (self.values.value(owner), self.values.value(neighbour));
+            add(owner, &d, &difference(neighbour_value, owner_value));
+            add(
+                neighbour,
+                &scale(&d, -1.0),
+                &difference(owner_value, neighbour_value),
+            );
+        }
+        let n_internal_faces = self.mesh.n_internal_faces();
+        for (i, value) in self.boundary_values.iter().enumerate() {
+            if let Some(value) = value {
+                let face = n_internal_faces + i;
+                let owner = self.mesh.owner.data[face];
+                let d = sub(&self.face_centres[face], &self.cell_centres[owner]);
+                add(owner, &d, &difference(value, self.values.value(owner)));
+            }
+        }
+
+        // directions without neighbours (e.g. the empty direction of 2D cases) get no gradient
+        let mut gradients = vec![0.0; n_cells * 3 * n];
+        for cell in 0..n_cells {
+            let mut matrix = dd[cell];
+            let scale = (0..3).map(|i| matrix[i][i]).fold(0.0, f64::max);
+            for (i, row) in matrix.iter_mut().enumerate() {
+                if row[i] <= 1e-12 * scale {
+                    row[i] = 1.0;
+                }
+            }
+            let Some(inverse) = invert(&matrix) else {
+                continue;
+            };
+            for i in 0..3 {
+                for j in 0..n {
+                    gradients[(cell * 3 + i) * n + j] = (0..3)
+                        .map(|k| inverse[i][k] * sums[(cell * 3 + k) * n + j])
+                        .sum();
+                }
+            }
+        }
+        gradients
+    }
+}
+
+/// Returns the inverse of a 3x3 matrix, or None if it is singular.
+fn invert(m: &[[f64; 3]; 3]) -> Option<[[f64; 3]; 3]> {
+    let cofactor = |i: usize, j: usize| {
+        let (r0, r1) = ((i + 1) % 3, (i + 2) % 3);
+        let (c0, c1) = ((j + 1) % 3, (j + 2) % 3);
+        m[r0][c0] * m[r1][c1] - m[r0][c1] * m[r1][c0]
+    };
+    let determinant: f64 = (0..3).map(|j| m[0][j] * cofactor(0, j)).sum();
+    if determinant.abs() <= f64::MIN_POSITIVE {
+        return None;
+    }
+    // the inverse is the transposed matrix of cofactors divided by the determinant
+    Some(std::array::from_fn(|i| {
+        std::array::from_fn(|j| cofactor(j, i) / determinant)
+    }))
+}
+
+fn invalid_input(message: String) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidInput, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base::FoamFile;
+
+    /// Returns a volume field with the values of a function at the cell and face centres.
+    /// Scalar values give a temperature field "T", vector values a velocity field "U".
+    fn field(mesh: &PolyMesh, f: impl Fn(&Vector) -> Vec<f64>) -> FileContent<ResultData> {
+        let to_field = |values: Vec<Vec<f64>>| match values.first().map(Vec::len) {
+            Some(1) => FoamField::Scalar(values.into_iter().map(|v| v[0]).collect()),
+            _ => FoamField::Vector(values),
+        };
+        let face_centres = mesh.face_centres();
+        let mut patches = IndexMap::new();
+        for (name, range) in mesh.boundary.data.face_ranges() {
+            let mut condition = IndexMap::new();
+            let values = range.map(|face| f(&face_centres[face])).collect();
+            condition.insert("value".to_string(), FoamValue::Field(to_field(values)));
+            patches.insert(name, FoamValue::Structure(FoamStructure(condition)));
+        }
+        let mut boundary_field = IndexMap::new();
+        boundary_field.insert(
+            "boundaryField".to_string(),
+            FoamValue::Structure(FoamStructure(patches)),
+        );
+        let result = to_field(mesh.cell_centres().iter().map(f).collect());
+        let (name, dimensions) = match result.n_components() {
+            1 => ("T", [0, 0, 0, 1, 0, 0, 0]),
+            _ => ("U", [0, 1, -1, 0, 0, 0, 0]),
+        };
+        FileContent {
+            location: Some(std::path::Path::new("0").join(name)),
+            meta: FoamFile::new(&FieldLocation::Cells.class(&result), "0", name),
+            data: ResultData {
+                dimensions: Dimensions::new(dimensions),
+                result,
+                boundary_field: Some(FoamStructure(boundary_field)),
+            },
+        }
+    }
+
+    #[test]
+    fn test_least_squares_gradient() {
+        let path = std::path::Path::new("tests/test_cases/original/two_zones_with_interfaces");
+        let mesh = PolyMesh::parse(&path.join("constant/polyMesh")).unwrap();
+        let t = field(&mesh, |x| vec![2.0 * x[0] + 3.0 * x[1] + x[2]]);
+        let gradient = mesh.gradient(&t, GradientScheme::LeastSquares).unwrap();
+        assert_eq!(gradient.meta.class(), Some("volVectorField"));
+        assert_eq!(gradient.location, Some("0/grad(T)".into()));
+        assert_eq!(
+            gradient.data.dimensions,
+            Dimensions::new([0, -1, 0, 1, 0, 0, 0])
+        );
+        for cell in 0..mesh.n_cells() {
+            let value = gradient.data.result.value(cell);
+            assert!(mag(&sub(&[value[0], value[1], value[2]], &[2.0, 3.0, 1.0])) < 1e-8);
+        }
+
+        let u = field(&mesh, |x| vec![x[0], 2.0 * x[1], 0.0]);
+        let gradient = mesh.gradient(&u, GradientScheme::LeastSquares).unwrap();
+        assert_eq!(gradient.meta.class(), Some("volTensorField"));
+        assert_eq!(gradient.location, Some("0/grad(U)".into()));
+        assert_eq!(
+            gradient.data.dimensions,
+            Dimensions::new([0, 0, -1, 0, 0, 0, 0])
+        );
+        let expected = [1.0, 0.0, 0.0, 0.0, 2.0, 0.0, 0.0, 0.0, 0.0];
+        for cell in 0..mesh.n_cells() {
+            let value = gradient.data.result.value(cell);
+            assert!(value
+                .iter()
+                .zip(expected)
+                .all(|(a, b)| (a - b).abs() < 1e-8));
+        }
+    }
+
+    #[test]
+    fn test_gauss_gradient() {
+        let path = std::path::Path::new("tests/test_cases/original/two_zones_with_interfaces");
+        let mesh = PolyMesh::parse(&path.join("constant/polyMesh")).unwrap();
+        let f = |x: &Vector| vec![x[0] * x[1] + x[2]];
+        let t = field(&mesh, f);
+        let gradient = mesh.gradient(&t, GradientScheme::GaussLinear).unwrap();
+        // the internal faces cancel out in the volume integral of the gradient
+        let volumes = mesh.cell_volumes();
+        let integral = (0..mesh.n_cells()).fold([0.0; 3], |sum, cell| {
+            let value = gradient.data.result.value(cell);
+            add(&sum, &scale(&[value[0], value[1], value[2]], volumes[cell]))
+        });
+        let (centres, areas) = (mesh.face_centres(), mesh.face_area_vectors());
+        let boundary = (mesh.n_internal_faces()..mesh.n_faces()).fold([0.0; 3], |sum, face| {
+            add(&sum, &scale(&areas[face], f(&centres[face])[0]))
+        });
+        assert!(mag(&sub(&integral, &boundary)) < 1e-9 * mag(&boundary));
+
+        // the gradient of a linear field on the cells without boundary faces
+        let exact = [2.0, 3.0, 1.0];
+        let linear = |x: &Vector| dot(&exact, x);
+        let t = field(&mesh, |x| vec![linear(x)]);
+        let gradient = mesh.gradient(&t, GradientScheme::GaussLinear).unwrap();
+        // On the tetrahedra of this mesh, the line between two cell centres misses the centre of
+        // their face, and linear interpolation gives the exact value at the intersection with the
+        // face instead. The exact gradient is corrected by the resulting error of the face values.
+        let (cell_centres, volumes) = mesh.cell_centres_and_volumes();
+        let mut correction = vec![[0.0; 3]; mesh.n_cells()];
+        for (face, &neighbour) in mesh.neighbour.data.iter().enumerate() {
+            let owner = mesh.owner.data[face];
+            let to_owner = dot(&areas[face], &sub(&centres[face], &cell_centres[owner]));
+            let to_neighbour = dot(&areas[face], &sub(&cell_centres[neighbour], &centres[face]));
+            let weight = to_neighbour / (to_owner + to_neighbour);
+            let intersection = add(
+                &scale(&cell_centres[owner], weight),
+                &scale(&cell_centres[neighbour], 1.0 - weight),
+            );
+            let error = scale(&areas[face], linear(&intersection) - linear(&centres[face]));
+            correction[owner] = add(&correction[owner], &error);
+            correction[neighbour] = sub(&correction[neighbour], &error);
+        }
+        let mut interior = vec![true; mesh.n_cells()];
+        for face in mesh.n_internal_faces()..mesh.n_faces() {
+            interior[mesh.owner.data[face]] = false;
+        }
+        let interior: Vec<usize> = (0..mesh.n_cells()).filter(|&c| interior[c]).collect();
+        assert!(!interior.is_empty());
+        for cell in interior {
+            let value = gradient.data.result.value(cell);
+            let expected = add(&exact, &scale(&correction[cell], 1.0 / volumes[cell]));
+            assert!(mag(&sub(&[value[0], value[1], value[2]], &expected)) < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_gradient_2d() {
+        let path = std::path::Path::new("tests/test_cases/original/cylinder");
+        let mesh = PolyMesh::parse(&path.join("constant/polyMesh")).unwrap();
+        let u = FileContent::<ResultData>::parse_file(&path.join("0/U")).unwrap();
+        for scheme in [GradientScheme::GaussLinear, GradientScheme::LeastSquares] {
+            let gradient = mesh.gradient(&u, scheme).unwrap();
+            // no gradient in the empty direction
+            for cell in 0..mesh.n_cells() {
+                let value = gradient.data.result.value(cell);
+                assert!(value[6..].iter().all(|c| c.abs() < 1e-6), "{:?}", value);
+            }
+        }
+        let phi = FileContent::<ResultData>::parse_file(&path.join("0/phi")).unwrap();
+        assert!(mesh.gradient(&phi, GradientScheme::GaussLinear).is_err());
+    }
+}
@@ -0,0 +1,194 @@
+use super::boundary::CONSTRAINT_PATCH_TYPES;
+use super::geometry::face_centre_and_area;
+use super::{FieldLocation, PolyMesh, ResultData};
+use crate::base::vector::{mag, sub, Vector};
+use crate::base::{FileContent, FoamField, FoamStructure, FoamValue};
+use indexmap::IndexMap;
+
+/// Interpolation of fields from the cell centres to the points, like OpenFOAM's volPointInterpolation.
+impl PolyMesh {
+    /// Interpolates a volume field to the points of the mesh, and returns it as a point field,
+    /// e.g. a "pointScalarField" for a "volScalarField". The point field is named after the
+    /// volume field, e.g. "volPointInterpolate(U)", and located in the same directory.
+    /// The point values are the inverse-distance weighted averages of the values of the cells
+    /// around each point. The points of patches that have values in the "boundaryField" of the
+    /// field are interpolated from the values of the patch faces instead, weighted by the inverse
+    /// distance to the face centres.
+    pub fn cell_to_point(
+        &self,
+        field: &FileContent<ResultData>,
+    ) -> std::io::Result<FileContent<ResultData>> {
+        let class = field.meta.class().unwrap_or_default();
+        if FieldLocation::from_class(class).is_some_and(|location| location != FieldLocation::Cells)
+        {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("Only volume fields can be interpolated, not {}.", class),
+            ));
+        }
+        let values = &field.data.result;
+        if let Some(n) = values.n_values().filter(|&n| n != self.n_cells()) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!(
+                    "The field has {} values, but there are {} cells.",
+                    n,
+                    self.n_cells()
+                ),
+            ));
+        }
+        let mut interpolation = Interpolation::new(self.n_points(), values.n_components());
+        let cell_points = self.cell_points();
+        for (cell, centre) in self.cell_centres().iter().enumerate() {
+            for &point in &cell_points[cell] {
+                interpolation.add(point, centre, &self.points.data[point], values.value(cell));
+            }
+        }
+
+        // the values of the patches replace the values of the cells at the boundary
+        let mut boundary = Interpolation::new(self.n_points(), values.n_components());
+        for (patch, range) in self.boundary.data.face_ranges() {
+            let Some(patch_values) = field.data.patch_value(&patch) else {
+                continue;
+            };
+            if patch_values.n_values().is_some_and(|n| n != range.len()) {
+                continue;
+            }
+            for (i, face) in range.enumerate() {
+                let vertices = &self.faces.data[face];
+                let (centre, _) = face_centre_and_area(&self.points.data, vertices);
+                for &point in vertices {
+                    let position = &self.points.data[point];
+                    boundary.add(point, &centre, position, patch_values.value(i));
+                }
+            }
+        }
+        let point_values = interpolation.finish(&boundary);
+
+        let name = format!("volPointInterpolate({})", field.name().unwrap_or_default());
+        let class = FieldLocation::Points.class(values);
+        let mut point_field = field.derived_field(
+            &name,
+            ResultData {
+                dimensions: field.data.dimensions.clone(),
+                result: point_values,
+                boundary_field: Some(self.point_boundary_field()),
+            },
+        );
+        point_field
+            .meta
+            .insert("class".to_string(), FoamValue::String(class));
+        Ok(point_field)
+    }
+
+    /// Returns the "boundaryField" entry of point fields, with the constraint types of the patches
+    /// and "calculated" for all other patches.
+    fn point_boundary_field(&self) -> FoamStructure {
+        let mut patches = IndexMap::new();
+        for name in self.boundary.data.keys() {
+            let patch_type = match self.boundary.data.patch_type(name) {
+                Some(patch_type) if CONSTRAINT_PATCH_TYPES.contains(&patch_type) => patch_type,
+                _ => "calculated",
+            };
+            let mut condition = IndexMap::new();
+            condition.insert(
+                "type".to_string(),
+                FoamValue::String(patch_type.to_string()),
+            );
+            patches.insert(name.clone(), FoamValue::Structure(FoamStructure(condition)));
+        }
+        let mut boundary_field = IndexMap::new();
+        boundary_field.insert(
+            "boundaryField".to_string(),
+            FoamValue::Structure(FoamStructure(patches)),
+        );
+        FoamStructure(boundary_field)
+    }
+}
+
+/// Accumulates the inverse-distance weighted sums of the values around each point.
+struct Interpolation {
+    sums: Vec<f64>,
+    weights: Vec<f64>,
+    n_components: usize,
+}
+
+impl Interpolation {
+    fn new(n_points: usize, n_components: usize) -> Self {
+        Interpolation {
+            sums: vec![0.0; n_points * n_components],
+            weights: vec![0.0; n_points],
+            n_components,
+        }
+    }
+
+    fn add(&mut self, point: usize, source: &Vector, position: &Vector, value: &[f64]) {
+        let weight = 1.0 / mag(&sub(position, source)).max(f64::MIN_POSITIVE);
+        self.weights[point] += weight;
+        let sums = &mut self.sums[point * self.n_components..(point + 1) * self.n_components];
+        for (sum, component) in sums.iter_mut().zip(value) {
+            *sum += weight * component;
+        }
+    }
+
+    /// Returns the point values, taken from `priority` where it has values.
+    fn finish(self, priority: &Interpolation) -> FoamField {
+        let n = self.n_components;
+        let values = (0..self.weights.len()).map(|point| {
+            let source = if priority.weights[point] > 0.0 {
+                priority
+            } else {
+                &self
+            };
+            let weight = source.weights[point];
+            source.sums[point * n..(point + 1) * n]
+                .iter()
+                .map(|sum| if weight > 0.0 { sum / weight } else { 0.0 })
+                .collect::<Vec<f64>>()
+        });
+        if n == 1 {
+            FoamField::Scalar(values.map(|value| value[0]).collect())
+        } else {
+            FoamField::Vector(values.collect())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base::FileElement;
+
+    #[test]
+    fn test_cell_to_point() {
+        let path = std::path::Path::new("tests/test_cases/original/cylinder");
+        let mesh = PolyMesh::parse(&path.join("constant/polyMesh")).unwrap();
+        let u = FileContent::<ResultData>::parse_file(&path.join("0/U")).unwrap();
+        let point_u = mesh.cell_to_point(&u).unwrap();
+        assert_eq!(point_u.meta.class(), Some("pointVectorField"));
+        // the point field does not overwrite the volume field when written
+        assert_eq!(point_u.name(), Some("volPointInterpolate(U)"));
+        assert_eq!(
+            point_u.location,
+            Some(std::path::PathBuf::from("0/volPointInterpolate(U)"))
+        );
+        assert_ne!(point_u.location, u.location);
+        assert_eq!(point_u.data.result.n_values(), Some(mesh.n_points()));
+        // the points of the inlet take the inlet value
+        let inlet = mesh.boundary.data.face_range("left").unwrap();
+        let point = mesh.faces.data[inlet.start][0];
+        assert_eq!(point_u.data.result.value(point), &[1.0, 0.0, 0.0]);
+        let (_, reparsed) = ResultData::parse(&point_u.data.to_string()).unwrap();
+        assert_eq!(reparsed, point_u.data);
+
+        // a uniform field stays uniform
+        let mut uniform = u.clone();
+        uniform.data.result = FoamField::UniformScalar(2.0);
+        uniform.data.boundary_field = None;
+        let point_values = mesh.cell_to_point(&uniform).unwrap().data.result;
+        assert!((0..mesh.n_points()).all(|p| (point_values.value(p)[0] - 2.0).abs() < 1e-12));
+
+        let phi = FileContent::<ResultData>::parse_file(&path.join("0/phi")).unwrap();
+        assert!(mesh.cell_to_point(&phi).is_err());
+    }
+}
@@ -0,0 +1,546 @@
+use super::search::{BoundBox, Octree};
+use super::{FieldLocation, Point, PolyMesh, ResultData, TimeDir};
+use crate::base::vector::*;
+use crate::base::{FileContent, FoamField, FoamValue};
+use indexmap::IndexMap;
+use std::collections::HashMap;
+use std::ops::Range;
+
+/// How the cell values are mapped from one mesh to another, like the options of OpenFOAM's
+/// mapFields.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MapMethod {
+    /// Each cell takes the value of the source cell that contains its centre, or of the nearest
+    /// source cell if its centre is outside of the source mesh.
+    CellCentre,
+    /// Each cell takes the average of the source cells it overlaps, weighted by the overlap
+    /// volume. The overlap volumes are computed by intersecting the tetrahedra of the cells, so
+    /// volume integrals are conserved up to round-off if both meshes cover the same domain and
+    /// have planar faces. Cells outside of the source mesh take the value of the nearest source
+    /// cell.
+    VolumeWeighted,
+}
+
+/// The mapping of the volume fields of a source mesh onto a target mesh, like OpenFOAM's mapFields.
+pub struct MeshToMesh<'a> {
+    target: &'a PolyMesh,
+    n_source_cells: usize,
+    /// The source cells and their weights for each target cell.
+    weights: Vec<Vec<(usize, f64)>>,
+    /// For the target patches that also exist in the source mesh: the range of the source patch,
+    /// and the nearest face of the source patch (relative to its start) for each target face.
+    patch_maps: IndexMap<String, (Range<usize>, Vec<usize>)>,
+}
+
+impl<'a> MeshToMesh<'a> {
+    /// Computes the mapping of the cells and of the patches found in both meshes.
+    /// An error is returned if the source mesh has no cells to map from.
+    pub fn new(
+        source: &PolyMesh,
+        target: &'a PolyMesh,
+        method: MapMethod,
+    ) -> std::io::Result<Self> {
+        let search = source.search();
+        let nearest = |point: &Point| {
+            search.find_nearest_cell(point).ok_or_else(|| {
+                std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    "The source mesh has no cells.",
+                )
+            })
+        };
+        let weights = match method {
+            MapMethod::CellCentre => target
+                .cell_centres()
+                .iter()
+                .map(|centre| Ok(vec![(nearest(centre)?, 1.0)]))
+                .collect::<std::io::Result<_>>()?,
+            MapMethod::VolumeWeighted => volume_weights(source, target, nearest)?,
+        };
+
+        let source_centres = source.face_centres();
+        let target_centres = target.face_centres();
+        let mut patch_maps = IndexMap::new();
+        for (name, range) in target.boundary.data.face_ranges() {
+            let Some(source_range) = source
+                .boundary
+                .data
+                .face_range(&name)
+                .filter(|range| !range.is_empty())
+            else {
+                continue;
+            };
+            let centres = &source_centres[source_range.clone()];
+            let boxes: Vec<BoundBox> = centres
+                .iter()
+                .map(|centre| BoundBox::from_points([centre]))
+                .collect();
+            let tree = Octree::new(&boxes);
+            let map = range
+                .map(|face| {
+                    let centre = &target_centres[face];
+                    tree.nearest(centre, |i| mag(&sub(&centres[i], centre)))
+                        .map_or(0, |(i, _)| i)
+                })
+                .collect();
+            patch_maps.insert(name, (source_range, map));
+        }
+
+        Ok(MeshToMesh {
+            target,
+            n_source_cells: source.n_cells(),
+            weights,
+            patch_maps,
+        })
+    }
+
+    /// Maps a volume field onto the target mesh.
+    /// The boundary conditions of the patches that exist in both meshes are kept, and their
+    /// nonuniform entries, e.g. "value", are mapped from the nearest faces of the source patch.
+    /// The other patches get their constraint type, or a "calculated" condition with the values
+    /// of the adjacent cells.
+    pub fn map_field(
+        &self,
+        field: &FileContent<ResultData>,
+    ) -> std::io::Result<FileContent<ResultData>> {
+        let class = field.meta.class().unwrap_or_default();
+        if FieldLocation::from_class(class) != Some(FieldLocation::Cells) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("Only volume fields can be mapped, not {}.", class),
+            ));
+        }
+        let values = &field.data.result;
+        if let Some(n) = values.n_values().filter(|&n| n != self.n_source_cells) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!(
+                    "The field has {} values, but there are {} cells.",
+                    n, self.n_source_cells
+                ),
+            ));
+        }
+        let result = if values.is_uniform() {
+            values.clone()
+        } else {
+            FoamField::from_values(
+                self.weights
+                    .iter()
+                    .map(|weights| {
+                        let mut value = vec![0.0; values.n_components()];
+                        for &(cell, weight) in weights {
+                            for (sum, component) in value.iter_mut().zip(values.value(cell)) {
+                                *sum += weight * component;
+                            }
+                        }
+                        value
+                    })
+                    .collect(),
+            )
+        };
+        let owner = &self.target.owner.data;
+        let mut boundary_field = self.target.calculated_boundary_field(|_, range| {
+            result.subset(&range.map(|face| owner[face]).collect::<Vec<_>>())
+        });
+        if let Some(FoamValue::Structure(patches)) = boundary_field.get_mut("boundaryField") {
+            for (name, (source_range, map)) in &self.patch_maps {
+                let Some(FoamValue::Structure(condition)) = field
+                    .data
+                    .boundary_patches()
+                    .and_then(|source_patches| source_patches.get(name))
+                else {
+                    continue;
+                };
+                let mut condition = condition.clone();
+                for entry in condition.values_mut() {
+                    if let FoamValue::Field(values) = entry {
+                        if values.n_values() == Some(source_range.len()) {
+                            *values = values.subset(map);
+                        }
+                    }
+                }
+                patches.insert(name.clone(), FoamValue::Structure(condition));
+            }
+        }
+        Ok(field.with_data(ResultData {
+            dimensions: field.data.dimensions.clone(),
+            result,
+            boundary_field: Some(boundary_field),
+        }))
+    }
+
+    /// Maps all volume fields of a time directory onto the target mesh. The other fields, e.g.
+    /// the face fluxes, and the mesh of the time directory are left out.
+    pub fn map_time_dir(&self, time_dir: &TimeDir) -> std::io::Result<TimeDir> {
+        let mut field_values = HashMap::new();
+        for (name, field) in time_dir.field_values.iter() {
+            let class = field.meta.class().unwrap_or_default();
+            if FieldLocation::from_class(class) == Some(FieldLocation::Cells) {
+                field_values.insert(name.clone(), self.map_field(field)?);
+            }
+        }
+        Ok(TimeDir {
+            field_values,
+            mesh: None,
+            ..time_dir.clone()
+        })
+    }
+}
+
+/// Returns the source cells overlapped by each target cell, with the fractions of its volume.
+/// The overlap volumes are the sums of the intersections of the tetrahedra of the target cell with
+/// those of the source cells, see `cell_tets`.
+fn volume_weights(
+    source: &PolyMesh,
+    target: &PolyMesh,
+    nearest: impl Fn(&Point) -> std::io::Result<usize>,
+) -> std::io::Result<Vec<Vec<(usize, f64)>>> {
+    let source_tets = cell_tets(source);
+    let source_boxes: Vec<BoundBox> = source_tets
+        .iter()
+        .map(|tets| BoundBox::from_points(tets.iter().flat_map(|tet| &tet.points)))
+        .collect();
+    let tree = Octree::new(&source_boxes);
+    let cell_centres = target.cell_centres();
+    cell_tets(target)
+        .iter()
+        .enumerate()
+        .map(|(cell, tets)| {
+            let cell_box = BoundBox::from_points(tets.iter().flat_map(|tet| &tet.points));
+            let mut volumes: Vec<(usize, f64)> = Vec::new();
+            for source_cell in tree.overlapping(&cell_box) {
+                if !source_boxes[source_cell].overlaps(&cell_box) {
+                    continue;
+                }
+                let candidates: Vec<&Tet> = source_tets[source_cell]
+                    .iter()
+                    .filter(|source_tet| source_tet.bound_box.overlaps(&cell_box))
+                    .collect();
+                let mut volume = 0.0;
+                for tet in tets {
+                    if !tet.bound_box.overlaps(&source_boxes[source_cell]) {
+                        continue;
+                    }
+                    for source_tet in &candidates {
+                        if tet.bound_box.overlaps(&source_tet.bound_box) {
+                            volume += tet.sign * source_tet.sign * tet.intersection(source_tet);
+                        }
+                    }
+                }
+                if volume != 0.0 {
+                    volumes.push((source_cell, volume));
+                }
+            }
+            let cell_volume: f64 = tets
+                .iter()
+                .map(|tet| tet.sign * tet_volume(&tet.points))
+                .sum();
+            let total: f64 = volumes.iter().map(|(_, volume)| volume).sum();
+            if total <= 1e-12 * cell_volume.abs() {
+                return Ok(vec![(nearest(&cell_centres[cell])?, 1.0)]);
+            }
+            Ok(volumes
+                .into_iter()
+                .map(|(source, volume)| (source, volume / total))
+                .collect())
+        })
+        .collect()
+}
+
+/// A tetrahedron of the decomposition of a cell.
+struct Tet {
+    points: [Point; 4],
+    /// The sign of its volume, negative where a face is seen from behind by the cell centre.
+    sign: f64,
+    bound_box: BoundBox,
+    /// The outward normals and the offsets of the planes of its faces: a point is inside if
+    /// `dot(normal, point) <= offset` for all of them.
+    planes: [(Point, f64); 4],
+}
+
+impl Tet {
+    /// Returns the tetrahedron with the sign of its volume multiplied by `orientation`, or None
+    /// if it has no volume.
+    fn new(points: [Point; 4], orientation: f64) -> Option<Self> {
+        let (a, b, c) = (
+            sub(&points[1], &points[0]),
+            sub(&points[2], &points[0]),
+            sub(&points[3], &points[0]),
+        );
+        let volume = dot(&a, &cross(&b, &c));
+        if volume == 0.0 {
+            return None;
+        }
+        let planes = std::array::from_fn(|opposite| {
+            let origin = &points[(opposite + 1) % 4];
+            let normal = cross(
+                &sub(&points[(opposite + 2) % 4], origin),
+                &sub(&points[(opposite + 3) % 4], origin),
+            );
+            // the normal points away from the opposite vertex
+            let normal = if dot(&normal, &sub(&points[opposite], origin)) > 0.0 {
+                scale(&normal, -1.0)
+            } else {
+                normal
+            };
+            (normal, dot(&normal, origin))
+        });
+        Some(Tet {
+            points,
+            sign: orientation * volume.signum(),
+            bound_box: BoundBox::from_points(&points),
+            planes,
+        })
+    }
+
+    /// Returns true if a plane of a face separates the other tetrahedron from this one.
+    fn separates(&self, other: &Tet) -> bool {
+        self.planes.iter().any(|(normal, offset)| {
+            other
+                .points
+                .iter()
+                .all(|point| dot(normal, point) >= *offset)
+        })
+    }
+
+    /// Returns the volume of the intersection with another tetrahedron, by clipping this one with
+    /// the planes of the faces of the other one.
+    fn intersection(&self, other: &Tet) -> f64 {
+        if self.separates(other) || other.separates(self) {
+            return 0.0;
+        }
+        let mut pieces = vec![self.points];
+        let mut clipped = Vec::new();
+        for plane in &other.planes {
+            clipped.clear();
+            for piece in &pieces {
+                clip_tet(piece, plane, &mut clipped);
+            }
+            std::mem::swap(&mut pieces, &mut clipped);
+            if pieces.is_empty() {
+                return 0.0;
+            }
+        }
+        pieces.iter().map(tet_volume).sum()
+    }
+}
+
+/// Decomposes the cells into tetrahedra formed by the cell centre, the face centre and two
+/// consecutive points of a face. The tetrahedra of neighbouring cells share their triangles on the
+/// common face, so the tetrahedra of a mesh fill its domain without gaps. Tetrahedra with a
+/// negative volume, e.g. in non-convex cells, are subtracted from the cell.
+fn cell_tets(mesh: &PolyMesh) -> Vec<Vec<Tet>> {
+    let points = &mesh.points.data;
+    let face_centres = mesh.face_centres();
+    let cell_centres = mesh.cell_centres();
+    mesh.cell_faces()
+        .iter()
+        .enumerate()
+        .map(|(cell, faces)| {
+            let mut tets = Vec::new();
+            for &face in faces {
+                let vertices = &mesh.faces.data[face];
+                // the faces point out of their owner cell
+                let orientation = if mesh.owner.data[face] == cell {
+                    1.0
+                } else {
+                    -1.0
+                };
+                for i in 0..vertices.len() {
+                    let points = [
+                        cell_centres[cell],
+                        face_centres[face],
+                        points[vertices[i]],
+                        points[vertices[(i + 1) % vertices.len()]],
+                    ];
+                    tets.extend(Tet::new(points, orientation));
+                }
+            }
+            tets
+        })
+        .collect()
+}
+
+/// Adds the part of a tetrahedron behind a plane to `pieces`, split into tetrahedra.
+fn clip_tet(tet: &[Point; 4], (normal, offset): &(Point, f64), pieces: &mut Vec<[Point; 4]>) {
+    let distance: [f64; 4] = std::array::from_fn(|i| dot(normal, &tet[i]) - offset);
+    let is_inside = |i: usize| distance[i] <= 0.0;
+    // the vertices behind the plane first
+    let mut order = [0, 1, 2, 3];
+    order.sort_by_key(|&i| !is_inside(i));
+    let [a, b, c, d] = order;
+    // the point where the plane cuts the edge from an inside to an outside vertex
+    let cut = |i: usize, j: usize| {
+        let t = distance[i] / (distance[i] - distance[j]);
+        add(&tet[i], &scale(&sub(&tet[j], &tet[i]), t))
+    };
+    match (0..4).filter(|&i| is_inside(i)).count() {
+        0 => {}
+        1 => pieces.push([tet[a], cut(a, b), cut(a, c), cut(a, d)]),
+        2 => split_prism(
+            [tet[a], cut(a, c), cut(a, d)],
+            [tet[b], cut(b, c), cut(b, d)],
+            pieces,
+        ),
+        3 => split_prism(
+            [tet[a], tet[b], tet[c]],
+            [cut(a, d), cut(b, d), cut(c, d)],
+            pieces,
+        ),
+        _ => pieces.push(*tet),
+    }
+}
+
+/// Splits the prism between the triangles `p` and `q` into three tetrahedra, where `p[i]` and
+/// `q[i]` are the ends of an edge of the prism.
+fn split_prism(p: [Point; 3], q: [Point; 3], pieces: &mut Vec<[Point; 4]>) {
+    pieces.push([p[0], p[1], p[2], q[0]]);
+    pieces.push([p[1], p[2], q[0], q[1]]);
+    pieces.push([p[2], q[0], q[1], q[2]]);
+}
+
+fn tet_volume(tet: &[Point; 4]) -> f64 {
+    let (a, b, c) = (
+        sub(&tet[1], &tet[0]),
+        sub(&tet[2], &tet[0]),
+        sub(&tet[3], &tet[0]),
+    );
+    dot(&a, &cross(&b, &c)).abs() / 6.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const UNIT_TET: [Point; 4] = [
+        [0.0, 0.0, 0.0],
+        [1.0, 0.0, 0.0],
+        [0.0, 1.0, 0.0],
+        [0.0, 0.0, 1.0],
+    ];
+
+    #[test]
+    fn test_clip_tet() {
+        let volume = |plane: (Point, f64)| {
+            let mut pieces = Vec::new();
+            clip_tet(&UNIT_TET, &plane, &mut pieces);
+            pieces.iter().map(tet_volume).sum::<f64>()
+        };
+        // one, two and three vertices behind the plane
+        let planes = [
+            ([-1.0, 0.0, 0.0], -0.5),
+            ([1.0, 1.0, 0.0], 0.5),
+            ([1.0, 0.0, 0.0], 0.5),
+        ];
+        for (normal, offset) in planes {
+            let behind = volume((normal, offset));
+            let before = volume((scale(&normal, -1.0), -offset));
+            assert!(behind > 0.0 && before > 0.0);
+            assert!((behind + before - 1.0 / 6.0).abs() < 1e-15);
+        }
+        assert!((volume(planes[0]) - 0.125 / 6.0).abs() < 1e-15);
+    }
+
+    #[test]
+    fn test_intersection() {
+        let tet = |shift: Point| Tet::new(UNIT_TET.map(|point| add(&point, &shift)), 1.0).unwrap();
+        let unit = tet([0.0; 3]);
+        assert!((unit.intersection(&unit) - 1.0 / 6.0).abs() < 1e-15);
+        // the intersection with the tetrahedron shifted by half an edge is half as large in
+        // every direction
+        let shifted = tet([0.5, 0.0, 0.0]);
+        assert!((unit.intersection(&shifted) - 0.125 / 6.0).abs() < 1e-15);
+        assert!((shifted.intersection(&unit) - 0.125 / 6.0).abs() < 1e-15);
+        assert_eq!(unit.intersection(&tet([2.0, 0.0, 0.0])), 0.0);
+    }
+
+    /// Returns a copy of the mesh with the points moved within the planes of the front and back
+    /// patches. The points of the other patches stay, so that both meshes cover the same domain.
+    fn distorted(mesh: &PolyMesh) -> PolyMesh {
+        let points = &mesh.points.data;
+        let boundary = &mesh.boundary.data;
+        let mut fixed = vec![false; points.len()];
+        for (name, range) in boundary.face_ranges() {
+            if boundary.patch_type(&name) != Some("empty") {
+                for face in range {
+                    for &point in &mesh.faces.data[face] {
+                        fixed[point] = true;
+                    }
+                }
+            }
+        }
+        // the shortest edge in the x-y plane at each point
+        let mut edge_length = vec![f64::INFINITY; points.len()];
+        for face in mesh.faces.data.iter() {
+            for i in 0..face.len() {
+                let (p, q) = (face[i], face[(i + 1) % face.len()]);
+                let edge = sub(&points[p], &points[q]);
+                let length = mag(&[edge[0], edge[1], 0.0]);
+                if length > 0.0 {
+                    edge_length[p] = edge_length[p].min(length);
+                    edge_length[q] = edge_length[q].min(length);
+                }
+            }
+        }
+        let mut distorted = mesh.clone();
+        for (point, p) in distorted.points.data.iter_mut().zip(0..) {
+            if !fixed[p] {
+                let [x, y, _] = *point;
+                let shift = 0.2 * edge_length[p];
+                point[0] += shift * (3.0 * x + 5.0 * y).sin();
+                point[1] += shift * (4.0 * x - 2.0 * y).cos();
+            }
+        }
+        distorted
+    }
+
+    #[test]
+    fn test_map_fields() {
+        let path = std::path::Path::new("tests/test_cases/original/cylinder");
+        let mesh = PolyMesh::parse(&path.join("constant/polyMesh")).unwrap();
+        let mut u = FileContent::<ResultData>::parse_file(&path.join("0/U")).unwrap();
+        let centres = mesh.cell_centres();
+        u.data.result = FoamField::Vector(centres.iter().map(|c| vec![c[0], c[1], 1.0]).collect());
+
+        // mapping onto the same mesh keeps the values and the boundary conditions
+        let mapping = MeshToMesh::new(&mesh, &mesh, MapMethod::CellCentre).unwrap();
+        let mapped = mapping.map_field(&u).unwrap();
+        assert_eq!(mapped.data.result, u.data.result);
+        assert_eq!(mapped.data.boundary_field, u.data.boundary_field);
+
+        // the volume-weighted mapping onto a different mesh of the same domain keeps the volume
+        // integral, checked on a part of the mesh to keep the test fast
+        let cells = mesh.cells_where(|centre| centre[0] < -1.0);
+        let subset = mesh.subset(&cells, "cut").unwrap();
+        let u = subset.map_field(&u).unwrap();
+        let source = subset.mesh;
+        let target = distorted(&source);
+        let mapping = MeshToMesh::new(&source, &target, MapMethod::VolumeWeighted).unwrap();
+        let mapped = mapping.map_field(&u).unwrap();
+        assert_ne!(mapped.data.result, u.data.result);
+        let integral = |field: &FoamField, mesh: &PolyMesh| match (field
+            * &FoamField::Scalar(mesh.cell_volumes()))
+            .sum()
+        {
+            FoamField::UniformVector(value) => value,
+            _ => panic!("Not a uniform vector."),
+        };
+        let expected = integral(&u.data.result, &source);
+        let actual = integral(&mapped.data.result, &target);
+        assert!((0..3).all(|i| (expected[i] - actual[i]).abs() < 1e-12 * expected[2].abs()));
+
+        let phi = FileContent::<ResultData>::parse_file(&path.join("0/phi")).unwrap();
+        assert!(mapping.map_field(&phi).is_err());
+    }
+
+    #[test]
+    fn test_map_from_empty_mesh() {
+        let path = std::path::Path::new("tests/test_cases/original/cylinder");
+        let mesh = PolyMesh::parse(&path.join("constant/polyMesh")).unwrap();
+        let empty = mesh.subset(&[], "cut").unwrap().mesh;
+        for method in [MapMethod::CellCentre, MapMethod::VolumeWeighted] {
+            let error = MeshToMesh::new(&empty, &mesh, method).err().unwrap();
+            assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+        }
+    }
+}
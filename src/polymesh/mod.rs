@@ -1,33 +1,94 @@
-use crate::base::FileContent;
+use crate::base::{parallel, Compression, FileContent};
 use std::path;
 
+mod addressing;
 mod boundary;
+mod celltoregion;
 mod cellzones;
+mod coupled;
+mod cuttingplane;
+mod dimensions;
 mod faces;
 mod facezones;
+mod fieldvalue;
+mod flowfields;
+mod geometry;
+mod gradient;
+mod interpolation;
+mod mapfields;
 mod neighbour;
 mod owner;
+mod patchtopology;
 mod points;
 mod pointzones;
+mod probes;
+mod regionproperties;
+mod renumber;
+mod repatch;
 mod result;
+mod sampledsets;
+mod search;
 mod sets;
+mod splitregions;
+mod subset;
+mod surface;
 mod timedir;
+mod timeselector;
+mod toposet;
 mod uniform;
 mod zones;
 
 // re-exports
 pub use boundary::BoundaryData;
+pub use celltoregion::CellToRegionData;
 pub use cellzones::CellZone;
+pub use coupled::CoupledKind;
+pub use coupled::CoupledPatch;
+pub use coupled::CoupledTransform;
+pub use cuttingplane::CuttingPlane;
+pub use dimensions::Dimensions;
+pub use dimensions::Exponent;
 pub use faces::FaceData;
+pub use faces::FaceIter;
 pub use facezones::FaceZone;
+pub use gradient::GradientScheme;
+pub use mapfields::MapMethod;
+pub use mapfields::MeshToMesh;
 pub use neighbour::NeighbourData;
 pub use owner::OwnerData;
+pub use patchtopology::PatchTopology;
+pub use patchtopology::SurfaceTopology;
+pub use points::Point;
 pub use points::PointData;
 pub use pointzones::PointZone;
+pub use probes::ProbeInterpolation;
+pub use probes::Probes;
+pub use probes::UNSET_PROBE_VALUE;
+pub use regionproperties::RegionPropertiesData;
+pub use renumber::Band;
+pub use renumber::CellOrdering;
+pub use renumber::MeshRenumbering;
+pub use repatch::MeshRepatching;
+pub use result::FieldLocation;
 pub use result::ResultData;
+pub use sampledsets::LineSampling;
+pub use sampledsets::SampledSet;
+pub use sampledsets::SetAxis;
+pub use search::MeshSearch;
 pub use sets::Set;
 pub use sets::Sets;
+pub use splitregions::RegionSplit;
+pub use subset::MeshSubset;
+pub use surface::PatchSurface;
+pub use timedir::MeshUpdate;
 pub use timedir::TimeDir;
+pub use timeselector::TimeSelector;
+pub use toposet::SetAction;
+pub use toposet::SetConnection;
+pub use toposet::SetType;
+pub use toposet::TopoSetSource;
+
+pub(crate) use timeselector::same_time;
 pub use uniform::UniformData;
 pub use zones::Zone;
 pub use zones::ZoneData;
@@ -48,10 +109,22 @@ pub struct PolyMesh {
 
 impl PolyMesh {
     pub fn parse(dir_path: &path::Path) -> std::io::Result<PolyMesh> {
-        let points = FileContent::<PointData>::parse_file(&dir_path.join("points"))?;
-        let faces = FileContent::<FaceData>::parse_file(&dir_path.join("faces"))?;
-        let owner = FileContent::<OwnerData>::parse_file(&dir_path.join("owner"))?;
-        let neighbour = FileContent::<NeighbourData>::parse_file(&dir_path.join("neighbour"))?;
+        // the large files are parsed concurrently if the "rayon" feature is enabled
+        let ((points, faces), (owner, neighbour)) = parallel::join(
+            || {
+                parallel::join(
+                    || FileContent::<PointData>::parse_file(&dir_path.join("points")),
+                    || FileContent::<FaceData>::parse_file(&dir_path.join("faces")),
+                )
+            },
+            || {
+                parallel::join(
+                    || FileContent::<OwnerData>::parse_file(&dir_path.join("owner")),
+                    || FileContent::<NeighbourData>::parse_file(&dir_path.join("neighbour")),
+                )
+            },
+        );
+        let (points, faces, owner, neighbour) = (points?, faces?, owner?, neighbour?);
         let boundary = FileContent::<BoundaryData>::parse_file(&dir_path.join("boundary"))?;
         let facezones =
             FileContent::<ZoneData<FaceZone>>::parse_file(&dir_path.join("faceZones")).ok();
@@ -73,23 +146,70 @@ impl PolyMesh {
         })
     }
 
+    /// Moves all files of the mesh to the given directory, relative to the case directory,
+    /// e.g. "constant/fluid/polyMesh" for the mesh of the region "fluid".
+    pub fn set_directory(&mut self, dir: &path::Path) {
+        self.points.set_location(&dir.join("points"));
+        self.faces.set_location(&dir.join("faces"));
+        self.owner.set_location(&dir.join("owner"));
+        self.neighbour.set_location(&dir.join("neighbour"));
+        self.boundary.set_location(&dir.join("boundary"));
+        if let Some(facezones) = &mut self.facezones {
+            facezones.set_location(&dir.join("faceZones"));
+        }
+        if let Some(cellzones) = &mut self.cellzones {
+            cellzones.set_location(&dir.join("cellZones"));
+        }
+        if let Some(pointzones) = &mut self.pointzones {
+            pointzones.set_location(&dir.join("pointZones"));
+        }
+        if let Some(sets) = &mut self.sets {
+            for (name, set) in sets.iter_mut() {
+                set.set_location(&dir.join("sets").join(name));
+            }
+        }
+    }
+
     pub fn write(&self, path: &path::Path) -> std::io::Result<()> {
-        self.points.write_file(path)?;
-        self.faces.write_file(path)?;
-        self.owner.write_file(path)?;
-        self.neighbour.write_file(path)?;
-        self.boundary.write_file(path)?;
+        self.write_compressed(path, Compression::Uncompressed)
+    }
+
+    /// Writes the mesh with the given compression.
+    pub fn write_compressed(
+        &self,
+        path: &path::Path,
+        compression: Compression,
+    ) -> std::io::Result<()> {
+        let ((points, faces), (owner, neighbour)) = parallel::join(
+            || {
+                parallel::join(
+                    || self.points.write_file_compressed(path, compression),
+                    || self.faces.write_file_compressed(path, compression),
+                )
+            },
+            || {
+                parallel::join(
+                    || self.owner.write_file_compressed(path, compression),
+                    || self.neighbour.write_file_compressed(path, compression),
+                )
+            },
+        );
+        points?;
+        faces?;
+        owner?;
+        neighbour?;
+        self.boundary.write_file_compressed(path, compression)?;
         if let Some(facezones) = &self.facezones {
-            facezones.write_file(path)?;
+            facezones.write_file_compressed(path, compression)?;
         }
         if let Some(cellzones) = &self.cellzones {
-            cellzones.write_file(path)?;
+            cellzones.write_file_compressed(path, compression)?;
         }
         if let Some(pointzones) = &self.pointzones {
-            pointzones.write_file(path)?;
+            pointzones.write_file_compressed(path, compression)?;
         }
         if let Some(sets) = &self.sets {
-            sets.write(path)?;
+            sets.write_compressed(path, compression)?;
         }
         Ok(())
     }
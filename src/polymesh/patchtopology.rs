@@ -0,0 +1,267 @@
+use super::geometry::face_centre_and_area;
+use super::{FaceData, Point, PolyMesh};
+use crate::base::vector::*;
+use std::collections::HashMap;
+use std::ops::Range;
+
+/// The edges and the connectivity of the faces of a patch, like OpenFOAM's primitivePatch.
+/// The faces are counted from the start of the patch, and the points are points of the mesh.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PatchTopology {
+    /// The edges of the patch, given by their two points, in the order in which they first
+    /// appear in the faces.
+    pub edges: Vec<[usize; 2]>,
+    /// The faces that use each edge.
+    pub edge_faces: Vec<Vec<usize>>,
+    /// The edges of each face, in the order of its points.
+    pub face_edges: Vec<Vec<usize>>,
+    /// The unit normal of each face.
+    pub face_normals: Vec<Point>,
+}
+
+/// The topology of a patch surface, as reported by OpenFOAM's checkMesh.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SurfaceTopology {
+    /// Every edge is used by two faces, and the faces around each point are connected.
+    ClosedSinglyConnected,
+    /// Like `ClosedSinglyConnected`, but some edges are only used by one face.
+    OpenSinglyConnected,
+    /// Some point is shared by faces that are not connected through the edges of that point.
+    MultiplyConnectedPoint,
+    /// Some edge is used by more than two faces.
+    MultiplyConnectedEdge,
+}
+
+impl PolyMesh {
+    /// Returns the topology of a patch, see `PatchTopology`.
+    pub fn patch_topology(&self, name: &str) -> std::io::Result<PatchTopology> {
+        let range = self.boundary.data.face_range(name).ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("Patch {} not found.", name),
+            )
+        })?;
+        Ok(PatchTopology::new(
+            &self.faces.data,
+            &self.points.data,
+            range,
+        ))
+    }
+}
+
+impl PatchTopology {
+    /// Computes the topology of the faces in `range`.
+    pub fn new(faces: &FaceData, points: &[Point], range: Range<usize>) -> Self {
+        let mut edges = Vec::new();
+        let mut edge_faces: Vec<Vec<usize>> = Vec::new();
+        let mut indices: HashMap<(usize, usize), usize> = HashMap::new();
+        let mut face_edges = Vec::with_capacity(range.len());
+        let mut face_normals = Vec::with_capacity(range.len());
+        for (i, face) in range.map(|face| &faces[face]).enumerate() {
+            let mut this_face_edges = Vec::with_capacity(face.len());
+            for (j, &a) in face.iter().enumerate() {
+                let b = face[(j + 1) % face.len()];
+                let edge = *indices.entry((a.min(b), a.max(b))).or_insert_with(|| {
+                    edges.push([a, b]);
+                    edge_faces.push(Vec::new());
+                    edges.len() - 1
+                });
+                edge_faces[edge].push(i);
+                this_face_edges.push(edge);
+            }
+            face_edges.push(this_face_edges);
+            let (_, area) = face_centre_and_area(points, face);
+            let length = mag(&area);
+            face_normals.push(if length > f64::MIN_POSITIVE {
+                scale(&area, 1.0 / length)
+            } else {
+                area
+            });
+        }
+        PatchTopology {
+            edges,
+            edge_faces,
+            face_edges,
+            face_normals,
+        }
+    }
+
+    pub fn n_faces(&self) -> usize {
+        self.face_edges.len()
+    }
+
+    /// Returns the edges that are used by a single face, i.e. the edges along the border of the
+    /// patch.
+    pub fn boundary_edges(&self) -> Vec<usize> {
+        self.edges_where(|faces| faces.len() == 1)
+    }
+
+    /// Returns the edges that are used by more than two faces.
+    pub fn non_manifold_edges(&self) -> Vec<usize> {
+        self.edges_where(|faces| faces.len() > 2)
+    }
+
+    /// Returns the edges between two faces whose normals differ by more than the given angle,
+    /// in degrees, like the "featureAngle" of OpenFOAM's tools.
+    pub fn feature_edges(&self, angle: f64) -> Vec<usize> {
+        let min_cos = angle.to_radians().cos();
+        self.edges_where(|faces| match faces {
+            [a, b] => dot(&self.face_normals[*a], &self.face_normals[*b]) < min_cos,
+            _ => false,
+        })
+    }
+
+    fn edges_where(&self, condition: impl Fn(&[usize]) -> bool) -> Vec<usize> {
+        (0..self.edges.len())
+            .filter(|&edge| condition(&self.edge_faces[edge]))
+            .collect()
+    }
+
+    /// Returns the number of connected regions of the patch, where faces are connected through
+    /// their edges, and the region of each face. The regions are numbered in the order of their
+    /// first face.
+    pub fn regions(&self) -> (usize, Vec<usize>) {
+        let mut regions = vec![usize::MAX; self.n_faces()];
+        let mut n_regions = 0;
+        for start in 0..self.n_faces() {
+            if regions[start] != usize::MAX {
+                continue;
+            }
+            regions[start] = n_regions;
+            let mut front = vec![start];
+            while let Some(face) = front.pop() {
+                for &edge in &self.face_edges[face] {
+                    for &neighbour in &self.edge_faces[edge] {
+                        if regions[neighbour] == usize::MAX {
+                            regions[neighbour] = n_regions;
+                            front.push(neighbour);
+                        }
+                    }
+                }
+            }
+            n_regions += 1;
+        }
+        (n_regions, regions)
+    }
+
+    /// Classifies the surface formed by the patch, like checkMesh.
+    pub fn surface_topology(&self) -> SurfaceTopology {
+        if !self.non_manifold_edges().is_empty() {
+            return SurfaceTopology::MultiplyConnectedEdge;
+        }
+        if self.has_multiply_connected_point() {
+            return SurfaceTopology::MultiplyConnectedPoint;
+        }
+        if self.boundary_edges().is_empty() {
+            SurfaceTopology::ClosedSinglyConnected
+        } else {
+            SurfaceTopology::OpenSinglyConnected
+        }
+    }
+
+    /// Checks whether the faces around some point are not connected through the edges of the point.
+    fn has_multiply_connected_point(&self) -> bool {
+        let mut point_edges: HashMap<usize, Vec<usize>> = HashMap::new();
+        for (edge, points) in self.edges.iter().enumerate() {
+            for &point in points {
+                point_edges.entry(point).or_default().push(edge);
+            }
+        }
+        point_edges.values().any(|edges| {
+            let faces: Vec<usize> = {
+                let mut faces: Vec<usize> = edges
+                    .iter()
+                    .flat_map(|&edge| self.edge_faces[edge].iter().copied())
+                    .collect();
+                faces.sort_unstable();
+                faces.dedup();
+                faces
+            };
+            // walk from the first face through the edges of the point
+            let mut reached = vec![faces[0]];
+            let mut front = vec![faces[0]];
+            while let Some(face) = front.pop() {
+                for &edge in edges {
+                    let edge_faces = &self.edge_faces[edge];
+                    if edge_faces.contains(&face) {
+                        for &neighbour in edge_faces {
+                            if !reached.contains(&neighbour) {
+                                reached.push(neighbour);
+                                front.push(neighbour);
+                            }
+                        }
+                    }
+                }
+            }
+            reached.len() < faces.len()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Two unit squares in the plane z = 0, and a third one folded up along x = 1.
+    fn folded_squares() -> (FaceData, Vec<Point>) {
+        let points = vec![
+            [0.0, 0.0, 0.0],
+            [1.0, 0.0, 0.0],
+            [1.0, 1.0, 0.0],
+            [0.0, 1.0, 0.0],
+            [0.0, 2.0, 0.0],
+            [1.0, 2.0, 0.0],
+            [2.0, 0.0, 1.0],
+            [2.0, 1.0, 1.0],
+        ];
+        let faces = FaceData::from(vec![vec![0, 1, 2, 3], vec![3, 2, 5, 4], vec![1, 6, 7, 2]]);
+        (faces, points)
+    }
+
+    #[test]
+    fn test_patch_topology() {
+        let (faces, points) = folded_squares();
+        let topology = PatchTopology::new(&faces, &points, 0..3);
+        assert_eq!(topology.edges.len(), 10);
+        assert_eq!(topology.boundary_edges().len(), 8);
+        assert!(topology.non_manifold_edges().is_empty());
+        let features = topology.feature_edges(30.0);
+        assert_eq!(features.len(), 1);
+        assert_eq!(topology.edges[features[0]], [1, 2]);
+        assert!(topology.feature_edges(60.0).is_empty());
+        assert_eq!(topology.regions(), (1, vec![0, 0, 0]));
+        assert_eq!(
+            topology.surface_topology(),
+            SurfaceTopology::OpenSinglyConnected
+        );
+
+        // without the middle square, the others only share the point 2
+        let topology = PatchTopology::new(
+            &FaceData::from(vec![faces[0].to_vec(), vec![2, 7, 5]]),
+            &points,
+            0..2,
+        );
+        assert_eq!(topology.regions(), (2, vec![0, 1]));
+        assert_eq!(
+            topology.surface_topology(),
+            SurfaceTopology::MultiplyConnectedPoint
+        );
+    }
+
+    #[test]
+    fn test_mesh_patches() {
+        let path = std::path::Path::new("tests/test_cases/original/cylinder");
+        let mesh = PolyMesh::parse(&path.join("constant/polyMesh")).unwrap();
+        // the front and the back planes of the 2D mesh are separate regions
+        let empty = mesh.patch_topology("defaultFaces").unwrap();
+        assert_eq!(empty.regions().0, 2);
+        assert!(empty.feature_edges(10.0).is_empty());
+        let cylinder = mesh.patch_topology("cylinder").unwrap();
+        assert_eq!(cylinder.regions().0, 1);
+        assert_eq!(
+            cylinder.surface_topology(),
+            SurfaceTopology::OpenSinglyConnected
+        );
+        assert!(mesh.patch_topology("wall").is_err());
+    }
+}
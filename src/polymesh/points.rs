@@ -1,3 +1,4 @@
+use crate::base::parallel::parse_list;
 use crate::base::parser_base::*;
 use crate::base::writer_base::*;
 use crate::base::FileElement;
@@ -8,7 +9,7 @@ use nom::{
 };
 
 /// A point is a 3D coordinate.
-type Point = [f64; 3];
+pub type Point = [f64; 3];
 
 /// The PointData structure holds the data of a polyMesh/points file.
 #[derive(Debug, PartialEq, Clone)]
@@ -48,7 +49,7 @@ impl FileElement for PointData {
         let n = n.parse().expect("Failed to parse number of points.");
         let (input, _) = next(tag("("))(input)?;
         // Parse exactly this many points.
-        let (input, points) = count(next(point), n)(input)?;
+        let (input, points) = parse_list(input, n, point)?;
         // If the number of points was accurate this schould work:
         let (input, _) = next(tag(")"))(input)?;
         // Return the new data structure
@@ -44,9 +44,9 @@ impl FileElement for PointZone {
         // opening curly brace
         let (input, _) = next(char('{'))(input)?;
         // "    type pointZone;"
-        let (input, _) = known_key_value_semicolon("type", "pointZone")(input)?;
+        let (input, _) = next(known_key_value_semicolon("type", "pointZone"))(input)?;
         // "pointLabels      List<label> "
-        let (input, _) = known_key_value("pointLabels", "List<label>")(input)?;
+        let (input, _) = next(known_key_value("pointLabels", "List<label>"))(input)?;
         // list of points
         let (input, points) = single_i_data(input)?;
         // closing semicolon
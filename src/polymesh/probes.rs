@@ -0,0 +1,290 @@
+use super::search::MeshSearch;
+use super::{Point, ResultData};
+use crate::base::vector::*;
+use crate::base::{FileContent, FoamField};
+
+/// The value of probes outside of the mesh, like in OpenFOAM.
+pub const UNSET_PROBE_VALUE: f64 = -1e300;
+
+/// How the fields are sampled at the probe locations, like the "interpolationScheme" of OpenFOAM's
+/// probes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProbeInterpolation {
+    /// The value of the cell that contains the probe.
+    Cell,
+    /// The linear interpolation of the cell value and of the point values in the tetrahedra of the
+    /// cell, with the point values interpolated like in `PolyMesh::cell_to_point`.
+    CellPoint,
+}
+
+impl MeshSearch<'_> {
+    /// Samples a volume field at the given locations, and returns one value per location.
+    /// Locations outside of the mesh take the value `UNSET_PROBE_VALUE`, in all components.
+    pub fn probe(
+        &self,
+        locations: &[Point],
+        field: &FileContent<ResultData>,
+        interpolation: ProbeInterpolation,
+    ) -> std::io::Result<FoamField> {
+        let values = &field.data.result;
+        if let Some(n) = values.n_values().filter(|&n| n != self.mesh().n_cells()) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!(
+                    "The field has {} values, but there are {} cells.",
+                    n,
+                    self.mesh().n_cells()
+                ),
+            ));
+        }
+        let point_values = match interpolation {
+            ProbeInterpolation::Cell => None,
+            ProbeInterpolation::CellPoint => Some(self.mesh().cell_to_point(field)?.data.result),
+        };
+        let n_components = values.n_components();
+        let probed = locations
+            .iter()
+            .map(|location| match (self.find_cell(location), &point_values) {
+                (None, _) => vec![UNSET_PROBE_VALUE; n_components],
+                (Some(cell), None) => values.value(cell).to_vec(),
+                (Some(cell), Some(point_values)) => {
+                    self.interpolate(cell, location, values, point_values)
+                }
+            })
+            .collect();
+        Ok(FoamField::from_values(probed))
+    }
+
+    /// Interpolates linearly in the tetrahedron of the cell that contains the location. The
+    /// tetrahedra are formed by the cell centre and the triangles of a fan decomposition of the
+    /// faces. If no tetrahedron contains the location, e.g. in non-convex cells, the one that
+    /// comes closest is used, with the weights clipped to the tetrahedron.
+    fn interpolate(
+        &self,
+        cell: usize,
+        location: &Point,
+        values: &FoamField,
+        point_values: &FoamField,
+    ) -> Vec<f64> {
+        let points = &self.mesh().points.data;
+        let centre = self.cell_centre(cell);
+        let mut best: Option<([f64; 4], [usize; 3])> = None;
+        for &face in self.cell_faces(cell) {
+            let vertices = &self.mesh().faces.data[face];
+            for i in 1..vertices.len().saturating_sub(1) {
+                let corners = [vertices[0], vertices[i], vertices[i + 1]];
+                let [a, b, c] = corners.map(|point| &points[point]);
+                let Some(weights) = barycentric(location, centre, a, b, c) else {
+                    continue;
+                };
+                let smallest = weights.iter().copied().fold(f64::INFINITY, f64::min);
+                let best_smallest = best.map_or(f64::NEG_INFINITY, |(weights, _)| {
+                    weights.iter().copied().fold(f64::INFINITY, f64::min)
+                });
+                if smallest > best_smallest {
+                    best = Some((weights, corners));
+                }
+            }
+        }
+        let Some((weights, corners)) = best else {
+            return values.value(cell).to_vec();
+        };
+        let weights = weights.map(|weight| weight.max(0.0));
+        let total: f64 = weights.iter().sum();
+        let mut value: Vec<f64> = values
+            .value(cell)
+            .iter()
+            .map(|component| component * weights[0] / total)
+            .collect();
+        for (weight, &point) in weights[1..].iter().zip(&corners) {
+            for (sum, component) in value.iter_mut().zip(point_values.value(point)) {
+                *sum += component * weight / total;
+            }
+        }
+        value
+    }
+}
+
+/// Returns the barycentric coordinates of `p` in the tetrahedron (a, b, c, d), or None if the
+/// tetrahedron is degenerate.
+fn barycentric(p: &Point, a: &Point, b: &Point, c: &Point, d: &Point) -> Option<[f64; 4]> {
+    let (ab, ac, ad) = (sub(b, a), sub(c, a), sub(d, a));
+    let volume = dot(&ab, &cross(&ac, &ad));
+    if volume.abs() <= f64::MIN_POSITIVE {
+        return None;
+    }
+    let ap = sub(p, a);
+    let wb = dot(&ap, &cross(&ac, &ad)) / volume;
+    let wc = dot(&ab, &cross(&ap, &ad)) / volume;
+    let wd = dot(&ab, &cross(&ac, &ap)) / volume;
+    Some([1.0 - wb - wc - wd, wb, wc, wd])
+}
+
+/// The time history of fields at probe locations, written like the output of OpenFOAM's probes
+/// function object, e.g. "postProcessing/probes/0/U".
+#[derive(Debug, Clone, PartialEq)]
+pub struct Probes {
+    pub locations: Vec<Point>,
+    /// The time and the probed values, one per location.
+    pub samples: Vec<(f64, FoamField)>,
+}
+
+impl Probes {
+    pub fn new(locations: Vec<Point>) -> Self {
+        Probes {
+            locations,
+            samples: Vec::new(),
+        }
+    }
+
+    /// Probes a field and adds the values at the given time.
+    pub fn sample(
+        &mut self,
+        search: &MeshSearch,
+        time: f64,
+        field: &FileContent<ResultData>,
+        interpolation: ProbeInterpolation,
+    ) -> std::io::Result<()> {
+        let values = search.probe(&self.locations, field, interpolation)?;
+        self.samples.push((time, values));
+        Ok(())
+    }
+}
+
+const COLUMN_WIDTH: usize = 14;
+
+impl std::fmt::Display for Probes {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        for (i, location) in self.locations.iter().enumerate() {
+            writeln!(
+                f,
+                "# Probe {} ({} {} {})",
+                i, location[0], location[1], location[2]
+            )?;
+        }
+        write!(f, "#{:>w$}", "Probe", w = COLUMN_WIDTH - 1)?;
+        for i in 0..self.locations.len() {
+            write!(f, " {:>w$}", i, w = COLUMN_WIDTH)?;
+        }
+        writeln!(f)?;
+        writeln!(f, "#{:>w$}", "Time", w = COLUMN_WIDTH - 1)?;
+        for (time, values) in &self.samples {
+            write!(f, "{:>w$}", time, w = COLUMN_WIDTH)?;
+            for i in 0..self.locations.len() {
+                let value = values.value(i);
+                let value = match value {
+                    [scalar] => format_number(*scalar),
+                    _ => format!(
+                        "({})",
+                        value
+                            .iter()
+                            .map(|&component| format_number(component))
+                            .collect::<Vec<_>>()
+                            .join(" ")
+                    ),
+                };
+                write!(f, " {:>w$}", value, w = COLUMN_WIDTH)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+/// Formats very large and very small numbers in scientific notation, e.g. the unset values.
+pub(crate) fn format_number(value: f64) -> String {
+    if value != 0.0 && !(1e-4..1e15).contains(&value.abs()) {
+        format!("{:e}", value)
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::polymesh::PolyMesh;
+
+    #[test]
+    fn test_probes() {
+        let path = std::path::Path::new("tests/test_cases/original/cylinder");
+        let mesh = PolyMesh::parse(&path.join("constant/polyMesh")).unwrap();
+        let search = mesh.search();
+        let mut u = FileContent::<ResultData>::parse_file(&path.join("0/U")).unwrap();
+        let linear = |x: &Point| [1.0 + x[0], 2.0 * x[1], 0.0];
+        let centres = mesh.cell_centres();
+        u.data.result = FoamField::Vector(centres.iter().map(|c| linear(c).to_vec()).collect());
+        u.data.boundary_field = None;
+        let cell = mesh.n_cells() / 2;
+        let locations = vec![centres[cell], [1e3, 0.0, 0.0]];
+
+        let cell_values = search
+            .probe(&locations, &u, ProbeInterpolation::Cell)
+            .unwrap();
+        assert_eq!(cell_values.value(0), u.data.result.value(cell));
+        assert_eq!(cell_values.value(1), &[UNSET_PROBE_VALUE; 3]);
+
+        // off-centre probes halfway between the centre and a point of cells away from the patches
+        let mut boundary_point = vec![false; mesh.n_points()];
+        for (name, range) in mesh.boundary.data.face_ranges() {
+            if mesh.boundary.data.patch_type(&name) != Some("empty") {
+                for face in range {
+                    for &point in &mesh.faces.data[face] {
+                        boundary_point[point] = true;
+                    }
+                }
+            }
+        }
+        let cell_points = mesh.cell_points();
+        let (cells, off_centre): (Vec<usize>, Vec<Point>) = (0..mesh.n_cells())
+            .filter(|&cell| cell_points[cell].iter().all(|&p| !boundary_point[p]))
+            .map(|cell| {
+                let point = &mesh.points.data[cell_points[cell][0]];
+                (cell, scale(&add(&centres[cell], point), 0.5))
+            })
+            .unzip();
+        assert!(!cells.is_empty());
+        let cell_values = search
+            .probe(&off_centre, &u, ProbeInterpolation::Cell)
+            .unwrap();
+        let interpolated = search
+            .probe(&off_centre, &u, ProbeInterpolation::CellPoint)
+            .unwrap();
+        let point_values = mesh.cell_to_point(&u).unwrap().data.result;
+        let error = |values: &FoamField, i: usize| {
+            mag(&sub(
+                &values.value(i).try_into().unwrap(),
+                &linear(&off_centre[i]),
+            ))
+        };
+        let (mut interpolation_error, mut cell_error) = (0.0, 0.0);
+        for (i, &cell) in cells.iter().enumerate() {
+            // the probe lies on the edge between the cell centre and the point of a tetrahedron
+            let point = cell_points[cell][0];
+            let expected = scale(
+                &add(
+                    &u.data.result.value(cell).try_into().unwrap(),
+                    &point_values.value(point).try_into().unwrap(),
+                ),
+                0.5,
+            );
+            let value = interpolated.value(i).try_into().unwrap();
+            assert!(mag(&sub(&value, &expected)) < 1e-9);
+            // the point values are not exact, but closer to the linear value than the cell value
+            assert!(error(&interpolated, i) <= error(&cell_values, i) + 1e-12);
+            interpolation_error += error(&interpolated, i);
+            cell_error += error(&cell_values, i);
+        }
+        assert!(interpolation_error < 0.1 * cell_error);
+
+        let mut probes = Probes::new(locations);
+        probes
+            .sample(&search, 0.0, &u, ProbeInterpolation::Cell)
+            .unwrap();
+        let output = probes.to_string();
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines.len(), 5);
+        assert!(lines[0].starts_with("# Probe 0 ("));
+        assert!(lines[4].ends_with("(-1e300 -1e300 -1e300)"));
+    }
+}
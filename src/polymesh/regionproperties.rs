@@ -0,0 +1,101 @@
+use crate::base::parser_base::*;
+use crate::base::FileElement;
+use crate::base::FileParser;
+use indexmap::IndexMap;
+use nom::{
+    bytes::complete::tag,
+    character::complete::char,
+    multi::many0,
+    sequence::{delimited, pair, preceded, terminated},
+    IResult,
+};
+
+/// The RegionPropertiesData structure holds the data of a constant/regionProperties file,
+/// which lists the regions of a multi-region case grouped by type:
+/// ```text
+/// regions
+/// (
+///     fluid       (channel)
+///     solid       (metal)
+/// );
+/// ```
+#[derive(Debug, PartialEq, Clone)]
+pub struct RegionPropertiesData(
+    // Keys: region types, e.g. "fluid" or "solid". Values: names of the regions.
+    pub IndexMap<String, Vec<String>>,
+);
+
+impl RegionPropertiesData {
+    /// Returns the names of all regions, in the order of the file.
+    pub fn region_names(&self) -> Vec<String> {
+        self.0.values().flatten().cloned().collect()
+    }
+}
+
+impl std::ops::Deref for RegionPropertiesData {
+    type Target = IndexMap<String, Vec<String>>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl std::ops::DerefMut for RegionPropertiesData {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl FileParser for RegionPropertiesData {
+    fn default_file_path() -> std::path::PathBuf {
+        std::path::PathBuf::from("constant/regionProperties")
+    }
+}
+
+fn region_names(input: &str) -> IResult<&str, Vec<String>> {
+    delimited(char('('), many0(next(string_val)), next(char(')')))(input)
+}
+
+fn region_group(input: &str) -> IResult<&str, (String, Vec<String>)> {
+    pair(next(string_val), next(region_names))(input)
+}
+
+impl FileElement for RegionPropertiesData {
+    fn parse(input: &str) -> IResult<&str, RegionPropertiesData> {
+        let (input, groups) = terminated(
+            preceded(next(tag("regions")), block_parentheses(many0(region_group))),
+            semicolon,
+        )(input)?;
+        Ok((input, RegionPropertiesData(groups.into_iter().collect())))
+    }
+}
+
+impl std::fmt::Display for RegionPropertiesData {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        writeln!(f, "regions")?;
+        writeln!(f, "(")?;
+        for (region_type, names) in self.0.iter() {
+            writeln!(f, "    {: <11} ({})", region_type, names.join(" "))?;
+        }
+        writeln!(f, ");")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_region_properties() {
+        let input = "
+regions
+(
+    fluid       (bottomAir topAir)
+    solid       (heater)
+);";
+        let (_, actual) = RegionPropertiesData::parse(input).unwrap();
+        assert_eq!(actual.region_names(), vec!["bottomAir", "topAir", "heater"]);
+        let (_, reparsed) = RegionPropertiesData::parse(&actual.to_string()).unwrap();
+        assert_eq!(actual, reparsed);
+    }
+}
@@ -0,0 +1,358 @@
+use super::subset::{check_n_values, reversed_face};
+use super::{FaceData, FieldLocation, FileContent, PolyMesh, ResultData, TimeDir};
+use std::collections::VecDeque;
+
+/// The orderings that can be applied to the cells of a mesh by `PolyMesh::renumber`.
+#[derive(Debug, PartialEq, Clone)]
+pub enum CellOrdering {
+    /// Cuthill-McKee ordering, a breadth-first traversal starting from a cell of low connectivity.
+    CuthillMcKee,
+    /// Reverse Cuthill-McKee ordering, the default of OpenFOAM's renumberMesh.
+    ReverseCuthillMcKee,
+    /// A given ordering: for each new cell, the label of the old cell.
+    Manual(Vec<usize>),
+}
+
+/// The bandwidth and profile of the cell connectivity, as reported by OpenFOAM's renumberMesh.
+/// The bandwidth of a cell is the largest label difference to a lower-numbered neighbour.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Band {
+    /// The largest bandwidth of all cells.
+    pub bandwidth: usize,
+    /// The sum of the bandwidths of all cells.
+    pub profile: usize,
+}
+
+/// The maps produced by `PolyMesh::renumber`, which are needed to renumber fields consistently.
+#[derive(Debug, PartialEq, Clone)]
+pub struct MeshRenumbering {
+    /// For each new cell, the label of the old cell.
+    pub cell_map: Vec<usize>,
+    /// For each new face, the label of the old face. Boundary faces are not moved.
+    pub face_map: Vec<usize>,
+    /// For each new face, whether its orientation was reversed to keep the owner lower than the neighbour.
+    pub face_flip: Vec<bool>,
+    /// The band of the mesh before renumbering.
+    pub band_before: Band,
+    /// The band of the mesh after renumbering.
+    pub band_after: Band,
+    n_internal_faces: usize,
+}
+
+impl PolyMesh {
+    /// Returns the bandwidth and the profile of the cell connectivity.
+    pub fn band(&self) -> Band {
+        let mut cell_bandwidth = vec![0; self.n_cells()];
+        for (face, &neighbour) in self.neighbour.data.iter().enumerate() {
+            let owner = self.owner.data[face];
+            let (low, high) = (owner.min(neighbour), owner.max(neighbour));
+            cell_bandwidth[high] = cell_bandwidth[high].max(high - low);
+        }
+        Band {
+            bandwidth: cell_bandwidth.iter().copied().max().unwrap_or(0),
+            profile: cell_bandwidth.iter().sum(),
+        }
+    }
+
+    /// Computes a new order of the cells, as a list of old cell labels for each new cell.
+    pub fn cell_order(&self, ordering: &CellOrdering) -> Vec<usize> {
+        match ordering {
+            CellOrdering::CuthillMcKee => cuthill_mckee(&self.cell_cells()),
+            CellOrdering::ReverseCuthillMcKee => {
+                let mut order = cuthill_mckee(&self.cell_cells());
+                order.reverse();
+                order
+            }
+            CellOrdering::Manual(order) => order.clone(),
+        }
+    }
+
+    /// Renumbers the cells with the given ordering, the equivalent of OpenFOAM's renumberMesh.
+    /// The internal faces are then sorted into upper-triangular order: by owner, then by neighbour,
+    /// with the owner always being the lower cell label. Faces are flipped where required.
+    /// Boundary faces and points keep their labels. Cell and face zones and sets are renumbered,
+    /// and the flipmap of face zones is inverted on flipped faces to keep the zone orientation.
+    /// Fields need to be renumbered with the returned maps, see `MeshRenumbering::renumber_field`.
+    /// An error is returned if a manual ordering is not a permutation of the cells.
+    pub fn renumber(&mut self, ordering: &CellOrdering) -> std::io::Result<MeshRenumbering> {
+        let band_before = self.band();
+        let n_cells = self.n_cells();
+        let cell_map = self.cell_order(ordering);
+        let not_a_permutation = || {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "The cell ordering is not a permutation of the cells.",
+            )
+        };
+        if cell_map.len() != n_cells {
+            return Err(not_a_permutation());
+        }
+        let mut new_cell = vec![usize::MAX; n_cells];
+        for (new, &old) in cell_map.iter().enumerate() {
+            if old >= n_cells || new_cell[old] != usize::MAX {
+                return Err(not_a_permutation());
+            }
+            new_cell[old] = new;
+        }
+
+        // sort the internal faces into upper-triangular order
+        let n_internal_faces = self.n_internal_faces();
+        let mut internal: Vec<(usize, usize, usize)> = (0..n_internal_faces)
+            .map(|face| {
+                let owner = new_cell[self.owner.data[face]];
+                let neighbour = new_cell[self.neighbour.data[face]];
+                (owner.min(neighbour), owner.max(neighbour), face)
+            })
+            .collect();
+        internal.sort_unstable();
+        let mut face_map: Vec<usize> = internal.iter().map(|&(_, _, face)| face).collect();
+        face_map.extend(n_internal_faces..self.n_faces());
+        let face_flip: Vec<bool> = face_map
+            .iter()
+            .enumerate()
+            .map(|(new, &old)| {
+                new < n_internal_faces && new_cell[self.owner.data[old]] != internal[new].0
+            })
+            .collect();
+        let mut new_face = vec![0; face_map.len()];
+        for (new, &old) in face_map.iter().enumerate() {
+            new_face[old] = new;
+        }
+
+        let mut faces: FaceData = face_map
+            .iter()
+            .zip(face_flip.iter())
+            .map(|(&face, &flip)| {
+                let vertices: Vec<usize> = self.faces.data[face].to_vec();
+                if flip {
+                    reversed_face(&vertices)
+                } else {
+                    vertices
+                }
+            })
+            .collect();
+        let mut owner: Vec<usize> = internal.iter().map(|&(owner, _, _)| owner).collect();
+        owner.extend(
+            self.owner.data[n_internal_faces..]
+                .iter()
+                .map(|&cell| new_cell[cell]),
+        );
+        let neighbour: Vec<usize> = internal
+            .iter()
+            .map(|&(_, neighbour, _)| neighbour)
+            .collect();
+        faces.set_compact(self.faces.data.is_compact());
+        self.faces.data = faces;
+        self.owner.data.0 = owner;
+        self.neighbour.data.0 = neighbour;
+
+        // zones and sets
+        let renumber_labels = |labels: &mut Vec<usize>, map: &[usize]| {
+            for label in labels.iter_mut() {
+                *label = map[*label];
+            }
+            labels.sort_unstable();
+        };
+        if let Some(cellzones) = &mut self.cellzones {
+            for zone in cellzones.data.zones.values_mut() {
+                renumber_labels(&mut zone.cells, &new_cell);
+            }
+        }
+        if let Some(facezones) = &mut self.facezones {
+            for zone in facezones.data.zones.values_mut() {
+                *zone = zone.renumbered(|face| Some(new_face[face]), |face| face_flip[face]);
+            }
+        }
+        if let Some(sets) = &mut self.sets {
+            for set in sets.values_mut() {
+                match set.meta.class() {
+                    Some("cellSet") => renumber_labels(&mut set.data.labels, &new_cell),
+                    Some("faceSet") => renumber_labels(&mut set.data.labels, &new_face),
+                    _ => {}
+                }
+            }
+        }
+
+        Ok(MeshRenumbering {
+            cell_map,
+            face_map,
+            face_flip,
+            band_before,
+            band_after: self.band(),
+            n_internal_faces,
+        })
+    }
+}
+
+impl MeshRenumbering {
+    /// Renumbers the internal field of a volume or surface field. The values of surface fields are
+    /// negated on flipped faces. Point fields and boundary values are not affected.
+    pub fn renumber_field(&self, field: &mut FileContent<ResultData>) -> std::io::Result<()> {
+        let result = &mut field.data.result;
+        match field.meta.class().and_then(FieldLocation::from_class) {
+            Some(FieldLocation::Cells) => {
+                check_n_values(result, self.cell_map.len(), "internalField")?;
+                *result = result.subset(&self.cell_map);
+            }
+            Some(FieldLocation::Faces) => {
+                let n_internal_faces = self.n_internal_faces;
+                check_n_values(result, n_internal_faces, "internalField")?;
+                *result = result
+                    .subset(&self.face_map[..n_internal_faces])
+                    .flip_sign(&self.face_flip[..n_internal_faces]);
+            }
+            Some(FieldLocation::Points) => {}
+            None => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    format!("Unknown field class in {:?}.", field.location),
+                ))
+            }
+        }
+        Ok(())
+    }
+
+    /// Renumbers all fields of a time directory.
+    pub fn renumber_time_dir(&self, time_dir: &mut TimeDir) -> std::io::Result<()> {
+        for field in time_dir.field_values.values_mut() {
+            self.renumber_field(field)?;
+        }
+        Ok(())
+    }
+}
+
+/// Computes the Cuthill-McKee ordering of a graph given by its adjacency lists.
+/// Each connected component is traversed breadth-first, starting from its cell of lowest degree,
+/// and the neighbours of each cell are visited by increasing degree.
+fn cuthill_mckee(adjacency: &[Vec<usize>]) -> Vec<usize> {
+    let n = adjacency.len();
+    let degree = |cell: usize| adjacency[cell].len();
+    let mut by_degree: Vec<usize> = (0..n).collect();
+    by_degree.sort_by_key(|&cell| degree(cell));
+
+    let mut visited = vec![false; n];
+    let mut order = Vec::with_capacity(n);
+    let mut queue = VecDeque::new();
+    for &start in by_degree.iter() {
+        if visited[start] {
+            continue;
+        }
+        visited[start] = true;
+        queue.push_back(start);
+        while let Some(cell) = queue.pop_front() {
+            order.push(cell);
+            let mut neighbours: Vec<usize> = adjacency[cell]
+                .iter()
+                .copied()
+                .filter(|&other| !visited[other])
+                .collect();
+            neighbours.sort_by_key(|&other| degree(other));
+            neighbours.dedup();
+            for other in neighbours {
+                if !visited[other] {
+                    visited[other] = true;
+                    queue.push_back(other);
+                }
+            }
+        }
+    }
+    order
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base::FileElement;
+    use crate::polymesh::{FaceZone, Point};
+
+    #[test]
+    fn test_renumber() {
+        let path = std::path::Path::new("tests/test_cases/original/two_zones_with_interfaces");
+        let original = PolyMesh::parse(&path.join("constant/polyMesh")).unwrap();
+        let mut mesh = original.clone();
+        let renumbering = mesh.renumber(&CellOrdering::ReverseCuthillMcKee).unwrap();
+        assert!(renumbering.band_after.bandwidth <= renumbering.band_before.bandwidth);
+        assert_eq!(renumbering.band_before, original.band());
+        assert_eq!(renumbering.band_after, mesh.band());
+
+        // upper-triangular order
+        for face in 0..mesh.n_internal_faces() {
+            assert!(mesh.owner.data[face] < mesh.neighbour.data[face]);
+            if face > 0 {
+                let previous = (mesh.owner.data[face - 1], mesh.neighbour.data[face - 1]);
+                assert!(previous < (mesh.owner.data[face], mesh.neighbour.data[face]));
+            }
+        }
+        // geometry is preserved
+        let old_volumes = original.cell_volumes();
+        let new_volumes = mesh.cell_volumes();
+        for (new, &old) in renumbering.cell_map.iter().enumerate() {
+            assert!((new_volumes[new] - old_volumes[old]).abs() < 1e-12);
+        }
+        let old_zone = &original.cellzones.as_ref().unwrap().data.zones["metal"];
+        let new_zone = &mesh.cellzones.as_ref().unwrap().data.zones["metal"];
+        let old_zone_volume: f64 = old_zone.iter().map(|&c| old_volumes[c]).sum();
+        let new_zone_volume: f64 = new_zone.iter().map(|&c| new_volumes[c]).sum();
+        assert!((old_zone_volume - new_zone_volume).abs() < 1e-9 * old_zone_volume);
+    }
+
+    #[test]
+    fn test_renumber_facezone_orientation() {
+        let path = std::path::Path::new("tests/test_cases/original/two_zones_with_interfaces");
+        let original = PolyMesh::parse(&path.join("constant/polyMesh")).unwrap();
+        let mut mesh = original.clone();
+        let renumbering = mesh.renumber(&CellOrdering::ReverseCuthillMcKee).unwrap();
+        let old_zone = &original.facezones.as_ref().unwrap().data.zones["interface"];
+        let new_zone = &mesh.facezones.as_ref().unwrap().data.zones["interface"];
+        assert!(new_zone.iter().any(|&face| renumbering.face_flip[face]));
+        assert!(new_zone
+            .flipmap
+            .iter()
+            .any(|&flip| flip != new_zone.flipmap[0]));
+
+        // the area vectors of the zone, oriented by the flipmap, are preserved
+        let oriented = |areas: &[Point], zone: &FaceZone, i: usize| {
+            let sign = if zone.flipmap[i] { -1.0 } else { 1.0 };
+            crate::base::vector::scale(&areas[zone[i]], sign)
+        };
+        let old_areas = original.face_area_vectors();
+        let new_areas = mesh.face_area_vectors();
+        for i in 0..new_zone.len() {
+            let old_face = renumbering.face_map[new_zone[i]];
+            let j = old_zone.iter().position(|&face| face == old_face).unwrap();
+            let difference = crate::base::vector::sub(
+                &oriented(&new_areas, new_zone, i),
+                &oriented(&old_areas, old_zone, j),
+            );
+            assert!(crate::base::vector::mag(&difference) < 1e-12);
+        }
+
+        // the nonuniform flipmap is written and read back
+        let (_, copy) = FaceZone::parse(&new_zone.to_string()).unwrap();
+        assert_eq!(&copy, new_zone);
+    }
+
+    #[test]
+    fn test_cuthill_mckee() {
+        // a chain 0 - 2 - 1 - 3
+        let adjacency = vec![vec![2], vec![2, 3], vec![0, 1], vec![1]];
+        assert_eq!(cuthill_mckee(&adjacency), vec![0, 2, 1, 3]);
+    }
+
+    #[test]
+    fn test_renumber_invalid_ordering() {
+        let path = std::path::Path::new("tests/test_cases/original/two_zones_with_interfaces");
+        let original = PolyMesh::parse(&path.join("constant/polyMesh")).unwrap();
+        let n_cells = original.n_cells();
+        let mut duplicate: Vec<usize> = (0..n_cells).collect();
+        duplicate[1] = 0;
+        let mut out_of_range: Vec<usize> = (0..n_cells).collect();
+        out_of_range[0] = n_cells;
+        for order in [vec![0], duplicate, out_of_range] {
+            let mut mesh = original.clone();
+            let error = mesh.renumber(&CellOrdering::Manual(order)).unwrap_err();
+            assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+            assert_eq!(mesh, original);
+        }
+    }
+}
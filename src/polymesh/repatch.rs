@@ -0,0 +1,348 @@
+use super::boundary::new_patch;
+use super::geometry::face_centre_and_area;
+use super::subset::{check_n_values, gather};
+use super::{FaceData, FieldLocation, FileContent, Point, PolyMesh, ResultData, TimeDir};
+use crate::base::vector::*;
+use crate::base::{FoamStructure, FoamValue};
+use std::ops::Range;
+
+/// The maps produced by `PolyMesh::repatch`, which are needed to update the boundary fields.
+#[derive(Debug, PartialEq, Clone)]
+pub struct MeshRepatching {
+    /// For each new face, the label of the old face. Internal faces are not moved.
+    pub face_map: Vec<usize>,
+    /// The patches and their face ranges before repatching.
+    original_patches: Vec<(String, Range<usize>)>,
+    /// The patches and their face ranges after repatching.
+    patches: Vec<(String, Range<usize>)>,
+    n_cells: usize,
+}
+
+impl PolyMesh {
+    /// Returns the boundary faces whose centre and unit normal fulfill the given predicate.
+    /// The result can be used to move faces to another patch with `repatch`.
+    pub fn boundary_faces_where<F: Fn(&Point, &Point) -> bool>(&self, predicate: F) -> Vec<usize> {
+        (self.n_internal_faces()..self.n_faces())
+            .filter(|&face| {
+                let (centre, area) =
+                    face_centre_and_area(&self.points.data, &self.faces.data[face]);
+                predicate(&centre, &scale(&area, 1.0 / mag(&area)))
+            })
+            .collect()
+    }
+
+    /// Moves boundary faces into the patch `patch`, the equivalent of OpenFOAM's createPatch.
+    /// The faces can be taken from a face `Set`, a `FaceZone` or from `boundary_faces_where`.
+    /// The patch is appended to the boundary with type "patch" if it does not exist yet.
+    /// The boundary faces are then reordered so that each patch is contiguous, keeping the
+    /// relative order of the faces. Patches that lose all their faces are kept.
+    /// Face zones and face sets are renumbered.
+    /// Fields need to be updated with the returned maps, see `MeshRepatching::repatch_field`.
+    pub fn repatch(&mut self, faces: &[usize], patch: &str) -> std::io::Result<MeshRepatching> {
+        let n_internal_faces = self.n_internal_faces();
+        let n_faces = self.n_faces();
+        if let Some(face) = faces
+            .iter()
+            .find(|&&face| face < n_internal_faces || face >= n_faces)
+        {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("Face {} is not a boundary face.", face),
+            ));
+        }
+        let original_patches = self.boundary.data.face_ranges();
+        let is_new_patch = !self.boundary.data.contains_key(patch);
+        let mut names: Vec<String> = original_patches.iter().map(|(n, _)| n.clone()).collect();
+        if is_new_patch {
+            names.push(patch.to_string());
+        }
+
+        // the new patch of each boundary face
+        let mut destination = vec![None; n_faces - n_internal_faces];
+        for (name, range) in original_patches.iter() {
+            let index = names.iter().position(|other| other == name);
+            for face in range.clone() {
+                destination[face - n_internal_faces] = index;
+            }
+        }
+        let target = names.iter().position(|name| name == patch);
+        for &face in faces {
+            destination[face - n_internal_faces] = target;
+        }
+        if let Some(face) = destination.iter().position(Option::is_none) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("Face {} is not part of any patch.", face + n_internal_faces),
+            ));
+        }
+        // the mesh is only changed once the faces are known to be valid
+        if is_new_patch {
+            self.boundary
+                .data
+                .insert(patch.to_string(), new_patch("patch"));
+        }
+
+        let mut face_map: Vec<usize> = (0..n_internal_faces).collect();
+        let mut patches = Vec::with_capacity(names.len());
+        for (index, name) in names.into_iter().enumerate() {
+            let start = face_map.len();
+            face_map.extend(
+                (n_internal_faces..n_faces)
+                    .filter(|&face| destination[face - n_internal_faces] == Some(index)),
+            );
+            self.boundary
+                .data
+                .set_face_range(&name, start..face_map.len());
+            patches.push((name, start..face_map.len()));
+        }
+        let mut new_face = vec![0; n_faces];
+        for (new, &old) in face_map.iter().enumerate() {
+            new_face[old] = new;
+        }
+
+        let mut new_faces = face_map
+            .iter()
+            .map(|&face| self.faces.data[face].to_vec())
+            .collect::<FaceData>();
+        new_faces.set_compact(self.faces.data.is_compact());
+        self.faces.data = new_faces;
+        self.owner.data.0 = face_map.iter().map(|&face| self.owner.data[face]).collect();
+
+        let renumber_labels = |labels: &mut Vec<usize>| {
+            for label in labels.iter_mut() {
+                *label = new_face[*label];
+            }
+            labels.sort_unstable();
+        };
+        if let Some(facezones) = &mut self.facezones {
+            for zone in facezones.data.zones.values_mut() {
+                *zone = zone.renumbered(|face| Some(new_face[face]), |_| false);
+            }
+        }
+        if let Some(sets) = &mut self.sets {
+            for set in sets.values_mut() {
+                if set.meta.class() == Some("faceSet") {
+                    renumber_labels(&mut set.data.labels);
+                }
+            }
+        }
+
+        Ok(MeshRepatching {
+            face_map,
+            original_patches,
+            patches,
+            n_cells: self.n_cells(),
+        })
+    }
+}
+
+impl MeshRepatching {
+    /// Updates the boundary conditions of a field after `PolyMesh::repatch`.
+    /// The faces moved into a patch take its boundary condition. The nonuniform entries of the
+    /// conditions, e.g. "value", are reassembled from the entries of the patches the faces came
+    /// from, falling back to their "value" and, for volume fields, to the values of the adjacent
+    /// cells. New patches receive a "calculated" condition.
+    pub fn repatch_field(
+        &self,
+        mesh: &PolyMesh,
+        field: &mut FileContent<ResultData>,
+    ) -> std::io::Result<()> {
+        let location = field
+            .meta
+            .class()
+            .and_then(FieldLocation::from_class)
+            .ok_or_else(|| {
+                std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    format!("Unknown field class in {:?}.", field.location),
+                )
+            })?;
+        let Some(original_conditions) = field.data.boundary_patches() else {
+            return Ok(());
+        };
+        let internal = &field.data.result;
+        if location == FieldLocation::Cells {
+            check_n_values(internal, self.n_cells, "internalField")?;
+        }
+        let original_condition = |name: &str| match original_conditions.get(name) {
+            Some(FoamValue::Structure(condition)) => Some(condition),
+            _ => None,
+        };
+
+        // entries that are not patches of the mesh, e.g. patch name patterns, are kept
+        let mut conditions = original_conditions.clone();
+        for (name, range) in self.patches.iter() {
+            let original_range = self.original_range(name);
+            let mut condition = match (original_conditions.get(name), &original_range) {
+                (Some(FoamValue::Structure(condition)), _) => condition.clone(),
+                (Some(_), _) | (None, Some(_)) => continue,
+                (None, None) => {
+                    let mut condition = FoamStructure(Default::default());
+                    condition.insert(
+                        "type".to_string(),
+                        FoamValue::String("calculated".to_string()),
+                    );
+                    if location != FieldLocation::Points {
+                        condition
+                            .insert("value".to_string(), FoamValue::Field(internal.subset(&[])));
+                    }
+                    condition
+                }
+            };
+            let n_original = original_range.map_or(0, |range| range.len());
+            for (key, entry) in condition.iter_mut() {
+                let FoamValue::Field(values) = entry else {
+                    continue;
+                };
+                if values.n_values() != Some(n_original) {
+                    continue;
+                }
+                let mut picks = Vec::with_capacity(range.len());
+                for face in range.clone() {
+                    let old = self.face_map[face];
+                    let (donor, donor_range) = self
+                        .original_patches
+                        .iter()
+                        .find(|(_, range)| range.contains(&old))
+                        .map(|(donor, range)| (donor.as_str(), range.clone()))
+                        .unwrap_or_default();
+                    let offset = old - donor_range.start;
+                    let from_donor = |key: &str| match original_condition(donor)?.get(key) {
+                        Some(FoamValue::Field(values))
+                            if values.is_uniform()
+                                || values.n_values() == Some(donor_range.len()) =>
+                        {
+                            Some((values, offset, false))
+                        }
+                        _ => None,
+                    };
+                    let pick = if donor == name {
+                        Some((&*values, offset, false))
+                    } else {
+                        from_donor(key).or_else(|| from_donor("value"))
+                    };
+                    let pick = pick.or_else(|| {
+                        (location == FieldLocation::Cells)
+                            .then(|| (internal, mesh.owner.data[face], false))
+                    });
+                    picks.push(pick.ok_or_else(|| {
+                        std::io::Error::new(
+                            std::io::ErrorKind::InvalidData,
+                            format!("No {} for face {} of patch {}.", key, face, name),
+                        )
+                    })?);
+                }
+                let value = gather(&picks)?.unwrap_or_else(|| values.subset(&[]));
+                *entry = FoamValue::Field(value);
+            }
+            conditions.insert(name.clone(), FoamValue::Structure(condition));
+        }
+        if let Some(patches) = field.data.boundary_patches_mut() {
+            *patches = conditions;
+        }
+        Ok(())
+    }
+
+    /// Updates the boundary conditions of all fields of a time directory.
+    pub fn repatch_time_dir(&self, mesh: &PolyMesh, time_dir: &mut TimeDir) -> std::io::Result<()> {
+        for field in time_dir.field_values.values_mut() {
+            self.repatch_field(mesh, field)?;
+        }
+        Ok(())
+    }
+
+    fn original_range(&self, name: &str) -> Option<Range<usize>> {
+        self.original_patches
+            .iter()
+            .find(|(original, _)| original == name)
+            .map(|(_, range)| range.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base::FoamField;
+
+    #[test]
+    fn test_repatch() {
+        let path = std::path::Path::new("tests/test_cases/original/cylinder");
+        let original = PolyMesh::parse(&path.join("constant/polyMesh")).unwrap();
+        let mut mesh = original.clone();
+        // the faces of the cylinder with x > 0 become a new patch
+        let cylinder = original.boundary.data.face_range("cylinder").unwrap();
+        let faces: Vec<usize> = original
+            .boundary_faces_where(|centre, _| centre[0] > 0.0)
+            .into_iter()
+            .filter(|face| cylinder.contains(face))
+            .collect();
+        assert!(!faces.is_empty() && faces.len() < cylinder.len());
+        let repatching = mesh.repatch(&faces, "cylinderRight").unwrap();
+
+        let boundary = &mesh.boundary.data;
+        assert_eq!(boundary.patch_type("cylinderRight"), Some("patch"));
+        assert_eq!(
+            boundary.face_range("cylinderRight").unwrap().len(),
+            faces.len()
+        );
+        assert_eq!(
+            boundary.face_range("cylinder").unwrap().len(),
+            cylinder.len() - faces.len()
+        );
+        // the patches are contiguous and cover the boundary
+        let mut end = mesh.n_internal_faces();
+        for (_, range) in boundary.face_ranges() {
+            assert_eq!(range.start, end);
+            end = range.end;
+        }
+        assert_eq!(end, mesh.n_faces());
+        for (new, &old) in repatching.face_map.iter().enumerate() {
+            assert_eq!(mesh.faces.data[new], original.faces.data[old]);
+            assert_eq!(mesh.owner.data[new], original.owner.data[old]);
+        }
+        assert!(mesh.repatch(&[0], "internal").is_err());
+
+        let mut u = FileContent::<ResultData>::parse_file(&path.join("0/U")).unwrap();
+        let values: Vec<Vec<f64>> = (0..cylinder.len())
+            .map(|i| vec![i as f64, 0.0, 0.0])
+            .collect();
+        if let Some(FoamValue::Structure(condition)) =
+            u.data.boundary_patches_mut().unwrap().get_mut("cylinder")
+        {
+            condition.insert(
+                "value".to_string(),
+                FoamValue::Field(FoamField::Vector(values.clone())),
+            );
+        }
+        repatching.repatch_field(&mesh, &mut u).unwrap();
+        let right = u.data.patch_value("cylinderRight").unwrap();
+        let rest = u.data.patch_value("cylinder").unwrap();
+        assert_eq!(right.n_values(), Some(faces.len()));
+        assert_eq!(rest.n_values(), Some(cylinder.len() - faces.len()));
+        // the values move with the faces
+        let range = mesh.boundary.data.face_range("cylinderRight").unwrap();
+        for (i, face) in range.enumerate() {
+            let old = repatching.face_map[face] - cylinder.start;
+            assert_eq!(right.value(i), values[old].as_slice());
+        }
+    }
+
+    #[test]
+    fn test_repatch_invalid_faces() {
+        let path = std::path::Path::new("tests/test_cases/original/cylinder");
+        let mut original = PolyMesh::parse(&path.join("constant/polyMesh")).unwrap();
+        // the faces of the cylinder patch are not part of any patch
+        let cylinder = original.boundary.data.face_range("cylinder").unwrap();
+        original
+            .boundary
+            .data
+            .set_face_range("cylinder", cylinder.start..cylinder.start);
+        let mut mesh = original.clone();
+        let error = mesh.repatch(&[cylinder.start], "newPatch").unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+        assert_eq!(mesh, original);
+        let error = mesh.repatch(&[0], "internal").unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+        assert_eq!(mesh, original);
+    }
+}
@@ -1,15 +1,8 @@
+use super::Dimensions;
 use crate::base::{parser_base::*, FileElement};
-use crate::base::{FileParser, FoamField, FoamStructure};
-use nom::combinator::map;
-use nom::{
-    bytes::complete::tag,
-    character::complete::char,
-    combinator::opt,
-    multi::count,
-    sequence::{delimited, preceded},
-    IResult,
-};
-use std::ops::Deref;
+use crate::base::{FileContent, FileParser, FoamField, FoamStructure, FoamValue};
+use nom::{bytes::complete::tag, combinator::opt, sequence::preceded, IResult};
+use std::ops::{Add, Div, Mul, Neg, Sub};
 
 /// The ResultData structure holds the data of a time directory file, e.g., "0/phi".
 #[derive(Debug, PartialEq, Clone)]
@@ -19,6 +12,245 @@ pub struct ResultData {
     pub boundary_field: Option<FoamStructure>,
 }
 
+impl ResultData {
+    /// Creates the data of a field without boundary conditions.
+    pub fn new(dimensions: Dimensions, result: FoamField) -> Self {
+        ResultData {
+            dimensions,
+            result,
+            boundary_field: None,
+        }
+    }
+
+    /// Returns the "boundaryField" entries, i.e. the boundary conditions of each patch, if present.
+    pub fn boundary_patches(&self) -> Option<&FoamStructure> {
+        match self.boundary_field.as_ref()?.get("boundaryField")? {
+            FoamValue::Structure(patches) => Some(patches),
+            _ => None,
+        }
+    }
+
+    /// Mutable version of `boundary_patches`.
+    pub fn boundary_patches_mut(&mut self) -> Option<&mut FoamStructure> {
+        match self.boundary_field.as_mut()?.get_mut("boundaryField")? {
+            FoamValue::Structure(patches) => Some(patches),
+            _ => None,
+        }
+    }
+
+    /// Returns the "value" entry of the boundary condition of the given patch, if present.
+    pub fn patch_value(&self, patch: &str) -> Option<&FoamField> {
+        let FoamValue::Structure(condition) = self.boundary_patches()?.get(patch)? else {
+            return None;
+        };
+        match condition.get("value")? {
+            FoamValue::Field(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+// Field algebra with dimension checks, see the operations of `FoamField`.
+// Like in OpenFOAM, adding fields of different dimensions panics, and the dimensions of products
+// are combined. The results only hold the internal field: boundary conditions are not carried over.
+impl ResultData {
+    /// Returns the magnitude of the values.
+    pub fn mag(&self) -> ResultData {
+        ResultData::new(self.dimensions.clone(), self.result.mag())
+    }
+
+    /// Returns the squared magnitude of the values.
+    pub fn mag_sqr(&self) -> ResultData {
+        ResultData::new(self.dimensions.pow(2), self.result.mag_sqr())
+    }
+
+    /// Returns a single component of the values, e.g. 0 for the x component of vectors.
+    pub fn component(&self, component: usize) -> ResultData {
+        ResultData::new(self.dimensions.clone(), self.result.component(component))
+    }
+
+    /// Returns the inner product of the values, see `FoamField::dot`.
+    pub fn dot(&self, other: &ResultData) -> ResultData {
+        ResultData::new(
+            &self.dimensions * &other.dimensions,
+            self.result.dot(&other.result),
+        )
+    }
+
+    /// Returns the cross product of two vector fields.
+    pub fn cross(&self, other: &ResultData) -> ResultData {
+        ResultData::new(
+            &self.dimensions * &other.dimensions,
+            self.result.cross(&other.result),
+        )
+    }
+
+    /// Returns the trace of a tensor field.
+    pub fn trace(&self) -> ResultData {
+        ResultData::new(self.dimensions.clone(), self.result.trace())
+    }
+
+    /// Returns the symmetric part of a tensor field.
+    pub fn symm(&self) -> ResultData {
+        ResultData::new(self.dimensions.clone(), self.result.symm())
+    }
+
+    /// Returns the deviatoric part of a tensor field.
+    pub fn dev(&self) -> ResultData {
+        ResultData::new(self.dimensions.clone(), self.result.dev())
+    }
+
+    fn check_dimensions(&self, other: &ResultData, operation: &str) {
+        assert!(
+            self.dimensions == other.dimensions,
+            "Different dimensions for \"{}\": {} and {}.",
+            operation,
+            self.dimensions,
+            other.dimensions
+        );
+    }
+}
+
+impl Add for &ResultData {
+    type Output = ResultData;
+
+    fn add(self, other: &ResultData) -> ResultData {
+        self.check_dimensions(other, "+");
+        ResultData::new(self.dimensions.clone(), &self.result + &other.result)
+    }
+}
+
+impl Sub for &ResultData {
+    type Output = ResultData;
+
+    fn sub(self, other: &ResultData) -> ResultData {
+        self.check_dimensions(other, "-");
+        ResultData::new(self.dimensions.clone(), &self.result - &other.result)
+    }
+}
+
+impl Mul for &ResultData {
+    type Output = ResultData;
+
+    fn mul(self, other: &ResultData) -> ResultData {
+        ResultData::new(
+            &self.dimensions * &other.dimensions,
+            &self.result * &other.result,
+        )
+    }
+}
+
+impl Div for &ResultData {
+    type Output = ResultData;
+
+    fn div(self, other: &ResultData) -> ResultData {
+        ResultData::new(
+            &self.dimensions / &other.dimensions,
+            &self.result / &other.result,
+        )
+    }
+}
+
+/// Scales the values by a dimensionless factor.
+impl Mul<f64> for &ResultData {
+    type Output = ResultData;
+
+    fn mul(self, factor: f64) -> ResultData {
+        ResultData::new(self.dimensions.clone(), &self.result * factor)
+    }
+}
+
+/// Divides the values by a dimensionless factor.
+impl Div<f64> for &ResultData {
+    type Output = ResultData;
+
+    fn div(self, factor: f64) -> ResultData {
+        ResultData::new(self.dimensions.clone(), &self.result / factor)
+    }
+}
+
+impl Neg for &ResultData {
+    type Output = ResultData;
+
+    fn neg(self) -> ResultData {
+        ResultData::new(self.dimensions.clone(), -&self.result)
+    }
+}
+
+impl FileContent<ResultData> {
+    /// Returns the name of the field, found in the "object" entry of the header.
+    pub fn name(&self) -> Option<&str> {
+        match self.meta.get("object")? {
+            FoamValue::String(name) => Some(name.trim_matches('"')),
+            _ => None,
+        }
+    }
+
+    /// Returns a new field computed from this field, e.g. its gradient, in the same directory.
+    /// The class of the header is deduced from the type of the values, for the same location.
+    pub(crate) fn derived_field(&self, name: &str, data: ResultData) -> Self {
+        let location = FieldLocation::from_class(self.meta.class().unwrap_or_default())
+            .unwrap_or(FieldLocation::Cells);
+        let mut meta = self.meta.clone();
+        meta.insert(
+            "class".to_string(),
+            FoamValue::String(location.class(&data.result)),
+        );
+        meta.insert("object".to_string(), FoamValue::String(name.to_string()));
+        FileContent {
+            location: self
+                .location
+                .as_ref()
+                .map(|location| location.with_file_name(name)),
+            meta,
+            data,
+        }
+    }
+}
+
+/// The mesh entities on which the values of a field are stored.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum FieldLocation {
+    /// Volume fields, e.g. "volScalarField", with one value per cell.
+    Cells,
+    /// Surface fields, e.g. "surfaceScalarField", with one value per internal face.
+    Faces,
+    /// Point fields, e.g. "pointVectorField", with one value per point.
+    Points,
+}
+
+impl FieldLocation {
+    /// Deduces the location of the field values from the class found in the file header.
+    pub fn from_class(class: &str) -> Option<Self> {
+        if class.starts_with("vol") {
+            Some(FieldLocation::Cells)
+        } else if class.starts_with("surface") {
+            Some(FieldLocation::Faces)
+        } else if class.starts_with("point") {
+            Some(FieldLocation::Points)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the class of a field with the given values at this location,
+    /// e.g. "volVectorField" for vectors in cells.
+    pub fn class(&self, field: &FoamField) -> String {
+        let prefix = match self {
+            FieldLocation::Cells => "vol",
+            FieldLocation::Faces => "surface",
+            FieldLocation::Points => "point",
+        };
+        let value_type = field.value_type();
+        format!(
+            "{}{}{}Field",
+            prefix,
+            value_type[..1].to_uppercase(),
+            &value_type[1..]
+        )
+    }
+}
+
 impl FileParser for ResultData {
     fn default_file_path() -> std::path::PathBuf {
         std::path::PathBuf::from("unspecifiedTimeDirectory/unspecifiedVariableName")
@@ -49,7 +281,7 @@ impl FileElement for ResultData {
 
 impl std::fmt::Display for ResultData {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        writeln!(f, "{}\n", &self.dimensions)?;
+        writeln!(f, "dimensions      {};\n", &self.dimensions)?;
         write!(f, "internalField   ")?;
         writeln!(f, "{}", &self.result)?;
         if let Some(boundaries) = &self.boundary_field {
@@ -59,46 +291,6 @@ impl std::fmt::Display for ResultData {
     }
 }
 
-#[derive(Debug, PartialEq, Clone)]
-pub struct Dimensions([i32; 7]);
-
-impl Deref for Dimensions {
-    type Target = [i32; 7];
-
-    fn deref(&self) -> &Self::Target {
-        &self.0
-    }
-}
-
-impl FileElement for Dimensions {
-    fn parse(input: &str) -> IResult<&str, Self> {
-        preceded(next(tag("dimensions")), next(Self::parse_data))(input)
-    }
-}
-
-impl std::fmt::Display for Dimensions {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "dimensions      [{} {} {} {} {} {} {}];",
-            self.0[0], self.0[1], self.0[2], self.0[3], self.0[4], self.0[5], self.0[6]
-        )
-    }
-}
-
-impl Dimensions {
-    pub fn new(dimensions: [i32; 7]) -> Self {
-        Self(dimensions)
-    }
-
-    fn parse_data(input: &str) -> IResult<&str, Self> {
-        map(
-            delimited(char('['), count(lws(i32_val), 7), tag("];")),
-            |x| Dimensions(Vec::try_into(x).unwrap()),
-        )(input)
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -119,7 +311,7 @@ internalField   nonuniform List<scalar>
 ;
 ";
         let expected_value = ResultData {
-            dimensions: Dimensions([0, 2, -2, 0, 0, 0, 0]),
+            dimensions: Dimensions::new([0, 2, -2, 0, 0, 0, 0]),
             result: FoamField::Scalar(vec![685.183, 685.183, 685.184, 685.184]),
             boundary_field: None,
         };
@@ -142,7 +334,7 @@ internalField   nonuniform List<vector>
 )
 ;";
         let expected_value = ResultData {
-            dimensions: Dimensions([0, 1, -1, 0, 0, 0, 0]),
+            dimensions: Dimensions::new([0, 1, -1, 0, 0, 0, 0]),
             result: FoamField::Vector(vec![
                 vec![-8.52809e-05, 0.00019428, 0.00777701],
                 vec![-8.36566e-05, 0.00019361, 0.00802691],
@@ -154,4 +346,23 @@ internalField   nonuniform List<vector>
         let (_, actual_value) = ResultData::parse(input).unwrap();
         assert_eq!(expected_value, actual_value);
     }
+
+    #[test]
+    fn test_dimensioned_algebra() {
+        let u = ResultData::new(
+            Dimensions::new([0, 1, -1, 0, 0, 0, 0]),
+            FoamField::Vector(vec![vec![3.0, 4.0, 0.0]]),
+        );
+        let p = ResultData::new(
+            Dimensions::new([0, 2, -2, 0, 0, 0, 0]),
+            FoamField::Scalar(vec![10.0]),
+        );
+        let p_total = &p + &(&u.mag_sqr() * 0.5);
+        assert_eq!(p_total.dimensions, p.dimensions);
+        assert_eq!(p_total.result, FoamField::Scalar(vec![22.5]));
+        let ratio = &p / &p_total;
+        assert!(ratio.dimensions.is_dimensionless());
+        assert_eq!(u.dot(&u).dimensions, p.dimensions);
+        assert!(std::panic::catch_unwind(|| &p + &u.mag()).is_err());
+    }
 }
@@ -0,0 +1,228 @@
+use super::probes::{format_number, ProbeInterpolation};
+use super::search::MeshSearch;
+use super::{Point, ResultData};
+use crate::base::vector::*;
+use crate::base::{FileContent, FoamField};
+use indexmap::IndexMap;
+
+/// How the points of a line are chosen, like the "type" of OpenFOAM's sampled sets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineSampling {
+    /// The given number of points, uniformly distributed from the start to the end ("uniform").
+    Uniform(usize),
+    /// The intersections of the line with the faces of the mesh ("face").
+    Face,
+}
+
+/// The coordinate written for each point of a sampled set, like the "axis" of OpenFOAM's sets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SetAxis {
+    #[default]
+    Xyz,
+    X,
+    Y,
+    Z,
+    /// The distance from the start of the line.
+    Distance,
+}
+
+/// Field values sampled along a line, like the "sets" function object of OpenFOAM.
+/// The points outside of the mesh are left out.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SampledSet {
+    pub name: String,
+    pub points: Vec<Point>,
+    /// The distance of each point from the start of the line.
+    pub distances: Vec<f64>,
+    pub axis: SetAxis,
+    /// The values of the sampled fields, by field name.
+    pub fields: IndexMap<String, FoamField>,
+}
+
+impl MeshSearch<'_> {
+    /// Returns the sampling points of the line from `start` to `end` that are inside of the mesh.
+    pub fn sample_line(
+        &self,
+        name: &str,
+        start: &Point,
+        end: &Point,
+        sampling: LineSampling,
+    ) -> SampledSet {
+        let direction = sub(end, start);
+        let mut parameters = match sampling {
+            LineSampling::Uniform(1) => vec![0.0],
+            LineSampling::Uniform(n) => (0..n).map(|i| i as f64 / (n - 1) as f64).collect(),
+            LineSampling::Face => self.face_intersections(start, end),
+        };
+        parameters.retain(|&t| self.find_cell(&add(start, &scale(&direction, t))).is_some());
+        let points = parameters
+            .iter()
+            .map(|&t| add(start, &scale(&direction, t)))
+            .collect();
+        SampledSet {
+            name: name.to_string(),
+            points,
+            distances: parameters.iter().map(|t| t * mag(&direction)).collect(),
+            axis: SetAxis::default(),
+            fields: IndexMap::new(),
+        }
+    }
+
+    /// Returns the sorted parameters along the line of its intersections with the faces.
+    fn face_intersections(&self, start: &Point, end: &Point) -> Vec<f64> {
+        let points = &self.mesh().points.data;
+        let face_centres = self.mesh().face_centres();
+        let mut parameters: Vec<f64> = Vec::new();
+        for (face, vertices) in self.mesh().faces.data.iter().enumerate() {
+            let centre = &face_centres[face];
+            let intersection = (0..vertices.len()).find_map(|i| {
+                let this = &points[vertices[i]];
+                let next = &points[vertices[(i + 1) % vertices.len()]];
+                segment_triangle_intersection(start, end, centre, this, next)
+            });
+            parameters.extend(intersection);
+        }
+        parameters.sort_by(f64::total_cmp);
+        // faces that share the intersection, e.g. at an edge, give a single point
+        parameters.dedup_by(|a, b| (*a - *b).abs() < 1e-10);
+        parameters
+    }
+}
+
+/// Returns the parameter t in [0, 1] at which the segment from `start` to `end` intersects the
+/// triangle (a, b, c), if it does.
+fn segment_triangle_intersection(
+    start: &Point,
+    end: &Point,
+    a: &Point,
+    b: &Point,
+    c: &Point,
+) -> Option<f64> {
+    const TOLERANCE: f64 = 1e-10;
+    let direction = sub(end, start);
+    let (ab, ac) = (sub(b, a), sub(c, a));
+    let p = cross(&direction, &ac);
+    let determinant = dot(&ab, &p);
+    if determinant.abs() <= f64::MIN_POSITIVE {
+        return None;
+    }
+    let s = sub(start, a);
+    let u = dot(&s, &p) / determinant;
+    let q = cross(&s, &ab);
+    let v = dot(&direction, &q) / determinant;
+    let t = dot(&ac, &q) / determinant;
+    let inside = u >= -TOLERANCE && v >= -TOLERANCE && u + v <= 1.0 + TOLERANCE;
+    (inside && (-TOLERANCE..=1.0 + TOLERANCE).contains(&t)).then_some(t.clamp(0.0, 1.0))
+}
+
+impl SampledSet {
+    /// Samples a field at the points of the set, and stores the values under the name of the field.
+    pub fn sample(
+        &mut self,
+        search: &MeshSearch,
+        field: &FileContent<ResultData>,
+        interpolation: ProbeInterpolation,
+    ) -> std::io::Result<()> {
+        let name = field.name().ok_or_else(|| {
+            std::io::Error::new(std::io::ErrorKind::InvalidInput, "The field has no name.")
+        })?;
+        let values = search.probe(&self.points, field, interpolation)?;
+        self.fields.insert(name.to_string(), values);
+        Ok(())
+    }
+
+    /// Writes the set like OpenFOAM's "raw" set writer: one line per point, with the coordinates
+    /// given by the axis and the components of the fields, separated by spaces.
+    pub fn write_raw(&self, path: &std::path::Path) -> std::io::Result<()> {
+        std::fs::write(path, self.table(" ", "# "))
+    }
+
+    /// Writes the set like OpenFOAM's "csv" set writer, with a header line.
+    pub fn write_csv(&self, path: &std::path::Path) -> std::io::Result<()> {
+        std::fs::write(path, self.table(",", ""))
+    }
+
+    fn table(&self, separator: &str, header_prefix: &str) -> String {
+        let mut columns: Vec<String> = match self.axis {
+            SetAxis::Xyz => vec!["x".to_string(), "y".to_string(), "z".to_string()],
+            SetAxis::X => vec!["x".to_string()],
+            SetAxis::Y => vec!["y".to_string()],
+            SetAxis::Z => vec!["z".to_string()],
+            SetAxis::Distance => vec!["distance".to_string()],
+        };
+        for (name, values) in &self.fields {
+            match values.n_components() {
+                1 => columns.push(name.clone()),
+                n => columns.extend((0..n).map(|i| format!("{}_{}", name, i))),
+            }
+        }
+        let mut table = format!("{}{}\n", header_prefix, columns.join(separator));
+        for (i, point) in self.points.iter().enumerate() {
+            let mut row: Vec<f64> = match self.axis {
+                SetAxis::Xyz => point.to_vec(),
+                SetAxis::X => vec![point[0]],
+                SetAxis::Y => vec![point[1]],
+                SetAxis::Z => vec![point[2]],
+                SetAxis::Distance => vec![self.distances[i]],
+            };
+            for values in self.fields.values() {
+                row.extend(values.value(i));
+            }
+            let row: Vec<String> = row.into_iter().map(format_number).collect();
+            table.push_str(&row.join(separator));
+            table.push('\n');
+        }
+        table
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::polymesh::PolyMesh;
+
+    #[test]
+    fn test_sample_line() {
+        let path = std::path::Path::new("tests/test_cases/original/cylinder");
+        let mesh = PolyMesh::parse(&path.join("constant/polyMesh")).unwrap();
+        let search = mesh.search();
+        let centres = mesh.cell_centres();
+        let (first, last) = (centres[0], centres[mesh.n_cells() - 1]);
+        let start = add(&first, &sub(&first, &last));
+        let end = add(&last, &sub(&last, &first));
+
+        // the line extends beyond the mesh on both sides
+        let uniform = search.sample_line("line", &start, &end, LineSampling::Uniform(101));
+        assert!(!uniform.points.is_empty() && uniform.points.len() < 101);
+        assert!(uniform.distances.windows(2).all(|d| d[0] < d[1]));
+        let faces = search.sample_line("line", &first, &last, LineSampling::Face);
+        assert!(!faces.points.is_empty());
+
+        let mut u = FileContent::<ResultData>::parse_file(&path.join("0/U")).unwrap();
+        let mut set = uniform.clone();
+        set.sample(&search, &u, ProbeInterpolation::Cell).unwrap();
+        u.meta.insert(
+            "object".to_string(),
+            crate::FoamValue::String("x".to_string()),
+        );
+        u.data.result = FoamField::Scalar(centres.iter().map(|c| c[0]).collect());
+        u.data.boundary_field = None;
+        set.sample(&search, &u, ProbeInterpolation::Cell).unwrap();
+        set.axis = SetAxis::Distance;
+        let table = set.table(",", "");
+        let lines: Vec<&str> = table.lines().collect();
+        assert_eq!(lines[0], "distance,U_0,U_1,U_2,x");
+        assert_eq!(lines.len(), set.points.len() + 1);
+    }
+
+    #[test]
+    fn test_segment_triangle_intersection() {
+        let (a, b, c) = ([0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]);
+        let t = segment_triangle_intersection(&[0.2, 0.2, -1.0], &[0.2, 0.2, 3.0], &a, &b, &c);
+        assert_eq!(t, Some(0.25));
+        let t = segment_triangle_intersection(&[0.8, 0.8, -1.0], &[0.8, 0.8, 3.0], &a, &b, &c);
+        assert_eq!(t, None);
+        let t = segment_triangle_intersection(&[0.2, 0.2, 1.0], &[0.2, 0.2, 3.0], &a, &b, &c);
+        assert_eq!(t, None);
+    }
+}
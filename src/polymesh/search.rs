@@ -0,0 +1,429 @@
+use super::geometry::face_centre_and_area;
+use super::{Point, PolyMesh};
+use crate::base::vector::*;
+
+/// Spatial search in a mesh, like OpenFOAM's meshSearch: finds the cell that contains a point,
+/// the nearest cell and the nearest boundary face.
+/// The cells and the boundary faces are stored in octrees of their bounding boxes, which are built
+/// once, so that many points can be searched quickly.
+pub struct MeshSearch<'a> {
+    mesh: &'a PolyMesh,
+    cell_tree: Octree,
+    face_tree: Octree,
+    cell_faces: Vec<Vec<usize>>,
+    cell_centres: Vec<Point>,
+    face_centres: Vec<Point>,
+    face_areas: Vec<Point>,
+    cell_boxes: Vec<BoundBox>,
+}
+
+impl PolyMesh {
+    /// Builds the search structure of the mesh, see `MeshSearch`.
+    pub fn search(&self) -> MeshSearch<'_> {
+        MeshSearch::new(self)
+    }
+}
+
+impl<'a> MeshSearch<'a> {
+    pub fn new(mesh: &'a PolyMesh) -> Self {
+        let (face_centres, face_areas) = mesh
+            .faces
+            .data
+            .iter()
+            .map(|face| face_centre_and_area(&mesh.points.data, face))
+            .unzip();
+        let points = &mesh.points.data;
+        let cell_boxes: Vec<BoundBox> = mesh
+            .cell_points()
+            .iter()
+            .map(|cell| BoundBox::from_points(cell.iter().map(|&point| &points[point])))
+            .collect();
+        let face_boxes: Vec<BoundBox> = (mesh.n_internal_faces()..mesh.n_faces())
+            .map(|face| BoundBox::from_points(mesh.faces.data[face].iter().map(|&p| &points[p])))
+            .collect();
+        MeshSearch {
+            mesh,
+            cell_tree: Octree::new(&cell_boxes),
+            face_tree: Octree::new(&face_boxes),
+            cell_faces: mesh.cell_faces(),
+            cell_centres: mesh.cell_centres(),
+            face_centres,
+            face_areas,
+            cell_boxes,
+        }
+    }
+
+    pub fn mesh(&self) -> &PolyMesh {
+        self.mesh
+    }
+
+    /// Returns the cell that contains the point, or None if the point is outside of the mesh.
+    /// A point is inside a cell if it is behind the planes of all faces of the cell, so that points
+    /// on a face shared by two cells are found in either of them.
+    pub fn find_cell(&self, point: &Point) -> Option<usize> {
+        self.cell_tree
+            .find_containing(point, |cell| self.cell_contains(cell, point))
+    }
+
+    /// Returns the cell whose centre is the nearest to the point, or None if the mesh has no cells.
+    pub fn nearest_cell(&self, point: &Point) -> Option<usize> {
+        self.cell_tree
+            .nearest(point, |cell| mag(&sub(&self.cell_centres[cell], point)))
+            .map(|(cell, _)| cell)
+    }
+
+    /// Returns the cell that contains the point, or the nearest cell if the point is outside of
+    /// the mesh.
+    pub fn find_nearest_cell(&self, point: &Point) -> Option<usize> {
+        self.find_cell(point).or_else(|| self.nearest_cell(point))
+    }
+
+    /// Returns the boundary face nearest to the point, and the nearest point on that face.
+    /// Returns None if the mesh has no boundary faces.
+    pub fn nearest_boundary_face(&self, point: &Point) -> Option<(usize, Point)> {
+        let offset = self.mesh.n_internal_faces();
+        self.face_tree
+            .nearest(point, |i| {
+                mag(&sub(&self.nearest_on_face(offset + i, point), point))
+            })
+            .map(|(i, _)| (offset + i, self.nearest_on_face(offset + i, point)))
+    }
+
+    /// Returns the faces of a cell.
+    pub(crate) fn cell_faces(&self, cell: usize) -> &[usize] {
+        &self.cell_faces[cell]
+    }
+
+    pub(crate) fn cell_centre(&self, cell: usize) -> &Point {
+        &self.cell_centres[cell]
+    }
+
+    fn cell_contains(&self, cell: usize, point: &Point) -> bool {
+        let tolerance = 1e-10 * self.cell_boxes[cell].size();
+        self.cell_faces[cell].iter().all(|&face| {
+            let area = &self.face_areas[face];
+            let magnitude = mag(area);
+            if magnitude <= f64::MIN_POSITIVE {
+                return true;
+            }
+            // the area vector points out of the owner cell
+            let outwards = if self.mesh.owner.data[face] == cell {
+                1.0
+            } else {
+                -1.0
+            };
+            let distance = dot(&sub(point, &self.face_centres[face]), area) / magnitude;
+            outwards * distance <= tolerance
+        })
+    }
+
+    /// Returns the nearest point to `point` on a face, decomposed into triangles around its centre.
+    fn nearest_on_face(&self, face: usize, point: &Point) -> Point {
+        let points = &self.mesh.points.data;
+        let vertices = &self.mesh.faces.data[face];
+        if vertices.len() == 3 {
+            let [a, b, c] = [0, 1, 2].map(|i| &points[vertices[i]]);
+            return nearest_on_triangle(point, a, b, c);
+        }
+        let centre = &self.face_centres[face];
+        (0..vertices.len())
+            .map(|i| {
+                let this = &points[vertices[i]];
+                let next = &points[vertices[(i + 1) % vertices.len()]];
+                nearest_on_triangle(point, centre, this, next)
+            })
+            .min_by(|p, q| mag(&sub(p, point)).total_cmp(&mag(&sub(q, point))))
+            .unwrap_or(*centre)
+    }
+}
+
+/// Returns the nearest point to `p` on the triangle (a, b, c).
+fn nearest_on_triangle(p: &Point, a: &Point, b: &Point, c: &Point) -> Point {
+    let ab = sub(b, a);
+    let ac = sub(c, a);
+    let ap = sub(p, a);
+    let (d1, d2) = (dot(&ab, &ap), dot(&ac, &ap));
+    if d1 <= 0.0 && d2 <= 0.0 {
+        return *a;
+    }
+    let bp = sub(p, b);
+    let (d3, d4) = (dot(&ab, &bp), dot(&ac, &bp));
+    if d3 >= 0.0 && d4 <= d3 {
+        return *b;
+    }
+    let vc = d1 * d4 - d3 * d2;
+    if vc <= 0.0 && d1 >= 0.0 && d3 <= 0.0 {
+        return add(a, &scale(&ab, d1 / (d1 - d3)));
+    }
+    let cp = sub(p, c);
+    let (d5, d6) = (dot(&ab, &cp), dot(&ac, &cp));
+    if d6 >= 0.0 && d5 <= d6 {
+        return *c;
+    }
+    let vb = d5 * d2 - d1 * d6;
+    if vb <= 0.0 && d2 >= 0.0 && d6 <= 0.0 {
+        return add(a, &scale(&ac, d2 / (d2 - d6)));
+    }
+    let va = d3 * d6 - d5 * d4;
+    if va <= 0.0 && d4 - d3 >= 0.0 && d5 - d6 >= 0.0 {
+        let bc = sub(c, b);
+        return add(b, &scale(&bc, (d4 - d3) / ((d4 - d3) + (d5 - d6))));
+    }
+    let sum = va + vb + vc;
+    if sum <= f64::MIN_POSITIVE {
+        // degenerate triangle
+        return *a;
+    }
+    add(a, &add(&scale(&ab, vb / sum), &scale(&ac, vc / sum)))
+}
+
+/// An axis-aligned bounding box.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct BoundBox {
+    min: Point,
+    max: Point,
+}
+
+impl BoundBox {
+    const EMPTY: BoundBox = BoundBox {
+        min: [f64::INFINITY; 3],
+        max: [f64::NEG_INFINITY; 3],
+    };
+
+    pub(crate) fn from_points<'a, I: IntoIterator<Item = &'a Point>>(points: I) -> Self {
+        let mut bound_box = BoundBox::EMPTY;
+        for point in points {
+            bound_box = bound_box.union(&BoundBox {
+                min: *point,
+                max: *point,
+            });
+        }
+        bound_box
+    }
+
+    fn union(&self, other: &BoundBox) -> BoundBox {
+        BoundBox {
+            min: std::array::from_fn(|i| self.min[i].min(other.min[i])),
+            max: std::array::from_fn(|i| self.max[i].max(other.max[i])),
+        }
+    }
+
+    fn centre(&self) -> Point {
+        scale(&add(&self.min, &self.max), 0.5)
+    }
+
+    /// Returns the length of the diagonal.
+    fn size(&self) -> f64 {
+        mag(&sub(&self.max, &self.min))
+    }
+
+    pub(crate) fn overlaps(&self, other: &BoundBox) -> bool {
+        (0..3).all(|i| self.min[i] <= other.max[i] && other.min[i] <= self.max[i])
+    }
+
+    fn contains(&self, point: &Point, tolerance: f64) -> bool {
+        (0..3).all(|i| point[i] >= self.min[i] - tolerance && point[i] <= self.max[i] + tolerance)
+    }
+
+    /// Returns the distance from the point to the box, zero inside the box.
+    fn distance(&self, point: &Point) -> f64 {
+        let outside: Point = std::array::from_fn(|i| {
+            (self.min[i] - point[i])
+                .max(point[i] - self.max[i])
+                .max(0.0)
+        });
+        mag(&outside)
+    }
+}
+
+/// An octree of the bounding boxes of items, e.g. cells or faces.
+/// Each node holds the bounding box of all items below it. The items are distributed to the
+/// octants of a node by the centres of their boxes, so that the boxes of the children may overlap.
+pub(crate) struct Octree {
+    nodes: Vec<Node>,
+}
+
+struct Node {
+    bound_box: BoundBox,
+    children: Vec<usize>,
+    items: Vec<usize>,
+}
+
+const MAX_LEAF_SIZE: usize = 8;
+const MAX_DEPTH: usize = 20;
+
+impl Octree {
+    pub(crate) fn new(boxes: &[BoundBox]) -> Self {
+        let mut tree = Octree { nodes: Vec::new() };
+        tree.build(boxes, (0..boxes.len()).collect(), 0);
+        tree
+    }
+
+    /// Adds the node of the items and the nodes below it, and returns its index.
+    fn build(&mut self, boxes: &[BoundBox], items: Vec<usize>, depth: usize) -> usize {
+        let bound_box = items.iter().fold(BoundBox::EMPTY, |bound_box, &item| {
+            bound_box.union(&boxes[item])
+        });
+        let index = self.nodes.len();
+        self.nodes.push(Node {
+            bound_box,
+            children: Vec::new(),
+            items: Vec::new(),
+        });
+        if items.len() <= MAX_LEAF_SIZE || depth >= MAX_DEPTH {
+            self.nodes[index].items = items;
+            return index;
+        }
+        let middle = bound_box.centre();
+        let mut octants = vec![Vec::new(); 8];
+        for &item in &items {
+            let centre = boxes[item].centre();
+            let octant = (0..3).fold(0, |octant, i| {
+                octant | (((centre[i] > middle[i]) as usize) << i)
+            });
+            octants[octant].push(item);
+        }
+        // items that cannot be separated stay in a leaf
+        if octants.iter().filter(|octant| !octant.is_empty()).count() < 2 {
+            self.nodes[index].items = items;
+            return index;
+        }
+        let children = octants
+            .into_iter()
+            .filter(|octant| !octant.is_empty())
+            .map(|octant| self.build(boxes, octant, depth + 1))
+            .collect();
+        self.nodes[index].children = children;
+        index
+    }
+
+    /// Returns the first item whose box contains the point and that passes the test.
+    fn find_containing(&self, point: &Point, test: impl Fn(usize) -> bool) -> Option<usize> {
+        let root = self.nodes.first()?;
+        let tolerance = 1e-10 * root.bound_box.size();
+        let mut stack = vec![0];
+        while let Some(node) = stack.pop() {
+            let node = &self.nodes[node];
+            if !node.bound_box.contains(point, tolerance) {
+                continue;
+            }
+            if let Some(&item) = node.items.iter().find(|&&item| test(item)) {
+                return Some(item);
+            }
+            stack.extend(&node.children);
+        }
+        None
+    }
+
+    /// Returns the items of all leaves whose boxes overlap the given box. This includes all items
+    /// whose own boxes overlap it, but may include others.
+    pub(crate) fn overlapping(&self, bound_box: &BoundBox) -> Vec<usize> {
+        let mut items = Vec::new();
+        let mut stack = vec![0];
+        while let Some(node) = stack.pop() {
+            let Some(node) = self.nodes.get(node) else {
+                break;
+            };
+            if node.bound_box.overlaps(bound_box) {
+                items.extend(&node.items);
+                stack.extend(&node.children);
+            }
+        }
+        items
+    }
+
+    /// Returns the item with the smallest distance to the point, and the distance.
+    /// The distance to an item must not be smaller than the distance to its bounding box.
+    pub(crate) fn nearest(
+        &self,
+        point: &Point,
+        distance: impl Fn(usize) -> f64,
+    ) -> Option<(usize, f64)> {
+        let mut nearest: Option<(usize, f64)> = None;
+        let mut stack = vec![0];
+        while let Some(node) = stack.pop() {
+            let Some(node) = self.nodes.get(node) else {
+                break;
+            };
+            let bound = nearest.map_or(f64::INFINITY, |(_, d)| d);
+            if node.bound_box.distance(point) >= bound {
+                continue;
+            }
+            for &item in &node.items {
+                let d = distance(item);
+                if nearest.is_none_or(|(_, nearest)| d < nearest) {
+                    nearest = Some((item, d));
+                }
+            }
+            // visit the nearest children first
+            let mut children = node.children.clone();
+            children.sort_by(|&a, &b| {
+                let distance = |child: usize| self.nodes[child].bound_box.distance(point);
+                distance(b).total_cmp(&distance(a))
+            });
+            stack.extend(children);
+        }
+        nearest
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mesh() -> PolyMesh {
+        let path = std::path::Path::new("tests/test_cases/original/two_zones_with_interfaces");
+        PolyMesh::parse(&path.join("constant/polyMesh")).unwrap()
+    }
+
+    #[test]
+    fn test_nearest_on_triangle() {
+        let (a, b, c) = ([0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]);
+        let nearest = nearest_on_triangle(&[0.2, 0.2, 1.0], &a, &b, &c);
+        assert!(mag(&sub(&nearest, &[0.2, 0.2, 0.0])) < 1e-12);
+        assert_eq!(nearest_on_triangle(&[-1.0, -1.0, 0.0], &a, &b, &c), a);
+        assert_eq!(nearest_on_triangle(&[2.0, 0.5, 0.0], &a, &b, &c), b);
+        assert_eq!(
+            nearest_on_triangle(&[1.0, 1.0, 0.0], &a, &b, &c),
+            [0.5, 0.5, 0.0]
+        );
+    }
+
+    #[test]
+    fn test_find_cell() {
+        let mesh = mesh();
+        let search = mesh.search();
+        let centres = mesh.cell_centres();
+        for (cell, centre) in centres.iter().enumerate() {
+            assert_eq!(search.find_cell(centre), Some(cell));
+            assert_eq!(search.nearest_cell(centre), Some(cell));
+        }
+        let outside = [1e3, 1e3, 1e3];
+        assert_eq!(search.find_cell(&outside), None);
+        // the nearest cell may not be unique, so compare the distances
+        let distance = |cell: usize| mag(&sub(&centres[cell], &outside));
+        let nearest = search.find_nearest_cell(&outside).unwrap();
+        let brute_force = (0..mesh.n_cells())
+            .map(distance)
+            .fold(f64::INFINITY, f64::min);
+        assert_eq!(distance(nearest), brute_force);
+    }
+
+    #[test]
+    fn test_nearest_boundary_face() {
+        let mesh = mesh();
+        let search = mesh.search();
+        let face_centres = mesh.face_centres();
+        let face = mesh.n_faces() - 1;
+        let (_, point) = search.nearest_boundary_face(&face_centres[face]).unwrap();
+        assert!(mag(&sub(&point, &face_centres[face])) < 1e-12);
+
+        let outside = [-0.3, 2.1, 0.7];
+        let (nearest, point) = search.nearest_boundary_face(&outside).unwrap();
+        let brute_force = (mesh.n_internal_faces()..mesh.n_faces())
+            .map(|face| mag(&sub(&search.nearest_on_face(face, &outside), &outside)))
+            .fold(f64::INFINITY, f64::min);
+        assert!(nearest >= mesh.n_internal_faces());
+        assert!((mag(&sub(&point, &outside)) - brute_force).abs() < 1e-12);
+    }
+}
@@ -3,9 +3,9 @@ use crate::base::parser_base::*;
 use crate::base::writer_base::*;
 use crate::base::FileElement;
 use crate::base::FileParser;
+use crate::base::{data_files, Compression};
 use nom::IResult;
 use std::collections::HashMap;
-use std::fs;
 use std::path;
 
 /// The Sets structure holds the full content of the "constant/polyMesh/sets" directory.
@@ -37,25 +37,30 @@ impl Sets {
                 format!("Directory {:?} not found.", path),
             ));
         }
-        let set_files = fs::read_dir(path).unwrap();
         let mut sets = HashMap::new();
-        for file in set_files {
-            let file = file.unwrap();
-            let path = file.path();
-            let name = path.file_name().unwrap().to_str().unwrap();
+        for (name, path) in data_files(path)? {
             let mut set = FileContent::<Set>::parse_file(&path)
                 .unwrap_or_else(|_| panic!("Failed to parse set file {:?}.", path));
             // the name is provided afterwards because it is not stored in the data
-            set.data.name = name.to_string();
-            sets.insert(name.to_string(), set);
+            set.data.name = name.clone();
+            sets.insert(name, set);
         }
         Ok(Self(sets))
     }
 
     /// Writes the complete "sets" directory to the provided path.
     pub fn write(&self, path: &path::Path) -> std::io::Result<()> {
+        self.write_compressed(path, Compression::Uncompressed)
+    }
+
+    /// Writes the complete "sets" directory to the provided path with the given compression.
+    pub fn write_compressed(
+        &self,
+        path: &path::Path,
+        compression: Compression,
+    ) -> std::io::Result<()> {
         for set in self.values() {
-            set.write_file(path)?;
+            set.write_file_compressed(path, compression)?;
         }
         Ok(())
     }
@@ -0,0 +1,275 @@
+use super::{CellToRegionData, FileContent, MeshSubset, MeshUpdate, PolyMesh, TimeDir};
+use crate::base::{FoamFile, FoamStructure, FoamValue};
+use indexmap::IndexMap;
+use std::path;
+
+/// The result of splitting a mesh into regions, the equivalent of OpenFOAM's splitMeshRegions.
+/// The faces between two regions become "mappedWall" patches named "<region>_to_<neighbour>",
+/// which sample the corresponding patch of the neighbouring region.
+#[derive(Debug, PartialEq, Clone)]
+pub struct RegionSplit {
+    /// The mesh of each region, with the maps to the original mesh.
+    /// The meshes are located in "constant/<region>/polyMesh".
+    pub regions: IndexMap<String, MeshSubset>,
+    /// The index of the region of each cell of the original mesh.
+    pub cell_to_region: FileContent<CellToRegionData>,
+}
+
+impl PolyMesh {
+    /// Splits the mesh into one region per cellZone, named after the zone.
+    /// Cells that are not part of any zone form an additional region named "domain0".
+    /// Cells that are part of several zones are attributed to the first one.
+    pub fn split_regions_by_cell_zones(&self) -> std::io::Result<RegionSplit> {
+        let mut names: Vec<String> = Vec::new();
+        let mut cell_to_region = vec![None; self.n_cells()];
+        if let Some(cellzones) = &self.cellzones {
+            for (name, zone) in cellzones.data.zones.iter() {
+                for &cell in zone.iter() {
+                    if cell_to_region[cell].is_none() {
+                        cell_to_region[cell] = Some(names.len());
+                    }
+                }
+                names.push(name.clone());
+            }
+        }
+        let unzoned = names.len();
+        if cell_to_region.iter().any(Option::is_none) {
+            names.push("domain0".to_string());
+        }
+        let cell_to_region: Vec<usize> = cell_to_region
+            .into_iter()
+            .map(|region| region.unwrap_or(unzoned))
+            .collect();
+        self.split_regions(&cell_to_region, &names)
+    }
+
+    /// Splits the mesh into regions, given the index of the region of every cell.
+    /// `names` contains the name of each region.
+    /// An error is returned if `cell_to_region` does not give a known region for every cell
+    /// or if a set of the mesh has an unknown class.
+    pub fn split_regions(
+        &self,
+        cell_to_region: &[usize],
+        names: &[String],
+    ) -> std::io::Result<RegionSplit> {
+        if cell_to_region.len() != self.n_cells() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!(
+                    "The region of every cell is needed to split the mesh, got {} regions for {} cells.",
+                    cell_to_region.len(),
+                    self.n_cells()
+                ),
+            ));
+        }
+        if let Some(cell) = cell_to_region.iter().position(|&r| r >= names.len()) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!(
+                    "Cell {} is in region {}, but only {} regions are named.",
+                    cell,
+                    cell_to_region[cell],
+                    names.len()
+                ),
+            ));
+        }
+        let n_internal_faces = self.n_internal_faces();
+        // the region on the other side of an internal face, seen from the given region
+        let other_region = |face: usize, region: usize| {
+            let owner_region = cell_to_region[self.owner.data[face]];
+            let neighbour_region = cell_to_region[self.neighbour.data[face]];
+            if owner_region == region {
+                neighbour_region
+            } else {
+                owner_region
+            }
+        };
+
+        let mut regions = IndexMap::new();
+        for (region, name) in names.iter().enumerate() {
+            let cells: Vec<usize> = (0..cell_to_region.len())
+                .filter(|&cell| cell_to_region[cell] == region)
+                .collect();
+            // the regions that share faces with this region, in order of appearance
+            let mut neighbours = Vec::new();
+            for face in 0..n_internal_faces {
+                let owner_region = cell_to_region[self.owner.data[face]];
+                let neighbour_region = cell_to_region[self.neighbour.data[face]];
+                if owner_region != neighbour_region
+                    && (owner_region == region || neighbour_region == region)
+                {
+                    let other = other_region(face, region);
+                    if !neighbours.contains(&other) {
+                        neighbours.push(other);
+                    }
+                }
+            }
+            let interface_patches: Vec<String> = neighbours
+                .iter()
+                .map(|&other| interface_patch_name(name, &names[other]))
+                .collect();
+            let mut subset = self.subset_with_patches(&cells, &interface_patches, |face| {
+                interface_patch_name(name, &names[other_region(face, region)])
+            })?;
+
+            // turn the interface patches into mapped walls
+            let boundary = &mut subset.mesh.boundary.data;
+            for &other in neighbours.iter() {
+                let patch_name = interface_patch_name(name, &names[other]);
+                let range = boundary
+                    .face_range(&patch_name)
+                    .expect("Interface patch was not created.");
+                let patch = mapped_wall_patch(
+                    range,
+                    &names[other],
+                    &interface_patch_name(&names[other], name),
+                );
+                boundary.insert(patch_name, patch);
+            }
+            subset
+                .mesh
+                .set_directory(&path::Path::new("constant").join(name).join("polyMesh"));
+            regions.insert(name.clone(), subset);
+        }
+
+        let cell_to_region = FileContent {
+            location: Some(path::PathBuf::from("constant/cellToRegion")),
+            meta: FoamFile::new("labelList", "constant", "cellToRegion"),
+            data: CellToRegionData(cell_to_region.to_vec()),
+        };
+        Ok(RegionSplit {
+            regions,
+            cell_to_region,
+        })
+    }
+}
+
+impl RegionSplit {
+    /// Splits the fields of a time directory into the regions.
+    /// The fields of each region are located in "<time>/<region>/".
+    /// The "uniform" directory is not part of the regions and stays in the original time directory.
+    /// Moved points are split as well and located in "<time>/<region>/polyMesh/points".
+    pub fn map_time_dir(&self, time_dir: &TimeDir) -> std::io::Result<IndexMap<String, TimeDir>> {
+        // the name of the time directory, taken from the location of a file
+        let time_name = |location: &Option<path::PathBuf>| match location
+            .as_ref()
+            .and_then(|p| p.iter().next())
+        {
+            Some(time_name) => path::PathBuf::from(time_name),
+            None => path::PathBuf::from(time_dir.time.to_string()),
+        };
+        let mut region_time_dirs = IndexMap::new();
+        for (name, subset) in self.regions.iter() {
+            let mut region_time_dir = subset.map_time_dir(time_dir)?;
+            for (field_name, field) in region_time_dir.field_values.iter_mut() {
+                let location = time_name(&field.location).join(name).join(field_name);
+                field.set_location(&location);
+            }
+            if let Some(MeshUpdate::Points(points)) = &mut region_time_dir.mesh {
+                let location = time_name(&points.location)
+                    .join(name)
+                    .join("polyMesh/points");
+                points.set_location(&location);
+            }
+            region_time_dir.uniform = None;
+            region_time_dirs.insert(name.clone(), region_time_dir);
+        }
+        Ok(region_time_dirs)
+    }
+
+    /// Writes the meshes of all regions and the cellToRegion file to the given case directory.
+    pub fn write(&self, path: &path::Path) -> std::io::Result<()> {
+        for subset in self.regions.values() {
+            subset.mesh.write(path)?;
+        }
+        self.cell_to_region.write_file(path)
+    }
+}
+
+/// Returns the name of the patch of `region` that faces `neighbour`.
+fn interface_patch_name(region: &str, neighbour: &str) -> String {
+    format!("{}_to_{}", region, neighbour)
+}
+
+/// Creates the boundary entry of a "mappedWall" patch that samples a patch of another region.
+fn mapped_wall_patch(
+    range: std::ops::Range<usize>,
+    sample_region: &str,
+    sample_patch: &str,
+) -> FoamValue {
+    let mut patch = FoamStructure(IndexMap::new());
+    let mut insert = |key: &str, value: FoamValue| patch.insert(key.to_string(), value);
+    insert("type", FoamValue::String("mappedWall".to_string()));
+    insert("inGroups", FoamValue::List(vec!["wall".to_string()]));
+    insert("nFaces", FoamValue::Integer(range.len()));
+    insert("startFace", FoamValue::Integer(range.start));
+    insert(
+        "sampleMode",
+        FoamValue::String("nearestPatchFace".to_string()),
+    );
+    insert("sampleRegion", FoamValue::String(sample_region.to_string()));
+    insert("samplePatch", FoamValue::String(sample_patch.to_string()));
+    FoamValue::Structure(patch)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_two_zones() {
+        let path = path::Path::new("tests/test_cases/original/two_zones_with_interfaces");
+        let mesh = PolyMesh::parse(&path.join("constant/polyMesh")).unwrap();
+        let split = mesh.split_regions_by_cell_zones().unwrap();
+        assert_eq!(
+            split.regions.keys().collect::<Vec<_>>(),
+            vec!["channel", "metal"]
+        );
+        let n_cells: usize = split.regions.values().map(|r| r.mesh.n_cells()).sum();
+        assert_eq!(n_cells, mesh.n_cells());
+
+        let channel = &split.regions["channel"].mesh.boundary.data;
+        let metal = &split.regions["metal"].mesh.boundary.data;
+        assert_eq!(channel.patch_type("channel_to_metal"), Some("mappedWall"));
+        let Some(FoamValue::Structure(patch)) = channel.get("channel_to_metal") else {
+            panic!("Interface patch not found.");
+        };
+        assert_eq!(
+            patch.get("samplePatch"),
+            Some(&FoamValue::String("metal_to_channel".to_string()))
+        );
+        let interface = &mesh.facezones.as_ref().unwrap().data.zones["interface"];
+        assert_eq!(
+            channel.face_range("channel_to_metal").unwrap().len(),
+            interface.len()
+        );
+        assert_eq!(
+            metal.face_range("metal_to_channel").unwrap().len(),
+            interface.len()
+        );
+        assert_eq!(
+            split.regions["metal"].mesh.points.location,
+            Some(path::PathBuf::from("constant/metal/polyMesh/points"))
+        );
+
+        // the region meshes can be written and read back
+        let copy_path = crate::base::TempDir::new("split_regions");
+        split.write(&copy_path).unwrap();
+        let metal_copy = PolyMesh::parse(&copy_path.join("constant/metal/polyMesh")).unwrap();
+        assert_eq!(metal_copy.boundary.data, *metal);
+        assert!(copy_path.join("constant/cellToRegion").is_file());
+    }
+
+    #[test]
+    fn test_split_invalid_regions() {
+        let path = path::Path::new("tests/test_cases/original/two_zones_with_interfaces");
+        let mesh = PolyMesh::parse(&path.join("constant/polyMesh")).unwrap();
+        let names = vec!["a".to_string(), "b".to_string()];
+        let error = mesh.split_regions(&[0, 1], &names).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+        let mut cell_to_region = vec![0; mesh.n_cells()];
+        cell_to_region[0] = 2;
+        let error = mesh.split_regions(&cell_to_region, &names).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+    }
+}
@@ -0,0 +1,633 @@
+use super::{
+    CellZone, FaceData, FaceZone, FieldLocation, FileContent, MeshUpdate, NeighbourData, OwnerData,
+    Point, PointData, PointZone, PolyMesh, ResultData, Set, Sets, TimeDir, Zone, ZoneData,
+};
+use crate::base::{FoamField, FoamStructure, FoamValue};
+use std::collections::HashMap;
+use std::ops::Range;
+
+/// The result of extracting a subset of cells from a mesh, the equivalent of OpenFOAM's subsetMesh.
+/// Besides the new mesh, it holds the maps from the new mesh entities to the original ones,
+/// which are used to map fields onto the new mesh.
+#[derive(Debug, PartialEq, Clone)]
+pub struct MeshSubset {
+    pub mesh: PolyMesh,
+    /// For each point of the new mesh, the label of the point in the original mesh.
+    pub point_map: Vec<usize>,
+    /// For each face of the new mesh, the label of the face in the original mesh.
+    pub face_map: Vec<usize>,
+    /// For each face of the new mesh, whether it is oriented opposite to the original face.
+    /// This happens for exposed internal faces whose owner was removed.
+    pub face_flip: Vec<bool>,
+    /// For each cell of the new mesh, the label of the cell in the original mesh.
+    pub cell_map: Vec<usize>,
+    // Information about the original mesh, needed to map the boundary fields.
+    original_patches: Vec<(String, Range<usize>)>,
+    original_n_cells: usize,
+    original_n_internal_faces: usize,
+    original_n_points: usize,
+}
+
+/// A face of the subset: the original face, the new owner cell and whether the face is flipped.
+type SubsetFace = (usize, usize, bool);
+
+impl PolyMesh {
+    /// Returns the cells whose centre fulfills the given predicate.
+    /// The result can be used to create a subset of the mesh.
+    pub fn cells_where<F: Fn(&Point) -> bool>(&self, predicate: F) -> Vec<usize> {
+        self.cell_centres()
+            .iter()
+            .enumerate()
+            .filter(|(_, centre)| predicate(centre))
+            .map(|(cell, _)| cell)
+            .collect()
+    }
+
+    /// Creates a new mesh that only contains the given cells.
+    /// The cells can be taken from a `CellZone`, a cell `Set` or from `cells_where`.
+    /// Points, faces and cells are renumbered while preserving their original order.
+    /// Internal faces that become boundary faces are collected into the patch `exposed_patch`,
+    /// which is appended to the boundary if it does not exist yet.
+    /// Zones and sets are restricted to the entities that remain in the new mesh.
+    /// An error is returned if a set has an unknown class.
+    pub fn subset(&self, cells: &[usize], exposed_patch: &str) -> std::io::Result<MeshSubset> {
+        self.subset_with_patches(cells, &[exposed_patch.to_string()], |_| {
+            exposed_patch.to_string()
+        })
+    }
+
+    /// Creates a new mesh that only contains the given cells, like `subset`.
+    /// Each exposed face is put into the patch returned by `exposed_patch` for its original label.
+    /// The patches in `new_patches` are created even if they do not receive any face.
+    pub(crate) fn subset_with_patches<F: Fn(usize) -> String>(
+        &self,
+        cells: &[usize],
+        new_patches: &[String],
+        exposed_patch: F,
+    ) -> std::io::Result<MeshSubset> {
+        let n_cells = self.n_cells();
+        let mut cell_map = cells.to_vec();
+        cell_map.sort_unstable();
+        cell_map.dedup();
+        let mut new_cell = vec![None; n_cells];
+        for (new, &old) in cell_map.iter().enumerate() {
+            if old >= n_cells {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    format!("Cell {} is not part of the mesh.", old),
+                ));
+            }
+            new_cell[old] = Some(new);
+        }
+
+        // Internal faces remain internal if both cells are kept, otherwise they become exposed.
+        let mut internal = Vec::new();
+        let mut new_neighbour = Vec::new();
+        let mut exposed = Vec::new();
+        let n_internal_faces = self.n_internal_faces();
+        for face in 0..n_internal_faces {
+            let owner = new_cell[self.owner.data[face]];
+            let neighbour = new_cell[self.neighbour.data[face]];
+            match (owner, neighbour) {
+                (Some(owner), Some(neighbour)) => {
+                    internal.push((face, owner, false));
+                    new_neighbour.push(neighbour);
+                }
+                (Some(owner), None) => exposed.push((face, owner, false)),
+                (None, Some(neighbour)) => exposed.push((face, neighbour, true)),
+                (None, None) => {}
+            }
+        }
+
+        // Boundary faces stay in their patch if their owner is kept.
+        let original_patches = self.boundary.data.face_ranges();
+        let mut patches: Vec<(String, Vec<SubsetFace>)> = original_patches
+            .iter()
+            .map(|(name, range)| {
+                let faces = range
+                    .clone()
+                    .filter_map(|face| Some((face, new_cell[self.owner.data[face]]?, false)))
+                    .collect();
+                (name.clone(), faces)
+            })
+            .collect();
+        for name in new_patches {
+            if !patches.iter().any(|(patch, _)| patch == name) {
+                patches.push((name.clone(), Vec::new()));
+            }
+        }
+        for exposed_face in exposed {
+            let name = exposed_patch(exposed_face.0);
+            match patches.iter_mut().find(|(patch, _)| *patch == name) {
+                Some((_, faces)) => faces.push(exposed_face),
+                None => patches.push((name, vec![exposed_face])),
+            }
+        }
+
+        // Assemble the new face list.
+        let mut face_map = Vec::new();
+        let mut face_flip = Vec::new();
+        let mut new_owner = Vec::new();
+        let mut boundary = self.boundary.data.clone();
+        for (face, owner, flip) in internal.iter() {
+            face_map.push(*face);
+            face_flip.push(*flip);
+            new_owner.push(*owner);
+        }
+        for (name, faces) in patches.iter() {
+            boundary.set_face_range(name, face_map.len()..face_map.len() + faces.len());
+            for (face, owner, flip) in faces {
+                face_map.push(*face);
+                face_flip.push(*flip);
+                new_owner.push(*owner);
+            }
+        }
+
+        // Only keep the points that are used by the remaining faces.
+        let mut new_point = vec![None; self.n_points()];
+        for &face in face_map.iter() {
+            for &point in self.faces.data[face].iter() {
+                new_point[point] = Some(0);
+            }
+        }
+        let mut point_map = Vec::new();
+        for (old, new) in new_point.iter_mut().enumerate() {
+            if new.is_some() {
+                *new = Some(point_map.len());
+                point_map.push(old);
+            }
+        }
+        let mut faces: FaceData = face_map
+            .iter()
+            .zip(face_flip.iter())
+            .map(|(&face, &flip)| {
+                let vertices: Vec<usize> = self.faces.data[face]
+                    .iter()
+                    .map(|&point| new_point[point].unwrap())
+                    .collect();
+                if flip {
+                    reversed_face(&vertices)
+                } else {
+                    vertices
+                }
+            })
+            .collect();
+        faces.set_compact(self.faces.data.is_compact());
+        let points = point_map.iter().map(|&p| self.points.data[p]).collect();
+
+        let mut new_face = vec![None; self.n_faces()];
+        for (new, &old) in face_map.iter().enumerate() {
+            new_face[old] = Some(new);
+        }
+
+        let mut mesh = PolyMesh {
+            points: self.points.with_data(PointData(points)),
+            faces: self.faces.with_data(faces),
+            owner: self.owner.with_data(OwnerData(new_owner)),
+            neighbour: self.neighbour.with_data(NeighbourData(new_neighbour)),
+            boundary: self.boundary.with_data(boundary),
+            facezones: self.facezones.as_ref().map(|zones| {
+                map_zones(zones, |zone: &FaceZone| {
+                    zone.renumbered(|face| new_face[face], |face| face_flip[face])
+                })
+            }),
+            cellzones: self.cellzones.as_ref().map(|zones| {
+                map_zones(zones, |zone: &CellZone| CellZone {
+                    cells: renumber(&zone.cells, &new_cell),
+                    ..zone.clone()
+                })
+            }),
+            pointzones: self.pointzones.as_ref().map(|zones| {
+                map_zones(zones, |zone: &PointZone| PointZone {
+                    points: renumber(&zone.points, &new_point),
+                    ..zone.clone()
+                })
+            }),
+            sets: self
+                .sets
+                .as_ref()
+                .map(|sets| {
+                    sets.iter()
+                        .map(|(name, set)| {
+                            let class = set.meta.class().unwrap_or_default();
+                            let new_labels = match class {
+                                "cellSet" => &new_cell,
+                                "faceSet" => &new_face,
+                                "pointSet" => &new_point,
+                                _ => {
+                                    return Err(invalid_data(format!(
+                                        "The set {} has the unknown class {}.",
+                                        name, class
+                                    )))
+                                }
+                            };
+                            let labels = renumber(&set.data.labels, new_labels);
+                            let data = Set {
+                                name: set.data.name.clone(),
+                                n: labels.len(),
+                                labels,
+                            };
+                            Ok((name.clone(), set.with_data(data)))
+                        })
+                        .collect::<std::io::Result<_>>()
+                        .map(Sets)
+                })
+                .transpose()?,
+        };
+        mesh.update_note();
+
+        Ok(MeshSubset {
+            mesh,
+            point_map,
+            face_map,
+            face_flip,
+            cell_map,
+            original_patches,
+            original_n_cells: n_cells,
+            original_n_internal_faces: n_internal_faces,
+            original_n_points: self.n_points(),
+        })
+    }
+}
+
+impl MeshSubset {
+    /// Maps a field of the original mesh onto the subset mesh.
+    /// The boundary conditions of the original patches are kept, and their values are restricted to
+    /// the remaining faces. Other entries, such as patch name patterns, are kept unchanged.
+    /// The exposed faces receive a "calculated" condition with the values of
+    /// the adjacent cells (volume fields) or of the original internal faces (surface fields).
+    pub fn map_field(
+        &self,
+        field: &FileContent<ResultData>,
+    ) -> std::io::Result<FileContent<ResultData>> {
+        let location = field
+            .meta
+            .class()
+            .and_then(FieldLocation::from_class)
+            .ok_or_else(|| invalid_data(format!("Unknown field class in {:?}.", field.location)))?;
+        let internal = &field.data.result;
+        let (expected, map) = match location {
+            FieldLocation::Cells => (self.original_n_cells, self.cell_map.clone()),
+            FieldLocation::Faces => (
+                self.original_n_internal_faces,
+                self.face_map[..self.mesh.n_internal_faces()].to_vec(),
+            ),
+            FieldLocation::Points => (self.original_n_points, self.point_map.clone()),
+        };
+        check_n_values(internal, expected, "internalField")?;
+
+        let mut data = ResultData {
+            dimensions: field.data.dimensions.clone(),
+            result: internal.subset(&map),
+            boundary_field: field.data.boundary_field.clone(),
+        };
+        if let Some(original_conditions) = field.data.boundary_patches() {
+            // entries that are not patches of the mesh, e.g. patch name patterns, are kept
+            let mut conditions = original_conditions.clone();
+            let is_original =
+                |name: &str| self.original_patches.iter().any(|(patch, _)| patch == name);
+            for (name, range) in self.mesh.boundary.data.face_ranges() {
+                let condition = match original_conditions.get(&name) {
+                    Some(FoamValue::Structure(condition)) => {
+                        let mut condition = condition.clone();
+                        if location != FieldLocation::Points {
+                            if let Some(FoamValue::Field(value)) = condition.get("value") {
+                                let value = self.map_patch_value(field, &name, value, &range)?;
+                                condition.insert("value".to_string(), FoamValue::Field(value));
+                            }
+                        }
+                        FoamValue::Structure(condition)
+                    }
+                    Some(other) => other.clone(),
+                    // original patches without an entry of their own are matched by patterns
+                    None if is_original(&name) => continue,
+                    None => {
+                        let mut condition = FoamStructure(Default::default());
+                        condition.insert(
+                            "type".to_string(),
+                            FoamValue::String("calculated".to_string()),
+                        );
+                        if location != FieldLocation::Points {
+                            let value = self.exposed_values(internal, location, range)?;
+                            condition.insert("value".to_string(), FoamValue::Field(value));
+                        }
+                        FoamValue::Structure(condition)
+                    }
+                };
+                conditions.insert(name, condition);
+            }
+            if let Some(patches) = data.boundary_patches_mut() {
+                *patches = conditions;
+            }
+        }
+        Ok(field.with_data(data))
+    }
+
+    /// Maps all fields of a time directory onto the subset mesh.
+    /// Moved points are mapped as well, but meshes with changed topology are dropped,
+    /// because they do not match the original mesh of the subset.
+    pub fn map_time_dir(&self, time_dir: &TimeDir) -> std::io::Result<TimeDir> {
+        let mut field_values = HashMap::new();
+        for (name, field) in time_dir.field_values.iter() {
+            field_values.insert(name.clone(), self.map_field(field)?);
+        }
+        let mesh = match &time_dir.mesh {
+            Some(MeshUpdate::Points(points)) => {
+                if points.data.len() != self.original_n_points {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::InvalidData,
+                        format!(
+                            "{:?} has {} points, but {} expected.",
+                            points.location,
+                            points.data.len(),
+                            self.original_n_points
+                        ),
+                    ));
+                }
+                let moved = self.point_map.iter().map(|&p| points.data[p]).collect();
+                Some(MeshUpdate::Points(points.with_data(PointData(moved))))
+            }
+            _ => None,
+        };
+        Ok(TimeDir {
+            field_values,
+            mesh,
+            ..time_dir.clone()
+        })
+    }
+
+    /// Assembles the value of a patch of the new mesh from the value of the original patch and,
+    /// for exposed faces, the internal field.
+    fn map_patch_value(
+        &self,
+        field: &FileContent<ResultData>,
+        name: &str,
+        value: &FoamField,
+        range: &Range<usize>,
+    ) -> std::io::Result<FoamField> {
+        let original_range = self
+            .original_patches
+            .iter()
+            .find(|(original, _)| original == name)
+            .map(|(_, range)| range.clone())
+            .unwrap_or(0..0);
+        // Patches of type "empty" have no values at all.
+        if value.n_values() == Some(0) {
+            return Ok(value.clone());
+        }
+        check_n_values(value, original_range.len(), name)?;
+        let internal = &field.data.result;
+        let location = FieldLocation::from_class(field.meta.class().unwrap_or_default());
+        let picks: Vec<(&FoamField, usize, bool)> = range
+            .clone()
+            .map(|face| {
+                let original = self.face_map[face];
+                if original_range.contains(&original) {
+                    (value, original - original_range.start, false)
+                } else if location == Some(FieldLocation::Cells) {
+                    (internal, self.cell_map[self.mesh.owner.data[face]], false)
+                } else {
+                    (internal, original, self.face_flip[face])
+                }
+            })
+            .collect();
+        Ok(gather(&picks)?.unwrap_or_else(|| value.subset(&[])))
+    }
+
+    /// Returns the values of the exposed faces of a patch that did not exist in the original mesh.
+    fn exposed_values(
+        &self,
+        internal: &FoamField,
+        location: FieldLocation,
+        range: Range<usize>,
+    ) -> std::io::Result<FoamField> {
+        let picks: Vec<(&FoamField, usize, bool)> = range
+            .map(|face| match location {
+                FieldLocation::Cells => {
+                    (internal, self.cell_map[self.mesh.owner.data[face]], false)
+                }
+                _ => (internal, self.face_map[face], self.face_flip[face]),
+            })
+            .collect();
+        Ok(gather(&picks)?.unwrap_or_else(|| internal.subset(&[])))
+    }
+}
+
+/// Reverses the orientation of a face while keeping its first point, as OpenFOAM does.
+pub(crate) fn reversed_face(face: &[usize]) -> Vec<usize> {
+    let mut reversed = Vec::with_capacity(face.len());
+    if let Some((first, rest)) = face.split_first() {
+        reversed.push(*first);
+        reversed.extend(rest.iter().rev());
+    }
+    reversed
+}
+
+/// Renumbers the labels with the given map, dropping the labels that are mapped to nothing.
+pub(crate) fn renumber(labels: &[usize], new_labels: &[Option<usize>]) -> Vec<usize> {
+    labels
+        .iter()
+        .filter_map(|&label| *new_labels.get(label)?)
+        .collect()
+}
+
+/// Applies a function to every zone in a zone file.
+fn map_zones<T: Zone + Clone, F: Fn(&T) -> T>(
+    zones: &FileContent<ZoneData<T>>,
+    f: F,
+) -> FileContent<ZoneData<T>> {
+    let mut new_zones = zones.clone();
+    for zone in new_zones.data.zones.values_mut() {
+        *zone = f(zone);
+    }
+    new_zones
+}
+
+/// Assembles a nonuniform field from single values picked from other fields.
+/// Each pick is the field, the index of the value and whether its sign needs to be flipped.
+/// Returns a uniform field if all values are picked from the same uniform field without flip,
+/// and `None` if nothing is picked.
+pub(crate) fn gather(picks: &[(&FoamField, usize, bool)]) -> std::io::Result<Option<FoamField>> {
+    let Some((first, _, _)) = picks.first() else {
+        return Ok(None);
+    };
+    let is_uniform = matches!(
+        first,
+        FoamField::UniformScalar(_) | FoamField::UniformVector(_)
+    );
+    if is_uniform && picks.iter().all(|(f, _, flip)| f == first && !flip) {
+        return Ok(Some((*first).clone()));
+    }
+    let sign = |flip: bool| if flip { -1.0 } else { 1.0 };
+    let mixed = || {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "Cannot combine scalar and vector values.",
+        )
+    };
+    let field = match first {
+        FoamField::UniformScalar(_) | FoamField::Scalar(_) => FoamField::Scalar(
+            picks
+                .iter()
+                .map(|(field, i, flip)| match field {
+                    FoamField::UniformScalar(value) => Ok(sign(*flip) * value),
+                    FoamField::Scalar(values) => Ok(sign(*flip) * values[*i]),
+                    _ => Err(mixed()),
+                })
+                .collect::<std::io::Result<_>>()?,
+        ),
+        FoamField::UniformVector(_) | FoamField::Vector(_) => FoamField::Vector(
+            picks
+                .iter()
+                .map(|(field, i, flip)| {
+                    let value = match field {
+                        FoamField::UniformVector(value) => value,
+                        FoamField::Vector(values) => &values[*i],
+                        _ => return Err(mixed()),
+                    };
+                    Ok(value.iter().map(|v| sign(*flip) * v).collect())
+                })
+                .collect::<std::io::Result<_>>()?,
+        ),
+    };
+    Ok(Some(field))
+}
+
+/// Checks that a nonuniform field has the expected number of values.
+pub(crate) fn check_n_values(
+    field: &FoamField,
+    expected: usize,
+    what: &str,
+) -> std::io::Result<()> {
+    match field.n_values() {
+        Some(n) if n != expected => Err(invalid_data(format!(
+            "{} has {} values, but {} were expected.",
+            what, n, expected
+        ))),
+        _ => Ok(()),
+    }
+}
+
+fn invalid_data(message: String) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base::FileElement;
+
+    fn two_zones() -> PolyMesh {
+        let path = std::path::Path::new("tests/test_cases/original/two_zones_with_interfaces");
+        PolyMesh::parse(&path.join("constant/polyMesh")).unwrap()
+    }
+
+    #[test]
+    fn test_subset_cellzone() {
+        let mesh = two_zones();
+        let metal = mesh.cellzones.as_ref().unwrap().data.zones["metal"].clone();
+        let subset = mesh.subset(&metal, "oldInternalFaces").unwrap();
+        let new_mesh = &subset.mesh;
+        assert_eq!(new_mesh.n_cells(), metal.len());
+        assert_eq!(subset.face_map.len(), new_mesh.n_faces());
+        // the interface between the zones becomes the new patch
+        let interface = &mesh.facezones.as_ref().unwrap().data.zones["interface"];
+        let exposed = new_mesh
+            .boundary
+            .data
+            .face_range("oldInternalFaces")
+            .unwrap();
+        assert_eq!(exposed.len(), interface.len());
+        // the subset is a closed volume, so the face area vectors sum up to zero
+        let areas = new_mesh.face_area_vectors();
+        let mut sum = [0.0; 3];
+        for area in &areas[new_mesh.n_internal_faces()..] {
+            sum = crate::base::vector::add(&sum, area);
+        }
+        assert!(crate::base::vector::mag(&sum) < 1e-9);
+        // the volume is preserved
+        let volumes = mesh.cell_volumes();
+        let original: f64 = metal.iter().map(|&c| volumes[c]).sum();
+        let new: f64 = new_mesh.cell_volumes().iter().sum();
+        assert!((original - new).abs() < 1e-9 * original);
+        // the zones are restricted to the subset
+        let zones = &new_mesh.cellzones.as_ref().unwrap().data.zones;
+        assert_eq!(zones["metal"].len(), metal.len());
+        assert!(zones["channel"].is_empty());
+    }
+
+    #[test]
+    fn test_map_field() {
+        let mesh = two_zones();
+        let cells = mesh.cells_where(|c| c[0] < 0.0);
+        let subset = mesh.subset(&cells, "cut").unwrap();
+        let input = "
+FoamFile
+{
+    format      ascii;
+    class       volScalarField;
+    object      T;
+}
+dimensions      [0 0 0 1 0 0 0];
+internalField   uniform 300;
+boundaryField
+{
+    inlet
+    {
+        type            fixedValue;
+        value           uniform 350;
+    }
+    \"(outlet|metal_.*)\"
+    {
+        type            zeroGradient;
+    }
+    \".*\"
+    {
+        type            symmetry;
+    }
+}
+";
+        let (_, field) = FileContent::<ResultData>::parse(input).unwrap();
+        let mapped = subset.map_field(&field).unwrap();
+        assert_eq!(mapped.data.result, FoamField::UniformScalar(300.0));
+        // the patterns are kept, and only the exposed patch receives a new entry
+        let patches = mapped.data.boundary_patches().unwrap();
+        assert_eq!(
+            patches.keys().collect::<Vec<_>>(),
+            vec!["inlet", "(outlet|metal_.*)", ".*", "cut"]
+        );
+        assert_eq!(
+            mapped.data.patch_value("cut"),
+            Some(&FoamField::UniformScalar(300.0))
+        );
+    }
+
+    #[test]
+    fn test_subset_unknown_set_class() {
+        let mut mesh = two_zones();
+        let set = FileContent {
+            location: None,
+            meta: crate::base::FoamFile::new("edgeSet", "constant/polyMesh/sets", "edges"),
+            data: Set {
+                name: "edges".to_string(),
+                n: 1,
+                labels: vec![0],
+            },
+        };
+        mesh.sets = Some(Sets([("edges".to_string(), set)].into_iter().collect()));
+        let error = mesh.subset(&[0, 1], "cut").unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_subset_invalid_input() {
+        let mesh = two_zones();
+        let error = mesh.subset(&[0, mesh.n_cells()], "cut").unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+
+        let scalar = FoamField::UniformScalar(1.0);
+        let vector = FoamField::UniformVector(vec![1.0, 0.0, 0.0]);
+        let error = gather(&[(&scalar, 0, false), (&vector, 0, false)]).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+    }
+}
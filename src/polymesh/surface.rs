@@ -0,0 +1,332 @@
+use super::probes::format_number;
+use super::{Point, PolyMesh, ResultData};
+use crate::base::vector::*;
+use crate::base::{FileContent, FoamField};
+use indexmap::IndexMap;
+use std::collections::HashMap;
+
+/// A patch of the mesh as a standalone surface, with its own compact list of points.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PatchSurface {
+    pub name: String,
+    pub points: Vec<Point>,
+    pub faces: Vec<Vec<usize>>,
+    /// The mesh point of each point of the surface.
+    pub point_map: Vec<usize>,
+    /// The face of the patch (counted from the start of the patch) of each face of the surface.
+    /// Faces of a triangulated surface share the face of the patch they were split from.
+    pub face_map: Vec<usize>,
+    /// The values of fields on the faces, by field name.
+    pub face_fields: IndexMap<String, FoamField>,
+}
+
+impl PolyMesh {
+    /// Returns the faces of a patch as a surface, in the order of the patch.
+    pub fn patch_surface(&self, name: &str) -> std::io::Result<PatchSurface> {
+        let range = self.boundary.data.face_range(name).ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("Patch {} not found.", name),
+            )
+        })?;
+        let mut point_map = Vec::new();
+        let mut local_points: HashMap<usize, usize> = HashMap::new();
+        let faces = range
+            .clone()
+            .map(|face| {
+                self.faces.data[face]
+                    .iter()
+                    .map(|&point| {
+                        *local_points.entry(point).or_insert_with(|| {
+                            point_map.push(point);
+                            point_map.len() - 1
+                        })
+                    })
+                    .collect()
+            })
+            .collect();
+        Ok(PatchSurface {
+            name: name.to_string(),
+            points: point_map.iter().map(|&p| self.points.data[p]).collect(),
+            faces,
+            point_map,
+            face_map: (0..range.len()).collect(),
+            face_fields: IndexMap::new(),
+        })
+    }
+}
+
+impl PatchSurface {
+    /// Returns the surface with its polygons split into triangles, fanning out from their first
+    /// point. The face fields are copied to the triangles.
+    pub fn triangulate(&self) -> PatchSurface {
+        let mut faces = Vec::new();
+        let mut parents = Vec::new();
+        for (i, face) in self.faces.iter().enumerate() {
+            for j in 1..face.len().saturating_sub(1) {
+                faces.push(vec![face[0], face[j], face[j + 1]]);
+                parents.push(i);
+            }
+        }
+        PatchSurface {
+            name: self.name.clone(),
+            points: self.points.clone(),
+            faces,
+            point_map: self.point_map.clone(),
+            face_map: parents.iter().map(|&i| self.face_map[i]).collect(),
+            face_fields: self
+                .face_fields
+                .iter()
+                .map(|(name, values)| (name.clone(), values.subset(&parents)))
+                .collect(),
+        }
+    }
+
+    /// Attaches the values of a field on the faces of the patch, given in the order of the patch.
+    pub fn add_field(&mut self, name: &str, values: &FoamField) -> std::io::Result<()> {
+        let n_patch_faces = self.face_map.iter().max().map_or(0, |&face| face + 1);
+        if values.n_values().is_some_and(|n| n != n_patch_faces) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!(
+                    "The field {} has {} values, but the patch {} has {} faces.",
+                    name,
+                    values.n_values().unwrap_or_default(),
+                    self.name,
+                    n_patch_faces
+                ),
+            ));
+        }
+        self.face_fields
+            .insert(name.to_string(), values.subset(&self.face_map));
+        Ok(())
+    }
+
+    /// Attaches the "value" entry of the boundary condition of a field on this patch.
+    pub fn add_patch_field(&mut self, field: &FileContent<ResultData>) -> std::io::Result<()> {
+        let name = field.name().unwrap_or_default().to_string();
+        let values = field.data.patch_value(&self.name).ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!(
+                    "The field {} has no values on the patch {}.",
+                    name, self.name
+                ),
+            )
+        })?;
+        self.add_field(&name, values)
+    }
+
+    /// Writes the surface as an ASCII or binary STL file. Polygons are triangulated, and the
+    /// fields are not written.
+    pub fn write_stl(&self, path: &std::path::Path, binary: bool) -> std::io::Result<()> {
+        let triangles = self.triangulate();
+        if binary {
+            std::fs::write(path, triangles.binary_stl())
+        } else {
+            std::fs::write(path, triangles.ascii_stl())
+        }
+    }
+
+    /// Writes the surface as a Wavefront OBJ file. The fields are not written.
+    pub fn write_obj(&self, path: &std::path::Path) -> std::io::Result<()> {
+        std::fs::write(path, self.obj())
+    }
+
+    /// Writes the surface as a legacy VTK file of polygons, with the face fields as cell data.
+    pub fn write_vtk(&self, path: &std::path::Path) -> std::io::Result<()> {
+        let vtk = vtk_polydata(
+            &self.name,
+            &self.points,
+            &self.faces,
+            &self.face_fields,
+            &IndexMap::new(),
+        );
+        std::fs::write(path, vtk)
+    }
+
+    /// Returns the unit normal of each triangle of a triangulated surface.
+    fn triangle_normals(&self) -> impl Iterator<Item = (Point, [&Point; 3])> {
+        self.faces.iter().map(|face| {
+            let [a, b, c] = [0, 1, 2].map(|i| &self.points[face[i]]);
+            let normal = cross(&sub(b, a), &sub(c, a));
+            let length = mag(&normal);
+            let normal = if length > f64::MIN_POSITIVE {
+                scale(&normal, 1.0 / length)
+            } else {
+                normal
+            };
+            (normal, [a, b, c])
+        })
+    }
+
+    fn ascii_stl(&self) -> String {
+        let mut stl = format!("solid {}\n", self.name);
+        for (normal, vertices) in self.triangle_normals() {
+            stl.push_str(&format!(
+                "  facet normal {} {} {}\n    outer loop\n",
+                normal[0], normal[1], normal[2]
+            ));
+            for vertex in vertices {
+                stl.push_str(&format!(
+                    "      vertex {} {} {}\n",
+                    vertex[0], vertex[1], vertex[2]
+                ));
+            }
+            stl.push_str("    endloop\n  endfacet\n");
+        }
+        stl.push_str(&format!("endsolid {}\n", self.name));
+        stl
+    }
+
+    fn binary_stl(&self) -> Vec<u8> {
+        let mut header = format!("polymesh_rw {}", self.name).into_bytes();
+        header.resize(80, b' ');
+        let mut stl = header;
+        stl.extend((self.faces.len() as u32).to_le_bytes());
+        for (normal, vertices) in self.triangle_normals() {
+            for vector in std::iter::once(&normal).chain(vertices) {
+                for component in vector {
+                    stl.extend((*component as f32).to_le_bytes());
+                }
+            }
+            // the attribute byte count
+            stl.extend(0u16.to_le_bytes());
+        }
+        stl
+    }
+
+    fn obj(&self) -> String {
+        let mut obj = format!("o {}\n", self.name);
+        for point in &self.points {
+            obj.push_str(&format!("v {} {} {}\n", point[0], point[1], point[2]));
+        }
+        for face in &self.faces {
+            // the points are counted from 1
+            let indices: Vec<String> = face.iter().map(|i| (i + 1).to_string()).collect();
+            obj.push_str(&format!("f {}\n", indices.join(" ")));
+        }
+        obj
+    }
+}
+
+/// Returns a legacy VTK file of polygons, with cell data and point data given by field name.
+pub(crate) fn vtk_polydata(
+    name: &str,
+    points: &[Point],
+    faces: &[Vec<usize>],
+    face_fields: &IndexMap<String, FoamField>,
+    point_fields: &IndexMap<String, FoamField>,
+) -> String {
+    let mut vtk = format!(
+        "# vtk DataFile Version 2.0\n{}\nASCII\nDATASET POLYDATA\nPOINTS {} double\n",
+        name,
+        points.len()
+    );
+    for point in points {
+        vtk.push_str(&format!("{} {} {}\n", point[0], point[1], point[2]));
+    }
+    let size: usize = faces.iter().map(|face| face.len() + 1).sum();
+    vtk.push_str(&format!("POLYGONS {} {}\n", faces.len(), size));
+    for face in faces {
+        let indices: Vec<String> = face.iter().map(|i| i.to_string()).collect();
+        vtk.push_str(&format!("{} {}\n", face.len(), indices.join(" ")));
+    }
+    for (data, n, fields) in [
+        ("CELL_DATA", faces.len(), face_fields),
+        ("POINT_DATA", points.len(), point_fields),
+    ] {
+        if fields.is_empty() {
+            continue;
+        }
+        vtk.push_str(&format!(
+            "{} {}\nFIELD attributes {}\n",
+            data,
+            n,
+            fields.len()
+        ));
+        for (name, values) in fields {
+            let n_components = values.n_components();
+            vtk.push_str(&format!("{} {} {} double\n", name, n_components, n));
+            for i in 0..n {
+                let value: Vec<String> = values
+                    .value(i)
+                    .iter()
+                    .map(|&component| format_number(component))
+                    .collect();
+                vtk.push_str(&value.join(" "));
+                vtk.push('\n');
+            }
+        }
+    }
+    vtk
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_patch_surface() {
+        let path = std::path::Path::new("tests/test_cases/original/cylinder");
+        let mesh = PolyMesh::parse(&path.join("constant/polyMesh")).unwrap();
+        let mut surface = mesh.patch_surface("cylinder").unwrap();
+        let range = mesh.boundary.data.face_range("cylinder").unwrap();
+        assert_eq!(surface.faces.len(), range.len());
+        // the quads of the 2D half cylinder form a strip with two rows of points
+        assert_eq!(surface.points.len(), 2 * (range.len() + 1));
+        for (face, mesh_face) in surface.faces.iter().zip(range.clone()) {
+            let mesh_points: Vec<usize> = face.iter().map(|&p| surface.point_map[p]).collect();
+            assert_eq!(mesh_points, mesh.faces.data[mesh_face]);
+        }
+        assert!(mesh.patch_surface("wall").is_err());
+
+        let mut u = FileContent::<ResultData>::parse_file(&path.join("0/U")).unwrap();
+        assert!(surface.add_patch_field(&u).is_err());
+        let mut inlet = mesh.patch_surface("left").unwrap();
+        inlet.add_patch_field(&u).unwrap();
+        assert_eq!(inlet.face_fields["U"].value(0), &[1.0, 0.0, 0.0]);
+        u.data.boundary_field = None;
+        assert!(inlet.add_patch_field(&u).is_err());
+        surface
+            .add_field(
+                "p",
+                &FoamField::Scalar((0..range.len()).map(|i| i as f64).collect()),
+            )
+            .unwrap();
+        assert!(surface
+            .add_field("short", &FoamField::Scalar(vec![0.0; 3]))
+            .is_err());
+
+        let triangles = surface.triangulate();
+        assert_eq!(triangles.faces.len(), 2 * range.len());
+        assert_eq!(triangles.face_fields["p"].value(1), &[0.0]);
+        assert_eq!(triangles.face_fields["p"].value(2), &[1.0]);
+        let stl = triangles.binary_stl();
+        assert_eq!(stl.len(), 84 + 50 * triangles.faces.len());
+        let ascii = triangles.ascii_stl();
+        assert_eq!(ascii.matches("facet normal").count(), triangles.faces.len());
+        assert!(ascii.starts_with("solid cylinder\n") && ascii.ends_with("endsolid cylinder\n"));
+        let obj = surface.obj();
+        assert_eq!(
+            obj.lines().filter(|l| l.starts_with("v ")).count(),
+            surface.points.len()
+        );
+        assert_eq!(
+            obj.lines().filter(|l| l.starts_with("f ")).count(),
+            surface.faces.len()
+        );
+        let vtk = vtk_polydata(
+            &surface.name,
+            &surface.points,
+            &surface.faces,
+            &surface.face_fields,
+            &IndexMap::new(),
+        );
+        assert!(vtk.contains(&format!("POLYGONS {} {}\n", range.len(), 5 * range.len())));
+        assert!(vtk.contains(&format!(
+            "CELL_DATA {}\nFIELD attributes 1\np 1 ",
+            range.len()
+        )));
+    }
+}
@@ -1,4 +1,5 @@
-use super::{FileContent, ResultData, UniformData};
+use super::{FileContent, PointData, PolyMesh, ResultData, UniformData};
+use crate::base::{data_files, find_file, parallel, Compression};
 use std::collections::HashMap;
 
 /// The structure that holds the full content of a time directory, which is where simulation results are stored.
@@ -8,47 +9,28 @@ pub struct TimeDir {
     // Keys: variable names.
     pub field_values: HashMap<String, FileContent<ResultData>>,
     pub uniform: Option<HashMap<String, FileContent<UniformData>>>,
+    /// The mesh written by dynamic mesh cases in "<time>/polyMesh", if any.
+    pub mesh: Option<MeshUpdate>,
+}
+
+/// The mesh of a time directory of a dynamic mesh case.
+/// See `Case::mesh_at` to find the mesh that is valid at a given time.
+#[derive(Debug, PartialEq, Clone)]
+pub enum MeshUpdate {
+    /// The points moved, and only "<time>/polyMesh/points" was written.
+    Points(FileContent<PointData>),
+    /// The topology changed, and a complete mesh was written.
+    Mesh(Box<PolyMesh>),
 }
 
 impl TimeDir {
     pub fn parse(path: &std::path::Path) -> std::io::Result<TimeDir> {
-        let Ok(time) = path.file_name().unwrap().to_str().unwrap().parse::<f64>() else {
-            return Err(std::io::Error::new(
-                std::io::ErrorKind::InvalidData,
-                "Time directory name is not a valid number.",
-            ));
-        };
-        let mut field_values = HashMap::new();
-        for entry in std::fs::read_dir(path)? {
-            let entry = entry?;
-            let path = entry.path();
-            if path.is_dir() {
-                continue;
-            }
-            let name = path
-                .file_name()
-                .expect("Unable to extract file name while parsing time directory.")
-                .to_str()
-                .expect("File name in time directory is not valid unicode.")
-                .to_string();
-            let result_data = FileContent::<ResultData>::parse_file(&path)?;
-            field_values.insert(name, result_data);
-        }
+        let time = parse_time(path)?;
+        let field_values = parse_fields(path)?;
         // check if there is a uniform directory
         let uniform = if path.join("uniform").is_dir() {
             let mut uniform = HashMap::new();
-            for entry in std::fs::read_dir(path.join("uniform"))? {
-                let entry = entry?;
-                let path = entry.path();
-                if path.is_dir() {
-                    continue;
-                }
-                let name = path
-                    .file_name()
-                    .expect("Unable to extract file name while parsing uniform directory.")
-                    .to_str()
-                    .expect("File name in uniform directory is not valid unicode.")
-                    .to_string();
+            for (name, path) in data_files(&path.join("uniform"))? {
                 let uniform_data = FileContent::<UniformData>::parse_file(&path)?;
                 uniform.insert(name, uniform_data);
             }
@@ -57,23 +39,97 @@ impl TimeDir {
             None
         };
 
+        let mesh = parse_mesh_update(path)?;
+
         Ok(TimeDir {
             time,
             field_values,
             uniform,
+            mesh,
+        })
+    }
+
+    /// Parses the fields of a region of a multi-region case, which are stored in "<time>/<region>/".
+    /// path: the path to the time directory.
+    pub fn parse_region(path: &std::path::Path, region: &str) -> std::io::Result<TimeDir> {
+        let time = parse_time(path)?;
+        let field_values = parse_fields(&path.join(region))?;
+        let mesh = parse_mesh_update(&path.join(region))?;
+        Ok(TimeDir {
+            time,
+            field_values,
+            uniform: None,
+            mesh,
         })
     }
 
     /// path: the path to the case directory.
     pub fn write(&self, path: &std::path::Path) -> std::io::Result<()> {
-        for result in self.field_values.values() {
-            result.write_file(path)?;
-        }
+        self.write_compressed(path, Compression::Uncompressed)
+    }
+
+    /// Writes the time directory with the given compression.
+    /// path: the path to the case directory.
+    pub fn write_compressed(
+        &self,
+        path: &std::path::Path,
+        compression: Compression,
+    ) -> std::io::Result<()> {
+        let fields: Vec<&FileContent<ResultData>> = self.field_values.values().collect();
+        parallel::map(&fields, |field| {
+            field.write_file_compressed(path, compression)
+        })
+        .into_iter()
+        .collect::<std::io::Result<()>>()?;
         if let Some(uniform) = &self.uniform {
             for uniform in uniform.values() {
-                uniform.write_file(path)?;
+                uniform.write_file_compressed(path, compression)?;
             }
         }
+        match &self.mesh {
+            Some(MeshUpdate::Points(points)) => points.write_file_compressed(path, compression)?,
+            Some(MeshUpdate::Mesh(mesh)) => mesh.write_compressed(path, compression)?,
+            None => {}
+        }
         Ok(())
     }
 }
+
+/// Reads the time from the name of the time directory.
+fn parse_time(path: &std::path::Path) -> std::io::Result<f64> {
+    match path.file_name().unwrap().to_str().unwrap().parse::<f64>() {
+        Ok(time) => Ok(time),
+        Err(_) => Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            "Time directory name is not a valid number.",
+        )),
+    }
+}
+
+/// Parses the "polyMesh" directory of a time directory, if present.
+/// A complete mesh is only expected if the faces were written.
+fn parse_mesh_update(path: &std::path::Path) -> std::io::Result<Option<MeshUpdate>> {
+    let mesh_path = path.join("polyMesh");
+    if find_file(&mesh_path.join("faces")).is_file() {
+        Ok(Some(MeshUpdate::Mesh(Box::new(PolyMesh::parse(
+            &mesh_path,
+        )?))))
+    } else if find_file(&mesh_path.join("points")).is_file() {
+        let points = FileContent::<PointData>::parse_file(&mesh_path.join("points"))?;
+        Ok(Some(MeshUpdate::Points(points)))
+    } else {
+        Ok(None)
+    }
+}
+
+/// Parses all field files of the given directory, which may be gzip-compressed.
+/// Subdirectories are skipped. The files are parsed concurrently if the "rayon" feature is enabled.
+fn parse_fields(
+    path: &std::path::Path,
+) -> std::io::Result<HashMap<String, FileContent<ResultData>>> {
+    parallel::map(&data_files(path)?, |(name, path)| {
+        Ok((name.clone(), FileContent::<ResultData>::parse_file(path)?))
+    })
+    .into_iter()
+    .collect()
+}
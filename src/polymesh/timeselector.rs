@@ -0,0 +1,150 @@
+/// Two times are considered equal if they differ by less than this value, as in OpenFOAM.
+const TIME_TOLERANCE: f64 = 1e-15;
+
+/// Selects time directories, like the "-time" and "-latestTime" options of OpenFOAM's utilities.
+/// Selections are made from a list of times sorted by value.
+#[derive(Debug, PartialEq, Clone)]
+pub enum TimeSelector {
+    /// All times.
+    All,
+    /// The largest time.
+    Latest,
+    /// The smallest time that is not zero, e.g. the first time written by a solver.
+    FirstNonZero,
+    /// All times within the given bounds, including the bounds.
+    /// Unbounded ranges are given with infinite bounds.
+    Range(f64, f64),
+    /// For each of the given times, the time nearest to it.
+    List(Vec<f64>),
+    /// The time nearest to the given time.
+    Nearest(f64),
+    /// All times selected by any of the selectors.
+    Any(Vec<TimeSelector>),
+}
+
+impl TimeSelector {
+    /// Returns the indices of the selected times, in increasing order.
+    /// The times need to be sorted by value.
+    pub fn select(&self, times: &[f64]) -> Vec<usize> {
+        let mut selected = match self {
+            TimeSelector::All => (0..times.len()).collect(),
+            TimeSelector::Latest => times.len().checked_sub(1).into_iter().collect(),
+            TimeSelector::FirstNonZero => times
+                .iter()
+                .position(|time| time.abs() > TIME_TOLERANCE)
+                .into_iter()
+                .collect(),
+            TimeSelector::Range(lower, upper) => (0..times.len())
+                .filter(|&i| {
+                    times[i] >= lower - TIME_TOLERANCE && times[i] <= upper + TIME_TOLERANCE
+                })
+                .collect(),
+            TimeSelector::List(values) => values
+                .iter()
+                .filter_map(|&value| nearest(times, value))
+                .collect(),
+            TimeSelector::Nearest(value) => nearest(times, *value).into_iter().collect(),
+            TimeSelector::Any(selectors) => selectors
+                .iter()
+                .flat_map(|selector| selector.select(times))
+                .collect::<Vec<usize>>(),
+        };
+        selected.sort_unstable();
+        selected.dedup();
+        selected
+    }
+}
+
+/// Parses the syntax of the "-time" option of OpenFOAM: a comma-separated list of times and
+/// ranges, e.g. "0.1,0.5:1,2:". Single times select the nearest time, and ranges can be open on
+/// either side. "latestTime" is also accepted and selects the latest time.
+impl std::str::FromStr for TimeSelector {
+    type Err = std::io::Error;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let invalid = |entry: &str| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("Invalid time selection \"{}\".", entry),
+            )
+        };
+        let bound = |value: &str, unbounded: f64| match value.trim() {
+            "" => Ok(unbounded),
+            value => value.parse::<f64>().map_err(|_| invalid(value)),
+        };
+        let mut selectors = Vec::new();
+        let mut values = Vec::new();
+        for entry in input.split(',').map(str::trim) {
+            match entry {
+                "latestTime" => selectors.push(TimeSelector::Latest),
+                _ => match entry.split_once(':') {
+                    Some((lower, upper)) => selectors.push(TimeSelector::Range(
+                        bound(lower, f64::NEG_INFINITY)?,
+                        bound(upper, f64::INFINITY)?,
+                    )),
+                    None => values.push(entry.parse::<f64>().map_err(|_| invalid(entry))?),
+                },
+            }
+        }
+        match values.len() {
+            0 => {}
+            1 => selectors.push(TimeSelector::Nearest(values[0])),
+            _ => selectors.push(TimeSelector::List(values)),
+        }
+        if selectors.len() == 1 {
+            Ok(selectors.pop().unwrap())
+        } else {
+            Ok(TimeSelector::Any(selectors))
+        }
+    }
+}
+
+/// Returns the index of the time nearest to the given value. Ties select the earlier time.
+fn nearest(times: &[f64], value: f64) -> Option<usize> {
+    let mut nearest = None;
+    let mut distance = f64::INFINITY;
+    for (i, time) in times.iter().enumerate() {
+        if (time - value).abs() < distance - TIME_TOLERANCE {
+            distance = (time - value).abs();
+            nearest = Some(i);
+        }
+    }
+    nearest
+}
+
+/// Returns true if two times are equal within the tolerance of OpenFOAM.
+pub(crate) fn same_time(a: f64, b: f64) -> bool {
+    (a - b).abs() < TIME_TOLERANCE
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_select_times() {
+        let times = [0.0, 0.1, 0.2, 0.5, 1.0];
+        assert_eq!(TimeSelector::All.select(&times), vec![0, 1, 2, 3, 4]);
+        assert_eq!(TimeSelector::Latest.select(&times), vec![4]);
+        assert_eq!(TimeSelector::FirstNonZero.select(&times), vec![1]);
+        assert_eq!(TimeSelector::Range(0.1, 0.5).select(&times), vec![1, 2, 3]);
+        assert_eq!(TimeSelector::Nearest(0.4).select(&times), vec![3]);
+        assert_eq!(
+            TimeSelector::List(vec![1.0, 0.09, 0.11]).select(&times),
+            vec![1, 4]
+        );
+        assert!(TimeSelector::Latest.select(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_parse_time_selector() {
+        let times = [0.0, 0.1, 0.2, 0.5, 1.0];
+        let select = |input: &str| input.parse::<TimeSelector>().unwrap().select(&times);
+        assert_eq!(select("0.1:0.2"), vec![1, 2]);
+        assert_eq!(select(":0.1"), vec![0, 1]);
+        assert_eq!(select("0.5:"), vec![3, 4]);
+        assert_eq!(select("0.3"), vec![2]);
+        assert_eq!(select("0, 0.5:0.6, latestTime"), vec![0, 3, 4]);
+        assert!("0.1:a".parse::<TimeSelector>().is_err());
+    }
+}
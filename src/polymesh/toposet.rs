@@ -0,0 +1,741 @@
+use super::{CellZone, FaceZone, FileContent, Point, PointZone, PolyMesh, Set, Sets, ZoneData};
+use crate::base::vector::*;
+use crate::base::{FoamField, FoamFile, FoamValue};
+use indexmap::IndexMap;
+use std::collections::HashSet;
+use std::path;
+
+/// The mesh entities whose labels are held by a set or a zone.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SetType {
+    Cell,
+    Face,
+    Point,
+}
+
+impl SetType {
+    /// Returns the class of the set files, e.g. "cellSet".
+    pub fn class(&self) -> &'static str {
+        match self {
+            SetType::Cell => "cellSet",
+            SetType::Face => "faceSet",
+            SetType::Point => "pointSet",
+        }
+    }
+
+    pub fn from_class(class: &str) -> Option<Self> {
+        match class {
+            "cellSet" => Some(SetType::Cell),
+            "faceSet" => Some(SetType::Face),
+            "pointSet" => Some(SetType::Point),
+            _ => None,
+        }
+    }
+}
+
+/// How the entities of one type are selected from a set of another type by
+/// `TopoSetSource::Connected`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SetConnection {
+    /// The entities connected to any entity of the set, e.g. all faces of the cells for
+    /// cellToFace, or all points of the faces for faceToPoint.
+    Any,
+    /// The entities whose connected entities all belong to the set, e.g. the faces whose owner
+    /// and neighbour cells are in the set.
+    All,
+}
+
+/// The sources that select entities, like the sources of OpenFOAM's topoSet. Cells are located by
+/// their centre, faces by their face centre and points by their position.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TopoSetSource {
+    /// The given labels.
+    Labels(Vec<usize>),
+    /// The entities inside the box between `min` and `max`, like boxToCell.
+    Box { min: Point, max: Point },
+    /// The entities inside the cylinder with the axis from `p1` to `p2`, like cylinderToCell.
+    Cylinder { p1: Point, p2: Point, radius: f64 },
+    /// The entities inside the sphere, like sphereToCell.
+    Sphere { centre: Point, radius: f64 },
+    /// The entities whose value of the field is within `min..=max`, like fieldToCell. The field
+    /// holds one value per entity; the magnitude of vectors is used.
+    FieldRange {
+        values: FoamField,
+        min: f64,
+        max: f64,
+    },
+    /// The faces of the patch, their owner cells or their points, like patchToFace.
+    Patch(String),
+    /// The entities of the zone of the same type, like zoneToCell.
+    Zone(String),
+    /// The entities of the set of the same type, like setToCell or setToCellZone.
+    Set(String),
+    /// The entities connected to a set of another type, like cellToFace, faceToPoint or
+    /// pointToCell. The type of the set is taken from its class.
+    Connected {
+        set: String,
+        connection: SetConnection,
+    },
+}
+
+/// The actions applied to a set or a zone, like the actions of topoSet.
+/// Sets or zones that do not exist yet are treated as empty.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SetAction {
+    /// Replaces the content with the selected entities.
+    New(TopoSetSource),
+    /// Adds the selected entities.
+    Add(TopoSetSource),
+    /// Removes the selected entities.
+    Subtract(TopoSetSource),
+    /// Keeps only the selected entities, like the "subset" action.
+    Intersect(TopoSetSource),
+    /// Selects all entities that are not selected, and none of the selected ones.
+    Invert,
+    /// Removes all entities.
+    Clear,
+    /// Removes the set or zone itself. Files that were already written are not deleted.
+    Remove,
+}
+
+impl PolyMesh {
+    /// Returns the sorted labels of the entities selected by a source.
+    pub fn select(&self, set_type: SetType, source: &TopoSetSource) -> std::io::Result<Vec<usize>> {
+        let n = self.n_entities(set_type);
+        let inside = |test: &dyn Fn(&Point) -> bool| -> Vec<usize> {
+            self.positions(set_type)
+                .iter()
+                .enumerate()
+                .filter(|(_, position)| test(position))
+                .map(|(i, _)| i)
+                .collect()
+        };
+        let mut labels = match source {
+            TopoSetSource::Labels(labels) => labels.clone(),
+            TopoSetSource::Box { min, max } => {
+                inside(&|p: &Point| (0..3).all(|i| min[i] <= p[i] && p[i] <= max[i]))
+            }
+            TopoSetSource::Cylinder { p1, p2, radius } => {
+                let axis = sub(p2, p1);
+                let length = dot(&axis, &axis);
+                inside(&|p: &Point| {
+                    let relative = sub(p, p1);
+                    let along = dot(&relative, &axis);
+                    let radial = sub(&relative, &scale(&axis, along / length));
+                    (0.0..=length).contains(&along) && mag(&radial) <= *radius
+                })
+            }
+            TopoSetSource::Sphere { centre, radius } => {
+                inside(&|p: &Point| mag(&sub(p, centre)) <= *radius)
+            }
+            TopoSetSource::FieldRange { values, min, max } => {
+                if let Some(n_values) = values.n_values().filter(|&n_values| n_values != n) {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::InvalidData,
+                        format!(
+                            "The field has {} values, but there are {} entities.",
+                            n_values, n
+                        ),
+                    ));
+                }
+                (0..n)
+                    .filter(|&i| {
+                        let value = match values.value(i) {
+                            [value] => *value,
+                            vector => vector.iter().map(|c| c * c).sum::<f64>().sqrt(),
+                        };
+                        (*min..=*max).contains(&value)
+                    })
+                    .collect()
+            }
+            TopoSetSource::Patch(name) => {
+                let range = self
+                    .boundary
+                    .data
+                    .face_range(name)
+                    .ok_or_else(|| not_found(format!("Patch {} not found.", name)))?;
+                match set_type {
+                    SetType::Face => range.collect(),
+                    SetType::Cell => range.map(|face| self.owner.data[face]).collect(),
+                    SetType::Point => range
+                        .flat_map(|face| self.faces.data[face].iter().copied())
+                        .collect(),
+                }
+            }
+            TopoSetSource::Zone(name) => self
+                .zone_labels(set_type, name)
+                .ok_or_else(|| not_found(format!("{} {} not found.", zone_kind(set_type), name)))?,
+            TopoSetSource::Set(name) => {
+                let (source_type, labels) = self.set_labels(name)?;
+                if source_type != set_type {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::InvalidInput,
+                        format!(
+                            "The set {} is a {}, not a {}.",
+                            name,
+                            source_type.class(),
+                            set_type.class()
+                        ),
+                    ));
+                }
+                labels.to_vec()
+            }
+            TopoSetSource::Connected { set, connection } => {
+                let (source_type, labels) = self.set_labels(set)?;
+                if source_type == set_type {
+                    labels.to_vec()
+                } else {
+                    let mut selected = vec![false; self.n_entities(source_type)];
+                    for &label in labels {
+                        if let Some(flag) = selected.get_mut(label) {
+                            *flag = true;
+                        }
+                    }
+                    self.connected(set_type, source_type)
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, sources)| match connection {
+                            SetConnection::Any => sources.iter().any(|&s| selected[s]),
+                            SetConnection::All => {
+                                !sources.is_empty() && sources.iter().all(|&s| selected[s])
+                            }
+                        })
+                        .map(|(i, _)| i)
+                        .collect()
+                }
+            }
+        };
+        labels.sort_unstable();
+        labels.dedup();
+        // labels given explicitly or read from files may not fit the mesh
+        if let Some(label) = labels.last().filter(|&&label| label >= n) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("Label {} is out of range, there are {} entities.", label, n),
+            ));
+        }
+        Ok(labels)
+    }
+
+    /// Applies an action to the set `name` in `sets`, which is created if it does not exist yet.
+    /// New sets are located in the "sets" directory of the mesh and written with the mesh.
+    pub fn topo_set(
+        &mut self,
+        name: &str,
+        set_type: SetType,
+        action: &SetAction,
+    ) -> std::io::Result<()> {
+        let current = match self.sets.as_ref().and_then(|sets| sets.get(name)) {
+            Some(set) if action != &SetAction::Remove && !matches!(action, SetAction::New(_)) => {
+                let class = set.meta.class().unwrap_or_default();
+                if SetType::from_class(class) != Some(set_type) {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::InvalidInput,
+                        format!(
+                            "The set {} is a {}, not a {}.",
+                            name,
+                            class,
+                            set_type.class()
+                        ),
+                    ));
+                }
+                set.data.labels.clone()
+            }
+            _ => Vec::new(),
+        };
+        let Some(labels) = self.apply(set_type, current, action)? else {
+            if let Some(sets) = &mut self.sets {
+                sets.remove(name);
+            }
+            return Ok(());
+        };
+        let directory = self.directory();
+        let sets = self.sets.get_or_insert_with(|| Sets(Default::default()));
+        let set = sets.entry(name.to_string()).or_insert_with(|| {
+            let mut set = FileContent {
+                location: None,
+                meta: FoamFile::new(set_type.class(), "", name),
+                data: Set {
+                    name: name.to_string(),
+                    n: 0,
+                    labels: Vec::new(),
+                },
+            };
+            set.set_location(&directory.join("sets").join(name));
+            set
+        });
+        // a new set may replace a set of another type
+        set.meta.insert(
+            "class".to_string(),
+            FoamValue::String(set_type.class().to_string()),
+        );
+        set.data.n = labels.len();
+        set.data.labels = labels;
+        Ok(())
+    }
+
+    /// Applies an action to the zone `name` of the cell, face or point zones, which are created if
+    /// they do not exist yet. Faces that stay in a face zone keep their flip, the faces added to
+    /// it and the faces of new face zones are not flipped.
+    pub fn topo_zone(
+        &mut self,
+        name: &str,
+        set_type: SetType,
+        action: &SetAction,
+    ) -> std::io::Result<()> {
+        let current = match action {
+            SetAction::New(_) => Vec::new(),
+            _ => self.zone_labels(set_type, name).unwrap_or_default(),
+        };
+        let labels = self.apply(set_type, current, action)?;
+        let directory = self.directory();
+        match set_type {
+            SetType::Cell => update_zone(&mut self.cellzones, &directory, name, labels, |cells| {
+                CellZone {
+                    name: name.to_string(),
+                    cells,
+                }
+            }),
+            SetType::Face => {
+                // the faces that stay in the zone keep their orientation
+                let flipped: HashSet<usize> = match action {
+                    SetAction::New(_) => HashSet::new(),
+                    _ => self
+                        .facezones
+                        .as_ref()
+                        .and_then(|zones| zones.data.zones.get(name))
+                        .map(|zone| {
+                            zone.faces
+                                .iter()
+                                .zip(zone.flipmap.iter())
+                                .filter_map(|(&face, &flip)| flip.then_some(face))
+                                .collect()
+                        })
+                        .unwrap_or_default(),
+                };
+                update_zone(&mut self.facezones, &directory, name, labels, |faces| {
+                    FaceZone {
+                        name: name.to_string(),
+                        flipmap: faces.iter().map(|face| flipped.contains(face)).collect(),
+                        faces,
+                    }
+                })
+            }
+            SetType::Point => {
+                update_zone(&mut self.pointzones, &directory, name, labels, |points| {
+                    PointZone {
+                        name: name.to_string(),
+                        points,
+                    }
+                })
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the new labels of a set or zone after an action, or None if it is removed.
+    fn apply(
+        &self,
+        set_type: SetType,
+        current: Vec<usize>,
+        action: &SetAction,
+    ) -> std::io::Result<Option<Vec<usize>>> {
+        let n = self.n_entities(set_type);
+        let mut selected = vec![false; n];
+        for &label in &current {
+            if let Some(flag) = selected.get_mut(label) {
+                *flag = true;
+            }
+        }
+        match action {
+            SetAction::New(source) => return self.select(set_type, source).map(Some),
+            SetAction::Add(source) => {
+                for label in self.select(set_type, source)? {
+                    selected[label] = true;
+                }
+            }
+            SetAction::Subtract(source) => {
+                for label in self.select(set_type, source)? {
+                    selected[label] = false;
+                }
+            }
+            SetAction::Intersect(source) => {
+                let mut kept = vec![false; n];
+                for label in self.select(set_type, source)? {
+                    kept[label] = selected[label];
+                }
+                selected = kept;
+            }
+            SetAction::Invert => selected.iter_mut().for_each(|flag| *flag = !*flag),
+            SetAction::Clear => selected.fill(false),
+            SetAction::Remove => return Ok(None),
+        }
+        Ok(Some((0..n).filter(|&i| selected[i]).collect()))
+    }
+
+    fn n_entities(&self, set_type: SetType) -> usize {
+        match set_type {
+            SetType::Cell => self.n_cells(),
+            SetType::Face => self.n_faces(),
+            SetType::Point => self.n_points(),
+        }
+    }
+
+    fn positions(&self, set_type: SetType) -> Vec<Point> {
+        match set_type {
+            SetType::Cell => self.cell_centres(),
+            SetType::Face => self.face_centres(),
+            SetType::Point => self.points.data.to_vec(),
+        }
+    }
+
+    /// Returns, for each entity of type `target`, the connected entities of type `source`.
+    fn connected(&self, target: SetType, source: SetType) -> Vec<Vec<usize>> {
+        let face_cells = || {
+            (0..self.n_faces())
+                .map(|face| {
+                    let mut cells = vec![self.owner.data[face]];
+                    cells.extend(self.neighbour.data.get(face));
+                    cells
+                })
+                .collect()
+        };
+        let face_points = || self.faces.data.iter().map(|face| face.to_vec()).collect();
+        match (target, source) {
+            (SetType::Face, SetType::Cell) => face_cells(),
+            (SetType::Face, SetType::Point) => face_points(),
+            (SetType::Cell, SetType::Face) => self.cell_faces(),
+            (SetType::Cell, SetType::Point) => self.cell_points(),
+            (SetType::Point, SetType::Cell) => self.point_cells(),
+            (SetType::Point, SetType::Face) => invert(&face_points(), self.n_points()),
+            _ => (0..self.n_entities(target)).map(|i| vec![i]).collect(),
+        }
+    }
+
+    /// Returns the type and the labels of a set.
+    fn set_labels(&self, name: &str) -> std::io::Result<(SetType, &[usize])> {
+        let set = self
+            .sets
+            .as_ref()
+            .and_then(|sets| sets.get(name))
+            .ok_or_else(|| not_found(format!("Set {} not found.", name)))?;
+        let class = set.meta.class().unwrap_or_default();
+        let set_type = SetType::from_class(class).ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("The set {} has the unknown class {}.", name, class),
+            )
+        })?;
+        Ok((set_type, &set.data.labels))
+    }
+
+    /// Returns the labels of a zone, or None if it does not exist.
+    fn zone_labels(&self, set_type: SetType, name: &str) -> Option<Vec<usize>> {
+        match set_type {
+            SetType::Cell => self
+                .cellzones
+                .as_ref()
+                .and_then(|zones| Some(zones.data.zones.get(name)?.cells.clone())),
+            SetType::Face => self
+                .facezones
+                .as_ref()
+                .and_then(|zones| Some(zones.data.zones.get(name)?.faces.clone())),
+            SetType::Point => self
+                .pointzones
+                .as_ref()
+                .and_then(|zones| Some(zones.data.zones.get(name)?.points.clone())),
+        }
+    }
+
+    /// Returns the directory of the mesh relative to the case, taken from the boundary file.
+    fn directory(&self) -> path::PathBuf {
+        self.boundary
+            .location
+            .as_ref()
+            .and_then(|location| location.parent())
+            .map_or_else(
+                || path::PathBuf::from("constant/polyMesh"),
+                path::Path::to_path_buf,
+            )
+    }
+}
+
+/// Replaces, creates or removes a zone, and creates the zone file if needed.
+fn update_zone<T: super::Zone>(
+    zones: &mut Option<FileContent<ZoneData<T>>>,
+    directory: &path::Path,
+    name: &str,
+    labels: Option<Vec<usize>>,
+    new_zone: impl FnOnce(Vec<usize>) -> T,
+) {
+    let Some(labels) = labels else {
+        if let Some(zones) = zones {
+            zones.data.zones.shift_remove(name);
+            zones.data.n = zones.data.zones.len();
+        }
+        return;
+    };
+    let file_name = T::default_file_path();
+    let file_name = file_name.file_name().unwrap_or_default();
+    let zones = zones.get_or_insert_with(|| {
+        let mut zones = FileContent {
+            location: None,
+            meta: FoamFile::new("regIOobject", "", &file_name.to_string_lossy()),
+            data: ZoneData {
+                n: 0,
+                zones: IndexMap::new(),
+            },
+        };
+        zones.set_location(&directory.join(file_name));
+        zones
+    });
+    zones.data.zones.insert(name.to_string(), new_zone(labels));
+    zones.data.n = zones.data.zones.len();
+}
+
+/// Inverts a connectivity: for each of the `n` entities, the lists that contain it.
+fn invert(lists: &[Vec<usize>], n: usize) -> Vec<Vec<usize>> {
+    let mut inverted = vec![Vec::new(); n];
+    for (i, list) in lists.iter().enumerate() {
+        for &j in list {
+            inverted[j].push(i);
+        }
+    }
+    inverted
+}
+
+fn zone_kind(set_type: SetType) -> &'static str {
+    match set_type {
+        SetType::Cell => "Cell zone",
+        SetType::Face => "Face zone",
+        SetType::Point => "Point zone",
+    }
+}
+
+fn not_found(message: String) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::NotFound, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn two_zones() -> PolyMesh {
+        let path = std::path::Path::new("tests/test_cases/original/two_zones_with_interfaces");
+        PolyMesh::parse(&path.join("constant/polyMesh")).unwrap()
+    }
+
+    #[test]
+    fn test_set_actions() {
+        let mut mesh = two_zones();
+        let centres = mesh.cell_centres();
+        let min = [0.0, -5.0, 0.0];
+        let max = [5.0, 1.0, 1.0];
+        let in_box: Vec<usize> =
+            mesh.cells_where(|c| (0..3).all(|i| min[i] <= c[i] && c[i] <= max[i]));
+        mesh.topo_set(
+            "box",
+            SetType::Cell,
+            &SetAction::New(TopoSetSource::Box { min, max }),
+        )
+        .unwrap();
+        let labels =
+            |mesh: &PolyMesh, name: &str| mesh.sets.as_ref().unwrap()[name].data.labels.clone();
+        assert_eq!(labels(&mesh, "box"), in_box);
+        assert!(!in_box.is_empty() && in_box.len() < mesh.n_cells());
+
+        // the same cells, selected by the value of a field
+        let x = FoamField::Scalar(centres.iter().map(|c| c[0]).collect());
+        let by_value = mesh
+            .select(
+                SetType::Cell,
+                &TopoSetSource::FieldRange {
+                    values: x,
+                    min: 0.0,
+                    max: 5.0,
+                },
+            )
+            .unwrap();
+        assert_eq!(by_value, mesh.cells_where(|c| c[0] <= 5.0));
+
+        mesh.topo_set("box", SetType::Cell, &SetAction::Invert)
+            .unwrap();
+        assert_eq!(labels(&mesh, "box").len(), mesh.n_cells() - in_box.len());
+        mesh.topo_set(
+            "box",
+            SetType::Cell,
+            &SetAction::Add(TopoSetSource::Set("box".to_string())),
+        )
+        .unwrap();
+        let metal = TopoSetSource::Zone("metal".to_string());
+        mesh.topo_set("box", SetType::Cell, &SetAction::Intersect(metal.clone()))
+            .unwrap();
+        let metal_cells = mesh.select(SetType::Cell, &metal).unwrap();
+        assert!(labels(&mesh, "box")
+            .iter()
+            .all(|cell| metal_cells.contains(cell)));
+        mesh.topo_set("box", SetType::Cell, &SetAction::Subtract(metal))
+            .unwrap();
+        assert!(labels(&mesh, "box").is_empty());
+        mesh.topo_set("box", SetType::Cell, &SetAction::Remove)
+            .unwrap();
+        assert!(!mesh.sets.as_ref().unwrap().contains_key("box"));
+
+        // the actions check the type of the set and the labels
+        assert!(mesh
+            .topo_set("metal", SetType::Face, &SetAction::Invert)
+            .is_err());
+        let out_of_range = TopoSetSource::Labels(vec![mesh.n_cells()]);
+        assert!(mesh.select(SetType::Cell, &out_of_range).is_err());
+        let sphere = TopoSetSource::Sphere {
+            centre: centres[0],
+            radius: 0.0,
+        };
+        assert_eq!(mesh.select(SetType::Cell, &sphere).unwrap(), vec![0]);
+    }
+
+    #[test]
+    fn test_set_conversions() {
+        let mut mesh = two_zones();
+        let metal = SetAction::New(TopoSetSource::Zone("metal".to_string()));
+        mesh.topo_set("metalCells", SetType::Cell, &metal).unwrap();
+        let connected = |connection| TopoSetSource::Connected {
+            set: "metalCells".to_string(),
+            connection,
+        };
+        let any = mesh
+            .select(SetType::Face, &connected(SetConnection::Any))
+            .unwrap();
+        let all = mesh
+            .select(SetType::Face, &connected(SetConnection::All))
+            .unwrap();
+        assert!(all.len() < any.len() && all.iter().all(|face| any.contains(face)));
+        // the faces between the zones are selected by any, but not by all
+        let cells = mesh
+            .select(SetType::Cell, &TopoSetSource::Zone("metal".to_string()))
+            .unwrap();
+        let interface = (0..mesh.n_internal_faces()).find(|&face| {
+            cells.contains(&mesh.owner.data[face]) != cells.contains(&mesh.neighbour.data[face])
+        });
+        let interface = interface.unwrap();
+        assert!(any.contains(&interface) && !all.contains(&interface));
+
+        // back from the faces of the cells to the cells
+        mesh.topo_set(
+            "metalFaces",
+            SetType::Face,
+            &SetAction::New(connected(SetConnection::Any)),
+        )
+        .unwrap();
+        let back = TopoSetSource::Connected {
+            set: "metalFaces".to_string(),
+            connection: SetConnection::All,
+        };
+        assert_eq!(mesh.select(SetType::Cell, &back).unwrap(), cells);
+        let faces = TopoSetSource::Connected {
+            set: "metalFaces".to_string(),
+            connection: SetConnection::Any,
+        };
+        let points = mesh.select(SetType::Point, &faces).unwrap();
+        let cell_points = mesh
+            .select(SetType::Point, &connected(SetConnection::Any))
+            .unwrap();
+        assert_eq!(points, cell_points);
+
+        let patch = mesh.select(SetType::Cell, &TopoSetSource::Patch("metal_in".to_string()));
+        assert!(patch.unwrap().iter().all(|cell| cells.contains(cell)));
+    }
+
+    #[test]
+    fn test_zones() {
+        let mut mesh = two_zones();
+        let cylinder = TopoSetSource::Cylinder {
+            p1: [0.0, 0.5, 0.5],
+            p2: [10.0, 0.5, 0.5],
+            radius: 0.25,
+        };
+        mesh.topo_set("core", SetType::Cell, &SetAction::New(cylinder))
+            .unwrap();
+        let from_set = SetAction::New(TopoSetSource::Set("core".to_string()));
+        mesh.topo_zone("core", SetType::Cell, &from_set).unwrap();
+        let cellzones = &mesh.cellzones.as_ref().unwrap().data;
+        assert_eq!(cellzones.n, cellzones.zones.len());
+        assert_eq!(
+            cellzones.zones["core"].cells,
+            mesh.sets.as_ref().unwrap()["core"].data.labels
+        );
+        assert!(!cellzones.zones["core"].cells.is_empty());
+
+        let patch = SetAction::New(TopoSetSource::Patch("inlet".to_string()));
+        mesh.topo_zone("inlet", SetType::Face, &patch).unwrap();
+        assert_eq!(
+            mesh.facezones.as_ref().unwrap().data.zones["inlet"].len(),
+            4
+        );
+        mesh.pointzones = None;
+        mesh.topo_zone("inlet", SetType::Point, &patch).unwrap();
+        let pointzones = mesh.pointzones.as_ref().unwrap();
+        assert_eq!(pointzones.data.n, 1);
+        assert_eq!(
+            pointzones.location,
+            Some(path::PathBuf::from("constant/polyMesh/pointZones"))
+        );
+        mesh.topo_zone("core", SetType::Cell, &SetAction::Remove)
+            .unwrap();
+        assert!(!mesh
+            .cellzones
+            .as_ref()
+            .unwrap()
+            .data
+            .zones
+            .contains_key("core"));
+
+        // the sets and zones are written and parsed with the mesh
+        let copy_path = crate::base::TempDir::new("toposet");
+        mesh.write(&copy_path).unwrap();
+        let copy = PolyMesh::parse(&copy_path.join("constant/polyMesh")).unwrap();
+        assert_eq!(
+            copy.sets.as_ref().unwrap()["core"].data,
+            mesh.sets.as_ref().unwrap()["core"].data
+        );
+        assert_eq!(copy.facezones.unwrap().data, mesh.facezones.unwrap().data);
+        assert_eq!(copy.pointzones.unwrap().data, mesh.pointzones.unwrap().data);
+    }
+
+    #[test]
+    fn test_face_zone_flipmap() {
+        let mut mesh = two_zones();
+        let inlet = SetAction::New(TopoSetSource::Patch("inlet".to_string()));
+        mesh.topo_zone("faces", SetType::Face, &inlet).unwrap();
+        let zone = |mesh: &PolyMesh| mesh.facezones.as_ref().unwrap().data.zones["faces"].clone();
+        let inlet_faces = zone(&mesh).faces;
+        if let Some(zone) = mesh
+            .facezones
+            .as_mut()
+            .and_then(|zones| zones.data.zones.get_mut("faces"))
+        {
+            zone.flipmap = vec![true, false, true, false];
+        }
+
+        let outlet = TopoSetSource::Patch("outlet".to_string());
+        mesh.topo_zone("faces", SetType::Face, &SetAction::Add(outlet.clone()))
+            .unwrap();
+        let added = zone(&mesh);
+        assert!(added.faces.len() > inlet_faces.len());
+        for (face, flip) in added.faces.iter().zip(added.flipmap.iter()) {
+            let expected = inlet_faces
+                .iter()
+                .position(|inlet| inlet == face)
+                .is_some_and(|i| i % 2 == 0);
+            assert_eq!(*flip, expected);
+        }
+
+        mesh.topo_zone("faces", SetType::Face, &SetAction::Subtract(outlet))
+            .unwrap();
+        assert_eq!(zone(&mesh).faces, inlet_faces);
+        assert_eq!(zone(&mesh).flipmap, vec![true, false, true, false]);
+
+        mesh.topo_zone("faces", SetType::Face, &inlet).unwrap();
+        assert!(zone(&mesh).flipmap.iter().all(|flip| !flip));
+    }
+}
@@ -1,4 +1,31 @@
-use polymesh_rw::Case;
+use polymesh_rw::*;
+
+/// A directory in the temporary directory of the system, which is removed when it is dropped.
+/// The process id is part of the name, so that concurrent test runs do not interfere.
+struct TempDir(std::path::PathBuf);
+
+impl TempDir {
+    fn new(name: &str) -> Self {
+        let path =
+            std::env::temp_dir().join(format!("polymesh_rw_{}_{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+        TempDir(path)
+    }
+}
+
+impl std::ops::Deref for TempDir {
+    type Target = std::path::Path;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
 
 #[test]
 fn test_consistency() -> std::io::Result<()> {
@@ -24,3 +51,275 @@ fn test_consistency() -> std::io::Result<()> {
     }
     Ok(())
 }
+
+#[test]
+fn test_subset() -> std::io::Result<()> {
+    let path = std::path::Path::new("./tests/test_cases/original/cylinder");
+    let case = Case::parse_file(path)?;
+    let cells = case.mesh()?.cells_where(|centre| centre[0] < 0.0);
+    let subset = case.subset(&cells, "oldInternalFaces")?;
+    let mesh = subset.mesh()?;
+    assert_eq!(mesh.n_cells(), cells.len());
+    for time_directory in subset.time_directories.values() {
+        for field in time_directory.field_values.values() {
+            if let Some(n) = field.data.result.n_values() {
+                assert!(n == mesh.n_cells() || n == mesh.n_internal_faces());
+            }
+            // patch values match the patch size, except for "empty" patches which have no values
+            for (name, range) in mesh.boundary.data.face_ranges() {
+                if let Some(value) = field.data.patch_value(&name) {
+                    assert!(value.n_values().is_none_or(|n| n == 0 || n == range.len()));
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn test_regions() -> std::io::Result<()> {
+    let path = std::path::Path::new("./tests/test_cases/original/two_zones_with_interfaces");
+    let case = Case::parse_file(path)?;
+    let split = case.mesh()?.split_regions_by_cell_zones()?;
+    let properties = RegionPropertiesData(
+        [
+            ("fluid".to_string(), vec!["channel".to_string()]),
+            ("solid".to_string(), vec!["metal".to_string()]),
+        ]
+        .into_iter()
+        .collect(),
+    );
+    // a temperature field of the whole mesh, split into a field of each region in "123/<region>/"
+    let mut inlet = FoamStructure(Default::default());
+    inlet.insert(
+        "type".to_string(),
+        FoamValue::String("fixedValue".to_string()),
+    );
+    inlet.insert(
+        "value".to_string(),
+        FoamValue::Field(FoamField::UniformScalar(350.0)),
+    );
+    let mut patches = FoamStructure(Default::default());
+    patches.insert("inlet".to_string(), FoamValue::Structure(inlet));
+    let mut boundary_field = FoamStructure(Default::default());
+    boundary_field.insert("boundaryField".to_string(), FoamValue::Structure(patches));
+    let mut temperature = FileContent {
+        location: None,
+        meta: FoamFile::new("volScalarField", "123", "T"),
+        data: ResultData {
+            dimensions: Dimensions::new([0, 0, 0, 1, 0, 0, 0]),
+            result: FoamField::UniformScalar(300.0),
+            boundary_field: Some(boundary_field),
+        },
+    };
+    temperature.set_location(std::path::Path::new("123/T"));
+    let time_directory = TimeDir {
+        time: 123.0,
+        field_values: [("T".to_string(), temperature)].into_iter().collect(),
+        uniform: None,
+        mesh: None,
+    };
+    let mut region_time_directories = split.map_time_dir(&time_directory)?;
+    let regions = split
+        .regions
+        .iter()
+        .map(|(name, subset)| {
+            let time_directory = region_time_directories.shift_remove(name).unwrap();
+            let region = RegionData {
+                polymesh: subset.mesh.clone(),
+                time_directories: [("123".to_string(), time_directory)].into_iter().collect(),
+            };
+            (name.clone(), region)
+        })
+        .collect();
+    let region_case = Case {
+        polymesh: None,
+        time_directories: case.time_directories.clone(),
+        region_properties: Some(FileContent {
+            location: None,
+            meta: FoamFile::new("dictionary", "constant", "regionProperties"),
+            data: properties,
+        }),
+        regions,
+    };
+    let copy_path = TempDir::new("regions");
+    region_case.write_file(&copy_path)?;
+    let copy = Case::parse_file(&copy_path)?;
+    assert!(copy.polymesh.is_none());
+    assert_eq!(
+        copy.regions.keys().collect::<Vec<_>>(),
+        vec!["channel", "metal"]
+    );
+    assert_eq!(copy.regions, region_case.regions);
+    // the region fields are written to and parsed from "<time>/<region>/"
+    assert!(copy_path.join("123/metal/T").is_file());
+    let metal = TimeDir::parse_region(&copy_path.join("123"), "metal")?;
+    assert_eq!(
+        metal.field_values,
+        region_case.regions["metal"].time_directories["123"].field_values
+    );
+    let metal_temperature = &metal.field_values["T"].data;
+    assert_eq!(metal_temperature.result, FoamField::UniformScalar(300.0));
+    assert_eq!(
+        metal_temperature.patch_value("inlet"),
+        Some(&FoamField::UniformScalar(350.0))
+    );
+    assert_eq!(
+        metal_temperature.patch_value("metal_to_channel"),
+        Some(&FoamField::UniformScalar(300.0))
+    );
+    Ok(())
+}
+
+#[test]
+fn test_renumber() -> std::io::Result<()> {
+    let path = std::path::Path::new("./tests/test_cases/original/cylinder");
+    let original = Case::parse_file(path)?;
+    let mut case = original.clone();
+    let renumbering = case.renumber(&CellOrdering::ReverseCuthillMcKee)?;
+    assert!(renumbering.band_after.profile <= renumbering.band_before.profile);
+    // the velocity of each cell moves with the cell
+    let FoamField::Vector(old_u) = &original.time_directories["0"].field_values["U"].data.result
+    else {
+        panic!("U is not a vector field.");
+    };
+    let FoamField::Vector(new_u) = &case.time_directories["0"].field_values["U"].data.result else {
+        panic!("U is not a vector field.");
+    };
+    for (new, &old) in renumbering.cell_map.iter().enumerate() {
+        assert_eq!(new_u[new], old_u[old]);
+    }
+    Ok(())
+}
+
+#[test]
+fn test_compressed() -> std::io::Result<()> {
+    let path = std::path::Path::new("./tests/test_cases/original/two_zones_with_interfaces");
+    let case = Case::parse_file(path)?;
+    let copy_path = TempDir::new("compressed");
+    case.write_file_compressed(&copy_path, Compression::Gzip)?;
+    assert!(copy_path.join("constant/polyMesh/points.gz").is_file());
+    assert!(!copy_path.join("constant/polyMesh/points").exists());
+    let copy = Case::parse_file(&copy_path)?;
+    assert_eq!(copy, case);
+
+    // writing uncompressed replaces the compressed files
+    copy.write_file(&copy_path)?;
+    assert!(copy_path.join("constant/polyMesh/points").is_file());
+    assert!(!copy_path.join("constant/polyMesh/points.gz").exists());
+    assert_eq!(Case::parse_file(&copy_path)?, case);
+    Ok(())
+}
+
+#[test]
+fn test_moving_mesh() -> std::io::Result<()> {
+    let path = std::path::Path::new("./tests/test_cases/original/cylinder");
+    let mut case = Case::parse_file(path)?;
+    let mesh = case.mesh()?.clone();
+
+    // moved points at 0.5, a new topology at 1
+    let mut points = mesh.points.clone();
+    for point in points.data.iter_mut() {
+        point[0] += 1.0;
+    }
+    points.set_location(std::path::Path::new("0.5/polyMesh/points"));
+    let mut changed_mesh = mesh.subset(&[0, 1, 2], "exposed")?.mesh;
+    changed_mesh.set_directory(std::path::Path::new("1/polyMesh"));
+    for (name, time, update) in [
+        ("0.5", 0.5, MeshUpdate::Points(points.clone())),
+        ("1", 1.0, MeshUpdate::Mesh(Box::new(changed_mesh.clone()))),
+    ] {
+        let time_directory = TimeDir {
+            time,
+            field_values: Default::default(),
+            uniform: None,
+            mesh: Some(update),
+        };
+        case.time_directories
+            .insert(name.to_string(), time_directory);
+    }
+    let copy_path = TempDir::new("moving_mesh");
+    case.write_file(&copy_path)?;
+
+    let copy = Case::parse_file(&copy_path)?;
+    assert_eq!(copy.times(), vec![0.0, 0.5, 1.0]);
+    assert_eq!(*copy.mesh_at(0.0)?, mesh);
+    let moved = copy.mesh_at(0.7)?;
+    assert_eq!(moved.points.data, points.data);
+    assert_eq!(moved.faces, mesh.faces);
+    assert_eq!(*copy.mesh_at(2.0)?, changed_mesh);
+    Ok(())
+}
+
+#[test]
+fn test_map_fields() -> std::io::Result<()> {
+    let path = std::path::Path::new("./tests/test_cases/original/cylinder");
+    let case = Case::parse_file(path)?;
+    let cells = case.mesh()?.cells_where(|centre| centre[0] < 0.0);
+    let subset = case.subset(&cells, "oldInternalFaces")?;
+    let target = subset.mesh()?;
+    // the cells of the subset coincide with cells of the case, so their values are mapped exactly
+    let mapped = case.map_fields(target, 0.0, MapMethod::CellCentre)?;
+    let expected = subset.time_directory(0.0).unwrap();
+    assert!(!mapped.field_values.contains_key("phi"));
+    for (name, field) in &mapped.field_values {
+        let expected = &expected.field_values[name];
+        assert_eq!(field.data.result, expected.data.result);
+        assert_eq!(
+            field.data.patch_value("left"),
+            expected.data.patch_value("left")
+        );
+    }
+    Ok(())
+}
+
+#[test]
+fn test_repatch() -> std::io::Result<()> {
+    let path = std::path::Path::new("./tests/test_cases/original/cylinder");
+    let original = Case::parse_file(path)?;
+    let mut case = original.clone();
+    // the inlet faces below y = 1 become a separate patch
+    let inlet = original.mesh()?.boundary.data.face_range("left").unwrap();
+    let faces: Vec<usize> = original
+        .mesh()?
+        .boundary_faces_where(|centre, normal| centre[1] < 1.0 && normal[0] < -0.99)
+        .into_iter()
+        .filter(|face| inlet.contains(face))
+        .collect();
+    case.repatch(&faces, "lowerInlet")?;
+    let mesh = case.mesh()?;
+    let lower = mesh.boundary.data.face_range("lowerInlet").unwrap();
+    assert_eq!(lower.len(), faces.len());
+    assert_eq!(lower.end, mesh.n_faces());
+    for time_directory in case.time_directories.values() {
+        for field in time_directory.field_values.values() {
+            if let Some(value) = field.data.patch_value("lowerInlet") {
+                assert!(value.n_values().is_none_or(|n| n == faces.len()));
+            }
+            if let Some(value) = field.data.patch_value("left") {
+                assert!(value
+                    .n_values()
+                    .is_none_or(|n| n == inlet.len() - faces.len()));
+            }
+        }
+    }
+    // the velocity of the new patch is the velocity of the inlet
+    let u = &case.time_directories["0"].field_values["U"];
+    assert_eq!(
+        u.data.patch_value("lowerInlet"),
+        Some(&FoamField::UniformVector(vec![1.0, 0.0, 0.0]))
+    );
+
+    // a field that cannot be repatched leaves the case unchanged
+    let mut broken = original.clone();
+    let u = broken
+        .time_directories
+        .get_mut("0")
+        .and_then(|time_directory| time_directory.field_values.get_mut("U"))
+        .unwrap();
+    u.data.result = FoamField::Vector(vec![vec![0.0; 3]]);
+    let unchanged = broken.clone();
+    assert!(broken.repatch(&faces, "lowerInlet").is_err());
+    assert_eq!(broken, unchanged);
+    Ok(())
+}